@@ -0,0 +1,57 @@
+//! Compares shadow locks on many tiny increments of a few hot cells.
+//!
+//! Run with `cargo bench --bench adaptive`.
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use lockerroom::{AdaptiveLocks, LockerRoom, ShadowLocksCollection};
+
+const THREADS: usize = 8;
+const CELLS: usize = 4;
+const INCREMENTS: usize = 200_000;
+const RUNS: usize = 5;
+
+fn run<S>(locker_room: &LockerRoom<Vec<u64>, S>) -> Duration
+where
+    S: ShadowLocksCollection<Idx = usize> + Default + Send + Sync,
+{
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for offset in 0..THREADS {
+            scope.spawn(move || {
+                for i in 0..INCREMENTS {
+                    *locker_room.write_cell((i + offset) % CELLS).unwrap() += 1;
+                }
+            });
+        }
+    });
+    black_box(locker_room);
+    start.elapsed()
+}
+
+fn bench<S>(name: &str)
+where
+    S: ShadowLocksCollection<Idx = usize> + Default + Send + Sync,
+{
+    let locker_room = LockerRoom::builder(vec![0u64; CELLS])
+        .shadow_locks::<S>()
+        .build();
+    // The best run is the least disturbed by the rest of the system.
+    let best = (0..RUNS).map(|_| run(&locker_room)).min().unwrap();
+    let increments = (THREADS * INCREMENTS) as f64;
+    println!(
+        "{name:<24} {best:>10.2?} {:>8.1} ns/increment",
+        best.as_nanos() as f64 / increments
+    );
+}
+
+fn main() {
+    bench::<Vec<std::sync::RwLock<()>>>("std::sync::RwLock");
+    bench::<AdaptiveLocks<16>>("AdaptiveLocks<16>");
+    bench::<AdaptiveLocks>("AdaptiveLocks<100>");
+    bench::<AdaptiveLocks<1000>>("AdaptiveLocks<1000>");
+}
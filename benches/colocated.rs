@@ -0,0 +1,83 @@
+//! Compares random access to cells of `Colocated`, which keeps every value inside its lock, with `LockerRoom`, which
+//! keeps the values and the shadow locks apart.
+//!
+//! Run with `cargo bench --bench colocated`.
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use lockerroom::{sync::Colocated, LockerRoom};
+
+const THREADS: usize = 4;
+const ACCESSES: usize = 1_000_000;
+const RUNS: usize = 5;
+
+// Indices of the accesses, the same for both layouts.
+fn indices(cells: usize, seed: usize) -> impl Iterator<Item = usize> {
+    let mut state = seed as u64 * 2 + 1;
+    (0..ACCESSES).map(move |_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % cells
+    })
+}
+
+fn best(mut run: impl FnMut()) -> Duration {
+    // The best run is the least disturbed by the rest of the system.
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn bench(cells: usize) {
+    let locker_room: LockerRoom<_> = vec![0u64; cells].into();
+    let split = best(|| {
+        thread::scope(|scope| {
+            for seed in 0..THREADS {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for index in indices(cells, seed) {
+                        *locker_room.write_cell(index).unwrap() += 1;
+                        black_box(*locker_room.read_cell(index).unwrap());
+                    }
+                });
+            }
+        });
+    });
+    let colocated: Colocated<_> = vec![0u64; cells].into();
+    let together = best(|| {
+        thread::scope(|scope| {
+            for seed in 0..THREADS {
+                let colocated = &colocated;
+                scope.spawn(move || {
+                    for index in indices(cells, seed) {
+                        *colocated.write_cell(index).unwrap() += 1;
+                        black_box(*colocated.read_cell(index).unwrap());
+                    }
+                });
+            }
+        });
+    });
+    let accesses = (THREADS * ACCESSES) as f64;
+    println!(
+        "{cells:>10} cells: split {:>8.1} ns/access, colocated {:>8.1} ns/access",
+        split.as_nanos() as f64 / accesses,
+        together.as_nanos() as f64 / accesses,
+    );
+}
+
+fn main() {
+    // From fitting in the cache to far beyond it.
+    for cells in [1_000, 100_000, 10_000_000] {
+        bench(cells);
+    }
+}
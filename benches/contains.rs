@@ -0,0 +1,62 @@
+//! Compares membership checks by the shadow locks with the lookup of cells.
+//!
+//! Run with `cargo bench --bench contains`.
+
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use lockerroom::{Collection, LockerRoom};
+
+const CELLS: usize = 10_000;
+const RUNS: usize = 5;
+
+fn best(mut run: impl FnMut()) -> Duration {
+    // The best run is the least disturbed by the rest of the system.
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn bench<T>(name: &str, locker_room: LockerRoom<T>)
+where
+    T: Collection<Idx = usize>,
+{
+    // Half of the indices are missing.
+    let indices = 0..CELLS * 2;
+    let contains = best(|| {
+        locker_room.read_room_scoped(|room| {
+            for index in indices.clone() {
+                black_box(room.contains(index));
+            }
+        })
+    });
+    let read_cell = best(|| {
+        locker_room.read_room_scoped(|room| {
+            for index in indices.clone() {
+                black_box(room.read_cell(index).is_some());
+            }
+        })
+    });
+    let checks = (CELLS * 2) as f64;
+    println!(
+        "{name:<8} contains {:>6.1} ns/check, read_cell {:>6.1} ns/check",
+        contains.as_nanos() as f64 / checks,
+        read_cell.as_nanos() as f64 / checks,
+    );
+}
+
+fn main() {
+    bench(
+        "HashMap",
+        HashMap::<usize, usize>::from_iter((0..CELLS).map(|index| (index * 2, index))).into(),
+    );
+    bench("Vec", LockerRoom::from((0..CELLS).collect::<Vec<_>>()));
+}
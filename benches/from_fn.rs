@@ -0,0 +1,49 @@
+//! Compares `LockerRoom::from_fn`, which collects the values and then creates their shadow locks, with creating
+//! the values and the locks in a single pass.
+//!
+//! Run with `cargo bench --bench from_fn`.
+
+use std::{
+    hint::black_box,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use lockerroom::LockerRoom;
+
+const RUNS: usize = 20;
+
+fn best(mut run: impl FnMut()) -> Duration {
+    // The best run is the least disturbed by the rest of the system.
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn single_pass(len: usize, f: impl FnMut(usize) -> u64) -> LockerRoom<Vec<u64>> {
+    let mut f = f;
+    let (values, locks): (Vec<_>, Vec<RwLock<()>>) =
+        (0..len).map(|i| (f(i), RwLock::default())).unzip();
+    LockerRoom::from_parts(values, locks)
+}
+
+fn bench(len: usize) {
+    let two_passes = best(|| {
+        black_box(LockerRoom::from_fn(len, |i| i as u64 * 3));
+    });
+    let single_pass = best(|| {
+        black_box(single_pass(len, |i| i as u64 * 3));
+    });
+    println!("{len:>10} cells: two passes {two_passes:>10.2?}, single pass {single_pass:>10.2?}");
+}
+
+fn main() {
+    for len in [1_000, 100_000, 10_000_000] {
+        bench(len);
+    }
+}
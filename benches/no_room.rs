@@ -0,0 +1,79 @@
+//! Compares locking of cells of `LockerRoom` with and without the lock of whole collection, i.e. with `ROOM` set to
+//! `true` and to `false`.
+//!
+//! Every thread locks cells of its own, so the threads contend only for the lock of whole collection, which every
+//! cell guard of `LockerRoom<T>` takes with shared access.
+//!
+//! Run with `cargo bench --bench no_room`.
+
+use std::{
+    hint::black_box,
+    thread,
+    time::{Duration, Instant},
+};
+
+use lockerroom::LockerRoom;
+
+const CELLS_PER_THREAD: usize = 64;
+const ACCESSES: usize = 1_000_000;
+const RUNS: usize = 5;
+
+fn best(mut run: impl FnMut()) -> Duration {
+    // The best run is the least disturbed by the rest of the system.
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// Cells of the thread, which no other thread locks.
+fn indices(thread: usize) -> impl Iterator<Item = usize> {
+    (0..ACCESSES).map(move |access| thread * CELLS_PER_THREAD + access % CELLS_PER_THREAD)
+}
+
+fn bench(threads: usize) {
+    let locker_room: LockerRoom<_> = vec![0u64; threads * CELLS_PER_THREAD].into();
+    let room = best(|| {
+        thread::scope(|scope| {
+            for thread in 0..threads {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for index in indices(thread) {
+                        *locker_room.write_cell(index).unwrap() += 1;
+                        black_box(*locker_room.read_cell(index).unwrap());
+                    }
+                });
+            }
+        });
+    });
+    let locker_room: LockerRoom<_, _, false> = locker_room.into();
+    let no_room = best(|| {
+        thread::scope(|scope| {
+            for thread in 0..threads {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for index in indices(thread) {
+                        *locker_room.write_cell(index).unwrap() += 1;
+                        black_box(*locker_room.read_cell(index).unwrap());
+                    }
+                });
+            }
+        });
+    });
+    let accesses = (threads * ACCESSES) as f64;
+    println!(
+        "{threads:>2} threads: room {:>6.1} ns/access, no room {:>6.1} ns/access",
+        room.as_nanos() as f64 / accesses,
+        no_room.as_nanos() as f64 / accesses,
+    );
+}
+
+fn main() {
+    for threads in [1, 2, 4, 8] {
+        bench(threads);
+    }
+}
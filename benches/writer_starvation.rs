@@ -0,0 +1,67 @@
+//! Measures how long writers of a cell wait under heavy read load with shadow locks of different fairness policies.
+//!
+//! Run with `cargo bench --bench writer_starvation --features parking_lot,spin`. Without the features only
+//! `std::sync::RwLock` is measured.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Barrier,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use lockerroom::{LockerRoom, ShadowLocksCollection};
+
+const READERS: usize = 8;
+const WRITES: usize = 100;
+const RUNS: usize = 5;
+
+// Returns the longest and the mean time a writer waited for the cell while readers kept locking it.
+fn run<S>(locker_room: &LockerRoom<Vec<usize>, S>) -> (Duration, Duration)
+where
+    S: ShadowLocksCollection<Idx = usize> + Send + Sync,
+{
+    let stop = AtomicBool::new(false);
+    // Writes start once every reader is running.
+    let barrier = Barrier::new(READERS + 1);
+    let mut waits = Vec::with_capacity(WRITES);
+    thread::scope(|scope| {
+        for _ in 0..READERS {
+            scope.spawn(|| {
+                barrier.wait();
+                while !stop.load(Ordering::Relaxed) {
+                    let _guard = locker_room.read_cell(0).unwrap();
+                }
+            });
+        }
+        barrier.wait();
+        for _ in 0..WRITES {
+            let start = Instant::now();
+            *locker_room.write_cell(0).unwrap() += 1;
+            waits.push(start.elapsed());
+        }
+        stop.store(true, Ordering::Relaxed);
+    });
+    let max = waits.iter().max().copied().unwrap();
+    (max, waits.iter().sum::<Duration>() / WRITES as u32)
+}
+
+fn bench<S>(name: &str)
+where
+    S: ShadowLocksCollection<Idx = usize> + Default + Send + Sync,
+{
+    let locker_room = LockerRoom::builder(vec![0]).shadow_locks::<S>().build();
+    // The best run is the least disturbed by the rest of the system.
+    let (max, mean) = (0..RUNS).map(|_| run(&locker_room)).min().unwrap();
+    println!("{name:<24} max writer wait {max:>10.2?}, mean {mean:>10.2?}");
+}
+
+fn main() {
+    bench::<Vec<std::sync::RwLock<()>>>("std::sync::RwLock");
+    #[cfg(feature = "parking_lot")]
+    bench::<Vec<parking_lot::RwLock<()>>>("parking_lot::RwLock");
+    #[cfg(feature = "spin")]
+    bench::<Vec<spin::RwLock<()>>>("spin::RwLock");
+}
@@ -0,0 +1,168 @@
+//! This module describes readers-writer locks which spin for a while before they park the thread.
+
+use std::borrow::Borrow;
+
+use crate::{
+    primitive::{self, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    LockState, ShadowLock, ShadowLocksCollection,
+};
+
+/// Readers-writer lock which retries [`try_read`](ShadowLock::try_read) or [`try_write`](ShadowLock::try_write)
+/// a number of times with [`std::hint::spin_loop`] before it parks the thread on [`std::sync::RwLock`].
+///
+/// When the lock is held for a few instructions, e.g. to increment a counter, it's usually released sooner than a
+/// parked thread would wake up, so spinning saves the context switches. But a thread spinning on a lock held for
+/// long only burns the CPU which the holder might need, and then parks anyway, so for long critical sections this
+/// lock is slower than plain `RwLock`. Unlike [`spin::RwLock`](https://docs.rs/spin/latest/spin/type.RwLock.html)
+/// it never spins unboundedly, so waiting for a long critical section stays cheap.
+///
+/// Shadow locks are created with [`Default`], which spins `SPINS` times, so the number of spins may be a parameter
+/// of the type:
+/// ```
+/// # use std::{sync::Arc, thread};
+/// # use lockerroom::{AdaptiveLocks, LockerRoom};
+/// let locker_room = Arc::new(
+///     LockerRoom::builder(vec![0; 4])
+///         .shadow_locks::<AdaptiveLocks<64>>()
+///         .build(),
+/// );
+/// thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| {
+///             for i in 0..100 {
+///                 *locker_room.write_cell(i % 4).unwrap() += 1;
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(vec![100; 4], Arc::into_inner(locker_room).unwrap().into_inner());
+/// ```
+/// Or it's chosen at runtime with [`adaptive`](crate::sync::LockerRoomBuilder::adaptive) of the builder, e.g. after
+/// measuring the machine, which creates the locks with [`AdaptiveLocks::with_spins`].
+pub struct AdaptiveLock<const SPINS: u32 = 100> {
+    lock: RwLock<()>,
+    spins: u32,
+}
+
+impl<const SPINS: u32> AdaptiveLock<SPINS> {
+    /// Creates an unlocked lock which spins `spins` times instead of `SPINS`.
+    pub fn with_spins(spins: u32) -> Self {
+        Self {
+            lock: RwLock::default(),
+            spins,
+        }
+    }
+
+    /// Returns the number of attempts before the thread parks.
+    pub fn spins(&self) -> u32 {
+        self.spins
+    }
+
+    // Retries the attempt `spins` times before giving up.
+    fn spin<'a, G>(&'a self, attempt: impl Fn(&'a RwLock<()>) -> Option<G>) -> Option<G> {
+        for _ in 0..self.spins {
+            if let Some(guard) = attempt(&self.lock) {
+                return Some(guard);
+            }
+            primitive::spin_loop();
+        }
+        None
+    }
+}
+
+impl<const SPINS: u32> Default for AdaptiveLock<SPINS> {
+    fn default() -> Self {
+        Self::with_spins(SPINS)
+    }
+}
+
+/// Shadow locks of index-based collections made of [`AdaptiveLock`], which all spin the same number of times.
+pub struct AdaptiveLocks<const SPINS: u32 = 100> {
+    spins: u32,
+    locks: Vec<AdaptiveLock<SPINS>>,
+}
+
+impl<const SPINS: u32> AdaptiveLocks<SPINS> {
+    /// Creates locks for no cells which spin `spins` times instead of `SPINS`.
+    /// ```
+    /// # use lockerroom::{AdaptiveLocks, ShadowLocksCollection};
+    /// let mut locks: AdaptiveLocks = AdaptiveLocks::with_spins(16);
+    /// locks.update_indices(0..2);
+    /// assert_eq!(16, locks.index(1).unwrap().spins());
+    /// ```
+    pub fn with_spins(spins: u32) -> Self {
+        Self {
+            spins,
+            locks: Vec::new(),
+        }
+    }
+
+    /// Returns the number of attempts before a thread parks on any of the locks.
+    pub fn spins(&self) -> u32 {
+        self.spins
+    }
+}
+
+impl<const SPINS: u32> Default for AdaptiveLocks<SPINS> {
+    fn default() -> Self {
+        Self::with_spins(SPINS)
+    }
+}
+
+impl<const SPINS: u32> ShadowLocksCollection for AdaptiveLocks<SPINS> {
+    type Idx = usize;
+    type Lock = AdaptiveLock<SPINS>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
+        self.locks.get(*index.borrow())
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        let spins = self.spins;
+        self.locks
+            .resize_with(indices.count(), || AdaptiveLock::with_spins(spins));
+    }
+
+    fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.locks.shrink_to_fit();
+    }
+}
+
+impl<const SPINS: u32> ShadowLock for AdaptiveLock<SPINS> {
+    type ReadGuard<'a> = RwLockReadGuard<'a, ()>;
+    type WriteGuard<'a> = RwLockWriteGuard<'a, ()>;
+
+    fn read(&self) -> Self::ReadGuard<'_> {
+        self.spin(ShadowLock::try_read)
+            .unwrap_or_else(|| ShadowLock::read(&self.lock))
+    }
+
+    fn write(&self) -> Self::WriteGuard<'_> {
+        self.spin(ShadowLock::try_write)
+            .unwrap_or_else(|| ShadowLock::write(&self.lock))
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        ShadowLock::try_read(&self.lock)
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        ShadowLock::try_write(&self.lock)
+    }
+
+    fn downgrade<'a>(guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+        <RwLock<()> as ShadowLock>::downgrade(guard)
+    }
+
+    fn clear_poison(&self) {
+        ShadowLock::clear_poison(&self.lock);
+    }
+
+    fn state(&self) -> LockState {
+        ShadowLock::state(&self.lock)
+    }
+}
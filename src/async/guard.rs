@@ -1,25 +1,67 @@
 //! Guards for different locking types.
 
-use std::ops::{Deref, DerefMut};
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::VecDeque,
+    fmt,
+    hash::{Hash, Hasher},
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr,
+};
 
-use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use crate::{Collection, ShadowLocksCollectionAsync};
+use crate::{
+    order, release::OrderedGuards, CanonicalOrder, Collection, ShadowLocksCollectionAsync,
+};
+
+// Makes a cell lock which lets at most `reader_cap` readers in at once, if there is a cap.
+pub(crate) fn new_cell_lock(reader_cap: Option<u32>) -> RwLock<()> {
+    match reader_cap {
+        Some(cap) => RwLock::with_max_readers((), cap),
+        None => RwLock::new(()),
+    }
+}
+
+// Locked cells of multi-cell guards with their guards, sorted by index.
+type ReadCells<'a, T> = Vec<(
+    <T as Collection>::Idx,
+    &'a <T as Collection>::Output,
+    RwLockReadGuard<'a, ()>,
+)>;
+type WriteCells<'a, T> = Vec<(
+    <T as Collection>::Idx,
+    &'a mut <T as Collection>::Output,
+    RwLockWriteGuard<'a, ()>,
+)>;
 
 /// RAII structure used to release the shared read access of a cell lock when dropped.
 ///
 /// This structure is created by the [`read_cell`](crate::LockerRoomAsync::read_cell) methods on [`LockerRoomAsync`](crate::LockerRoomAsync).
+///
+/// It's formatted, hashed and compared like the value of the cell, as is [`WriteCellGuard`]:
+/// ```
+/// # use lockerroom::LockerRoomAsync;
+/// # tokio_test::block_on(async {
+/// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+/// let (a, b) = (locker_room.read_cell(0).await.unwrap(), locker_room.read_cell(1).await.unwrap());
+/// assert_eq!("1 < 2", format!("{a} < {b}"));
+/// assert!(a < b);
+/// # });
+/// ```
+#[must_use = "locking has no effect unless the guard is used"]
 pub struct ReadCellGuard<'a, T>
 where
     T: Collection,
 {
     value: &'a T::Output,
-    // For dropping and, after that, unlocking.
-    #[allow(dead_code)]
-    cell_rwlock_read_guard: RwLockReadGuard<'a, ()>,
-    // For dropping and, after that, unlocking. But it stands after cell guard because of order of dropping.
+    cell_rwlock: &'a RwLock<()>,
+    global_rwlock: &'a RwLock<()>,
+    // Releases the cell lock and, after it, the lock of whole collection.
     #[allow(dead_code)]
-    global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+    guards: OrderedGuards<RwLockReadGuard<'a, ()>, RwLockReadGuard<'a, ()>>,
 }
 
 impl<'a, T> ReadCellGuard<'a, T>
@@ -28,17 +70,113 @@ where
 {
     pub(crate) fn new(
         value: &'a T::Output,
-        global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
-        cell_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+        (global_rwlock, global_rwlock_read_guard): (&'a RwLock<()>, RwLockReadGuard<'a, ()>),
+        (cell_rwlock, cell_rwlock_read_guard): (&'a RwLock<()>, RwLockReadGuard<'a, ()>),
     ) -> Self {
         Self {
             value,
-            global_rwlock_read_guard,
-            cell_rwlock_read_guard,
+            cell_rwlock,
+            global_rwlock,
+            guards: OrderedGuards::new(cell_rwlock_read_guard, global_rwlock_read_guard),
+        }
+    }
+
+    /// Makes an independent guard of the same cell, locking the cell and whole collection with shared read access once more.
+    ///
+    /// The locks are acquired without waiting: the current task already holds them, and [`RwLock`] is fair,
+    /// so waiting behind a queued writer, which in turn waits for `orig` to be dropped, would deadlock.
+    /// This function returns `None` if a writer is queued on one of the locks.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::clone_guard(&guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoomAsync, r#async::ReadCellGuard};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+    /// let guard = locker_room.read_cell(1).await.unwrap();
+    /// let cloned = ReadCellGuard::clone_guard(&guard).unwrap();
+    /// drop(guard);
+    /// assert_eq!(1, *cloned);
+    /// # });
+    /// ```
+    pub fn clone_guard(orig: &Self) -> Option<Self> {
+        let global_rwlock_read_guard = orig.global_rwlock.try_read().ok()?;
+        let cell_rwlock_read_guard = orig.cell_rwlock.try_read().ok()?;
+        Some(Self {
+            value: orig.value,
+            cell_rwlock: orig.cell_rwlock,
+            global_rwlock: orig.global_rwlock,
+            guards: OrderedGuards::new(cell_rwlock_read_guard, global_rwlock_read_guard),
+        })
+    }
+
+    /// Makes a guard for a component of the cell value, e.g. `str` of a `String`. The cell stays locked with
+    /// shared read access until the new guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoomAsync, r#async::ReadCellGuard};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![String::from("hello")].into();
+    /// let guard = ReadCellGuard::map(locker_room.read_cell(0).await.unwrap(), String::as_str);
+    /// assert_eq!("hello", &*guard);
+    /// # });
+    /// ```
+    pub fn map<U>(orig: Self, f: impl FnOnce(&T::Output) -> &U) -> MappedReadCellGuard<'a, U>
+    where
+        U: ?Sized,
+    {
+        MappedReadCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+
+    /// Attempts to make a guard for a component of the cell value. The original guard is given back if `f` returns `None`.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::try_map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    pub fn try_map<U>(
+        orig: Self,
+        f: impl FnOnce(&T::Output) -> Option<&U>,
+    ) -> Result<MappedReadCellGuard<'a, U>, Self>
+    where
+        U: ?Sized,
+    {
+        match f(orig.value) {
+            Some(value) => Ok(MappedReadCellGuard {
+                value,
+                guards: orig.guards,
+            }),
+            None => Err(orig),
         }
     }
 }
 
+impl<'a, T, V> ReadCellGuard<'a, T>
+where
+    T: Collection<Output = Option<V>>,
+{
+    /// Makes a guard for the value inside the cell's `Option`, or releases the cell and returns `None` if the cell
+    /// holds `None`.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::as_deref(guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoomAsync, r#async::ReadCellGuard};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<Vec<Option<i32>>> = vec![Some(1), None].into();
+    /// let guard = ReadCellGuard::as_deref(locker_room.read_cell(0).await.unwrap()).unwrap();
+    /// assert_eq!(1, *guard);
+    /// assert!(ReadCellGuard::as_deref(locker_room.read_cell(1).await.unwrap()).is_none());
+    /// # });
+    /// ```
+    pub fn as_deref(orig: Self) -> Option<MappedReadCellGuard<'a, V>> {
+        Self::try_map(orig, Option::as_ref).ok()
+    }
+}
+
 impl<'a, T> Deref for ReadCellGuard<'a, T>
 where
     T: Collection,
@@ -50,20 +188,65 @@ where
     }
 }
 
+impl<'a, T> fmt::Display for ReadCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<'a, T> Hash for ReadCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<'a, T> PartialEq for ReadCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<'a, T> Eq for ReadCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: Eq,
+{
+}
+
+impl<'a, T> PartialOrd for ReadCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
 /// RAII structure used to release the exclusive write access of a cell lock when dropped.
 ///
 /// This structure is created by the [`write_cell`](crate::LockerRoomAsync::write_cell) methods on [`LockerRoomAsync`](crate::LockerRoomAsync).
+#[must_use = "locking has no effect unless the guard is used"]
 pub struct WriteCellGuard<'a, T>
 where
     T: Collection,
 {
     value: &'a mut T::Output,
-    // For dropping and, after that, unlocking.
-    #[allow(dead_code)]
-    cell_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
-    // For dropping and, after that, unlocking. But it stands after cell guard because of order of dropping.
+    // Releases the cell lock and, after it, the lock of whole collection.
     #[allow(dead_code)]
-    global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+    guards: OrderedGuards<RwLockWriteGuard<'a, ()>, RwLockReadGuard<'a, ()>>,
 }
 
 impl<'a, T> WriteCellGuard<'a, T>
@@ -77,10 +260,82 @@ where
     ) -> Self {
         Self {
             value,
-            global_rwlock_read_guard,
-            cell_rwlock_write_guard,
+            guards: OrderedGuards::new(cell_rwlock_write_guard, global_rwlock_read_guard),
         }
     }
+
+    /// Makes a guard for a component of the cell value, e.g. `str` of a `String`. The cell stays locked with
+    /// exclusive write access until the new guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoomAsync, r#async::WriteCellGuard};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![String::from("hello")].into();
+    /// let mut guard = WriteCellGuard::map(locker_room.write_cell(0).await.unwrap(), String::as_mut_str);
+    /// guard.make_ascii_uppercase();
+    /// drop(guard);
+    /// assert_eq!("HELLO", *locker_room.read_cell(0).await.unwrap());
+    /// # });
+    /// ```
+    pub fn map<U>(
+        orig: Self,
+        f: impl FnOnce(&mut T::Output) -> &mut U,
+    ) -> MappedWriteCellGuard<'a, U>
+    where
+        U: ?Sized,
+    {
+        MappedWriteCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+
+    /// Attempts to make a guard for a component of the cell value. The original guard is given back if `f` returns `None`.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::try_map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    pub fn try_map<U>(
+        orig: Self,
+        f: impl FnOnce(&mut T::Output) -> Option<&mut U>,
+    ) -> Result<MappedWriteCellGuard<'a, U>, Self>
+    where
+        U: ?Sized,
+    {
+        // The reference is reborrowed through a raw pointer, so `orig` can be given back if `f` returns `None`.
+        let value: *mut T::Output = orig.value;
+        match f(unsafe { &mut *value }) {
+            Some(value) => Ok(MappedWriteCellGuard {
+                value,
+                guards: orig.guards,
+            }),
+            None => Err(orig),
+        }
+    }
+}
+
+impl<'a, T, V> WriteCellGuard<'a, T>
+where
+    T: Collection<Output = Option<V>>,
+{
+    /// Makes a guard for the value inside the cell's `Option`, or releases the cell and returns `None` if the cell
+    /// holds `None`.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::as_deref_mut(guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoomAsync, r#async::WriteCellGuard};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<Vec<Option<i32>>> = vec![Some(1), None].into();
+    /// *WriteCellGuard::as_deref_mut(locker_room.write_cell(0).await.unwrap()).unwrap() += 1;
+    /// assert!(WriteCellGuard::as_deref_mut(locker_room.write_cell(1).await.unwrap()).is_none());
+    /// assert_eq!(vec![Some(2), None], locker_room.into_inner());
+    /// # });
+    /// ```
+    pub fn as_deref_mut(orig: Self) -> Option<MappedWriteCellGuard<'a, V>> {
+        Self::try_map(orig, Option::as_mut).ok()
+    }
 }
 
 impl<'a, T> Deref for WriteCellGuard<'a, T>
@@ -103,9 +358,150 @@ where
     }
 }
 
+impl<'a, T> fmt::Display for WriteCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<'a, T> Hash for WriteCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<'a, T> PartialEq for WriteCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<'a, T> Eq for WriteCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: Eq,
+{
+}
+
+impl<'a, T> PartialOrd for WriteCellGuard<'a, T>
+where
+    T: Collection,
+    T::Output: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+/// RAII structure used to release the shared read access of a cell lock when dropped, which gives access
+/// to a component of the cell value.
+///
+/// This structure is created by [`ReadCellGuard::map`] and [`ReadCellGuard::try_map`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MappedReadCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    value: &'a U,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<RwLockReadGuard<'a, ()>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<'a, U> MappedReadCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    /// Makes a guard for a component of the value, like [`ReadCellGuard::map`] does.
+    pub fn map<V>(orig: Self, f: impl FnOnce(&U) -> &V) -> MappedReadCellGuard<'a, V>
+    where
+        V: ?Sized,
+    {
+        MappedReadCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+}
+
+impl<'a, U> Deref for MappedReadCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a cell lock when dropped, which gives access
+/// to a component of the cell value.
+///
+/// This structure is created by [`WriteCellGuard::map`] and [`WriteCellGuard::try_map`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MappedWriteCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    value: &'a mut U,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<RwLockWriteGuard<'a, ()>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<'a, U> MappedWriteCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    /// Makes a guard for a component of the value, like [`WriteCellGuard::map`] does.
+    pub fn map<V>(orig: Self, f: impl FnOnce(&mut U) -> &mut V) -> MappedWriteCellGuard<'a, V>
+    where
+        V: ?Sized,
+    {
+        MappedWriteCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+}
+
+impl<'a, U> Deref for MappedWriteCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, U> DerefMut for MappedWriteCellGuard<'a, U>
+where
+    U: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
 /// RAII structure used to release the exclusive write access of a whole collection lock when dropped.
 ///
 /// This structure is created by the [`lock_room`](crate::LockerRoomAsync::lock_room) methods on [`LockerRoomAsync`](crate::LockerRoomAsync).
+#[must_use = "locking has no effect unless the guard is used"]
 pub struct RoomGuard<'a, T>
 where
     T: Collection,
@@ -114,6 +510,8 @@ where
     index_locks: &'a mut T::ShadowLocksAsync,
     #[allow(dead_code)]
     global_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
+    // Put on the locks of inserted cells.
+    reader_cap: Option<u32>,
 }
 
 impl<'a, T> RoomGuard<'a, T>
@@ -124,11 +522,211 @@ where
         collection: &'a mut T,
         index_locks: &'a mut T::ShadowLocksAsync,
         global_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
+        reader_cap: Option<u32>,
     ) -> Self {
         Self {
             collection,
             index_locks,
             global_rwlock_write_guard,
+            reader_cap,
+        }
+    }
+
+    /// Returns a reference to the cell at the index through the held lock of whole collection, locking nothing.
+    ///
+    /// Awaiting [`read_cell`](crate::LockerRoomAsync::read_cell) while holding the guard would never complete.
+    /// This function will return `None` if there is no cell with such index.
+    pub fn read_cell(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        self.collection.index(index)
+    }
+
+    /// Peeks at the cell at the index like [`read_cell`](Self::read_cell) does, for those who look for this name.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+    /// let room = locker_room.lock_room().await;
+    /// assert_eq!(Some(&2), room.peek(1));
+    /// # });
+    /// ```
+    pub fn peek(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        self.read_cell(index)
+    }
+
+    /// Returns a mutable reference to the cell at the index through the held lock of whole collection, locking
+    /// nothing.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    pub fn write_cell_mut(&mut self, index: impl Borrow<T::Idx>) -> Option<&mut T::Output> {
+        self.collection.index_mut(index)
+    }
+
+    /// Updates the shadow locks to the indices of the collection right away, like dropping the guard does.
+    ///
+    /// No cell can be locked while the guard is alive, and dropping the guard updates the locks once more,
+    /// so this only helps when the locks have to follow the collection while it stays locked.
+    pub fn sync_locks(&mut self) {
+        let reader_cap = self.reader_cap;
+        self.index_locks
+            .update_indices_with(self.collection.indices(), || new_cell_lock(reader_cap));
+    }
+
+    /// Takes the fields out of the guard, updating the shadow locks like dropping it does.
+    fn into_locked(self) -> (&'a mut T, &'a T::ShadowLocksAsync, RwLockWriteGuard<'a, ()>) {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped after its fields are moved out.
+        let (collection, index_locks, global_rwlock_write_guard) = unsafe {
+            (
+                ptr::read(&this.collection),
+                ptr::read(&this.index_locks),
+                ptr::read(&this.global_rwlock_write_guard),
+            )
+        };
+        let reader_cap = this.reader_cap;
+        index_locks.update_indices_with(collection.indices(), || new_cell_lock(reader_cap));
+        (collection, index_locks, global_rwlock_write_guard)
+    }
+
+    /// Atomically turns the guard into exclusive write access to the cells at the indices, so no other task can
+    /// lock them or whole collection in between. Other cells become available to other tasks.
+    ///
+    /// It suits "resize, then initialize the new cells" workflows. Repeated indices are locked once.
+    ///
+    /// This is an associated function that needs to be used as `RoomGuard::into_cells(guard, ...)`,
+    /// so it doesn't conflict with a method on the collection.
+    ///
+    /// This function will return `None`, releasing all the locks, if there is no cell with one of the indices.
+    /// ```
+    /// # use lockerroom::{LockerRoomAsync, r#async::RoomGuard};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+    /// let mut room = locker_room.lock_room().await;
+    /// room.resize(4, 0);
+    /// let mut cells = RoomGuard::into_cells(room, [2, 3]).unwrap();
+    /// // Cells 0 and 1 are available again, but the new ones are not yet.
+    /// assert_eq!(Some(1), locker_room.get_cell(0).await);
+    /// cells.iter_mut().for_each(|(index, value)| *value = *index);
+    /// # });
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn into_cells(
+        orig: Self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> Option<MultiWriteGuard<'a, T>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let (collection, index_locks, global_rwlock_write_guard) = orig.into_locked();
+        let collection: *mut T = collection;
+        let mut cells = Vec::with_capacity(indices.len());
+        for index in indices {
+            // Nobody holds cell locks while whole collection is locked, so locking the cell never fails.
+            let cell_rwlock_write_guard = index_locks.index(&index)?.try_write().ok()?;
+            // SAFETY: the indices are distinct, so every cell is borrowed mutably only once.
+            let value = unsafe { &mut *collection }.index_mut(&index)?;
+            cells.push((index, value, cell_rwlock_write_guard));
+        }
+        let global_rwlock_read_guard = global_rwlock_write_guard.downgrade();
+        Some(MultiWriteGuard::new(cells, global_rwlock_read_guard))
+    }
+
+    /// Atomically turns the guard into exclusive write access to the cell at the index,
+    /// so no other task can lock the cell or whole collection in between.
+    pub(crate) fn into_write_cell(self, index: &T::Idx) -> Option<WriteCellGuard<'a, T>> {
+        let (collection, index_locks, global_rwlock_write_guard) = self.into_locked();
+        // Nobody holds cell locks while whole collection is locked, so locking the cell never fails.
+        let cell_rwlock_write_guard = index_locks.index(index)?.try_write().ok()?;
+        let global_rwlock_read_guard = global_rwlock_write_guard.downgrade();
+        let value = collection.index_mut(index)?;
+        Some(WriteCellGuard::new(
+            value,
+            global_rwlock_read_guard,
+            cell_rwlock_write_guard,
+        ))
+    }
+}
+
+/// Ends of a [`VecDeque`] are changed together with its shadow locks, so every lock stays with its cell when
+/// the front of the deque shifts indices of the cells, like in [`sync::RoomGuard`](crate::sync::RoomGuard).
+impl<V> RoomGuard<'_, VecDeque<V>> {
+    /// Prepends a cell to the deque together with its lock.
+    pub fn push_front(&mut self, value: V) {
+        self.align_locks();
+        self.collection.push_front(value);
+        self.index_locks.push_front(RwLock::new(()));
+    }
+
+    /// Appends a cell to the deque together with its lock.
+    pub fn push_back(&mut self, value: V) {
+        self.align_locks();
+        self.collection.push_back(value);
+        self.index_locks.push_back(RwLock::new(()));
+    }
+
+    /// Removes the first cell of the deque together with its lock and returns its value, or `None` if the deque is empty.
+    pub fn pop_front(&mut self) -> Option<V> {
+        self.align_locks();
+        let value = self.collection.pop_front()?;
+        self.index_locks.pop_front();
+        Some(value)
+    }
+
+    /// Removes the last cell of the deque together with its lock and returns its value, or `None` if the deque is empty.
+    pub fn pop_back(&mut self) -> Option<V> {
+        self.align_locks();
+        let value = self.collection.pop_back()?;
+        self.index_locks.pop_back();
+        Some(value)
+    }
+
+    /// Inserts a cell at the index together with its lock, shifting the cells after it to the back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length of the deque, like [`VecDeque::insert`].
+    pub fn insert(&mut self, index: usize, value: V) {
+        self.align_locks();
+        self.collection.insert(index, value);
+        self.index_locks.insert(index, RwLock::new(()));
+    }
+
+    /// Removes the cell at the index together with its lock and returns its value, or `None` if there is no cell
+    /// with such index. The cells after it are shifted to the front.
+    pub fn remove(&mut self, index: usize) -> Option<V> {
+        self.align_locks();
+        let value = self.collection.remove(index)?;
+        self.index_locks.remove(index);
+        Some(value)
+    }
+
+    /// Rotates the cells together with their locks `n` places to the front, like [`VecDeque::rotate_left`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the deque.
+    pub fn rotate_left(&mut self, n: usize) {
+        self.align_locks();
+        self.collection.rotate_left(n);
+        self.index_locks.rotate_left(n);
+    }
+
+    /// Rotates the cells together with their locks `n` places to the back, like [`VecDeque::rotate_right`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the deque.
+    pub fn rotate_right(&mut self, n: usize) {
+        self.align_locks();
+        self.collection.rotate_right(n);
+        self.index_locks.rotate_right(n);
+    }
+
+    // Cells changed through `DerefMut` may have left the shadow locks behind, and then they only follow the length.
+    fn align_locks(&mut self) {
+        if self.index_locks.len() != self.collection.len() {
+            self.sync_locks();
         }
     }
 }
@@ -158,6 +756,140 @@ where
     T: Collection,
 {
     fn drop(&mut self) {
-        self.index_locks.update_indices(self.collection.indices());
+        self.sync_locks();
+    }
+}
+
+/// RAII structure used to release the shared read access of several cell locks when dropped.
+///
+/// Unlike [`ReadCellGuard`] it remembers the index of every locked cell.
+///
+/// This structure is created by the [`read_cells`](crate::LockerRoomAsync::read_cells) methods on [`LockerRoomAsync`](crate::LockerRoomAsync).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MultiReadGuard<'a, T>
+where
+    T: Collection,
+{
+    // Released before the lock of whole collection.
+    guards: OrderedGuards<ReadCells<'a, T>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<'a, T> MultiReadGuard<'a, T>
+where
+    T: Collection,
+    T::Idx: CanonicalOrder,
+{
+    pub(crate) fn new(
+        cells: ReadCells<'a, T>,
+        global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+    ) -> Self {
+        Self {
+            guards: OrderedGuards::new(cells, global_rwlock_read_guard),
+        }
+    }
+
+    /// Returns a reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        let position = order::position(self.guards.cells(), index.borrow(), |(idx, _, _)| idx)?;
+        Some(self.guards.cells()[position].1)
+    }
+
+    /// An iterator visiting all locked cells with their indices in [`CanonicalOrder`](crate::CanonicalOrder) of indices.
+    pub fn iter(&self) -> impl Iterator<Item = (&T::Idx, &T::Output)> {
+        self.guards
+            .cells()
+            .iter()
+            .map(|(idx, value, _)| (idx, &**value))
+    }
+
+    /// An iterator visiting the indices of all locked cells in [`CanonicalOrder`](crate::CanonicalOrder).
+    pub fn indices(&self) -> impl Iterator<Item = &T::Idx> + use<'_, 'a, T> {
+        self.guards.cells().iter().map(|(idx, _, _)| idx)
+    }
+
+    /// Returns the number of locked cells.
+    pub fn len(&self) -> usize {
+        self.guards.cells().len()
+    }
+
+    /// Returns `true` if the guard locks no cells.
+    pub fn is_empty(&self) -> bool {
+        self.guards.cells().is_empty()
+    }
+}
+
+/// RAII structure used to release the exclusive write access of several cell locks when dropped.
+///
+/// Unlike [`WriteCellGuard`] it remembers the index of every locked cell.
+///
+/// This structure is created by the [`write_cells`](crate::LockerRoomAsync::write_cells) methods on [`LockerRoomAsync`](crate::LockerRoomAsync).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MultiWriteGuard<'a, T>
+where
+    T: Collection,
+{
+    // Released before the lock of whole collection.
+    guards: OrderedGuards<WriteCells<'a, T>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<'a, T> MultiWriteGuard<'a, T>
+where
+    T: Collection,
+    T::Idx: CanonicalOrder,
+{
+    pub(crate) fn new(
+        cells: WriteCells<'a, T>,
+        global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+    ) -> Self {
+        Self {
+            guards: OrderedGuards::new(cells, global_rwlock_read_guard),
+        }
+    }
+
+    fn position(&self, index: &T::Idx) -> Option<usize> {
+        order::position(self.guards.cells(), index, |(idx, _, _)| idx)
+    }
+
+    /// Returns a reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        let position = self.position(index.borrow())?;
+        Some(&*self.guards.cells()[position].1)
+    }
+
+    /// Returns a mutable reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get_mut(&mut self, index: impl Borrow<T::Idx>) -> Option<&mut T::Output> {
+        let position = self.position(index.borrow())?;
+        Some(&mut *self.guards.cells_mut()[position].1)
+    }
+
+    /// An iterator visiting all locked cells with their indices in [`CanonicalOrder`](crate::CanonicalOrder) of indices.
+    pub fn iter(&self) -> impl Iterator<Item = (&T::Idx, &T::Output)> {
+        self.guards
+            .cells()
+            .iter()
+            .map(|(idx, value, _)| (idx, &**value))
+    }
+
+    /// An iterator visiting all locked cells with their indices in [`CanonicalOrder`](crate::CanonicalOrder) of indices, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&T::Idx, &mut T::Output)> + use<'_, 'a, T> {
+        self.guards
+            .cells_mut()
+            .iter_mut()
+            .map(|(idx, value, _)| (&*idx, &mut **value))
+    }
+
+    /// An iterator visiting the indices of all locked cells in [`CanonicalOrder`](crate::CanonicalOrder).
+    pub fn indices(&self) -> impl Iterator<Item = &T::Idx> + use<'_, 'a, T> {
+        self.guards.cells().iter().map(|(idx, _, _)| idx)
+    }
+
+    /// Returns the number of locked cells.
+    pub fn len(&self) -> usize {
+        self.guards.cells().len()
+    }
+
+    /// Returns `true` if the guard locks no cells.
+    pub fn is_empty(&self) -> bool {
+        self.guards.cells().is_empty()
     }
 }
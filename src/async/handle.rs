@@ -0,0 +1,48 @@
+use crate::Collection;
+
+use super::{LockerRoomAsync, ReadCellGuard, WriteCellGuard};
+
+/// A handle of a single cell of a [`LockerRoomAsync`], which locks the cell on demand.
+///
+/// Nothing is locked until [`read`](Self::read) or [`write`](Self::write) is awaited.
+///
+/// This structure is created by the [`cell`](LockerRoomAsync::cell) method on [`LockerRoomAsync`].
+pub struct CellHandle<'a, T>
+where
+    T: Collection,
+{
+    locker_room: &'a LockerRoomAsync<T>,
+    index: T::Idx,
+}
+
+impl<'a, T> CellHandle<'a, T>
+where
+    T: Collection,
+{
+    pub(crate) fn new(locker_room: &'a LockerRoomAsync<T>, index: T::Idx) -> Self {
+        Self { locker_room, index }
+    }
+
+    /// Gets a reference to the index of the cell.
+    pub fn index(&self) -> &T::Idx {
+        &self.index
+    }
+}
+
+impl<'a, T> CellHandle<'a, T>
+where
+    T: Collection,
+    T::Idx: Sync,
+{
+    /// Locks the cell with shared read access like [`LockerRoomAsync::read_cell`].
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub async fn read(&self) -> Option<ReadCellGuard<'a, T>> {
+        self.locker_room.read_cell(&self.index).await
+    }
+
+    /// Locks the cell with exclusive write access like [`LockerRoomAsync::write_cell`].
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub async fn write(&self) -> Option<WriteCellGuard<'a, T>> {
+        self.locker_room.write_cell(&self.index).await
+    }
+}
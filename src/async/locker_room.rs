@@ -1,10 +1,37 @@
-use std::{borrow::Borrow, cell::UnsafeCell, marker::PhantomData};
+use std::{
+    borrow::Borrow,
+    cell::UnsafeCell,
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    future::Future,
+    hash::Hash,
+    iter,
+    marker::PhantomData,
+    mem,
+    pin::pin,
+    sync::Arc,
+};
 
+#[cfg(any(feature = "time", doc))]
+use std::time::Duration;
+
+#[cfg(any(feature = "stream", doc))]
+use futures_util::{stream, Stream, StreamExt};
 use tokio::sync::RwLock;
 
-use crate::{Collection, ShadowLocksCollectionAsync};
+#[cfg(feature = "tracing")]
+use crate::trace::TraceIndex;
+#[cfg(any(feature = "tracing", doc))]
+use crate::trace::{self, FmtIndex};
+use crate::{
+    order, CanonicalOrder, Collection, CompareAndSwapError, FromCells, LockState, MapCollection,
+    ShadowLocksCollectionAsync,
+};
 
-use super::{ReadCellGuard, RoomGuard, WriteCellGuard};
+use super::{
+    guard::new_cell_lock, notify::Notifier, CellHandle, MultiReadGuard, MultiWriteGuard,
+    OwnedRoomGuard, ReadCellGuard, RoomGuard, WriteCellGuard,
+};
 
 /// Provides readers-writer lock for each indexed cell or exclusive write access to whole collection.
 /// Same as [`LockerRoom`](crate::LockerRoom) but async.
@@ -36,31 +63,136 @@ where
     collection: UnsafeCell<T>,
     global_lock: RwLock<()>,
     index_locks: UnsafeCell<T::ShadowLocksAsync>,
+    notifier: Notifier,
+    // Put on every cell lock by `with_reader_cap`.
+    reader_cap: Option<u32>,
+    #[cfg(any(feature = "tracing", doc))]
+    fmt_index: Option<FmtIndex<T::Idx>>,
     phantom: PhantomData<T::Idx>,
 }
 
-unsafe impl<T: Collection> Sync for LockerRoomAsync<T> {}
+// Tasks share the collection and the shadow locks, get mutable access to them through `lock_room`
+// and to the cell values through `write_cell`. `Send` is derived automatically with the right bounds.
+unsafe impl<T> Sync for LockerRoomAsync<T>
+where
+    T: Collection + Send + Sync,
+    T::Output: Send + Sync,
+    T::ShadowLocksAsync: Send + Sync,
+{
+}
 
 impl<'a, T> LockerRoomAsync<T>
 where
     T: Collection,
 {
+    /// Creates a `LockerRoomAsync` in which at most `cap` guards of every cell are held at once.
+    ///
+    /// The cap is put on the lock of every cell with [`RwLock::with_max_readers`], including the cells inserted later.
+    /// The lock queues tasks in order of arrival, so once `cap` guards of a cell are held, further readers wait behind
+    /// every writer queued before them instead of joining the readers of a hot cell. This way a writer waits for at
+    /// most `cap` readers of the cell to finish. In exchange readers past the cap wait even if no writer is around.
+    ///
+    /// Cells are capped separately, so guards of unrelated cells don't count against each other, but a task which
+    /// holds `cap` guards of a cell waits forever for one more of it. The lock of whole collection isn't capped.
+    /// Clones made by [`clone_async`](Self::clone_async) keep the cap.
+    ///
+    /// Shadow locks get the cap through [`ShadowLocksCollectionAsync::update_indices_with`], so custom ones which
+    /// don't implement it stay uncapped.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `cap` is zero or greater than `u32::MAX >> 3`, the limit of `RwLock`.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room = LockerRoomAsync::with_reader_cap(vec![1, 2], 1);
+    /// let guard = locker_room.read_cell(0).await.unwrap();
+    /// // Only cell 0 is at its cap.
+    /// assert_eq!(2, *locker_room.read_cell(1).await.unwrap());
+    /// assert!(locker_room.try_write_cells([1]).is_some());
+    /// drop(guard);
+    /// # });
+    /// ```
+    pub fn with_reader_cap(collection: T, cap: u32) -> Self {
+        assert!(cap > 0, "reader cap must be positive");
+        assert!(
+            cap <= u32::MAX >> 3,
+            "reader cap exceeds the limit of RwLock"
+        );
+        let mut locker_room = Self {
+            reader_cap: Some(cap),
+            ..collection.into()
+        };
+        // The locks made with the collection aren't capped, so they're made anew.
+        let index_locks = locker_room.index_locks.get_mut();
+        index_locks.update_indices_with(iter::empty(), || RwLock::new(()));
+        index_locks.update_indices_with(locker_room.collection.get_mut().indices(), || {
+            new_cell_lock(Some(cap))
+        });
+        locker_room
+    }
+
+    /// Makes spans of the `LockerRoomAsync` record indices of cells.
+    ///
+    /// With feature `tracing` every wait for a cell lock is instrumented with `lock_cell` span at `TRACE` level which
+    /// records the kind of access, `read` or `write`. Its field `index` is `_` unless this function is called.
+    /// The wait for [`lock_room`](Self::lock_room) is instrumented with `lock_room` span. Releasing of locks isn't traced.
+    ///
+    /// Spans cost almost nothing if no subscriber is interested in them, and indices are formatted only if one is.
+    #[cfg(any(feature = "tracing", doc))]
+    #[doc(cfg(feature = "tracing"))]
+    pub fn trace_indices(mut self) -> Self
+    where
+        T::Idx: std::fmt::Debug,
+    {
+        self.fmt_index = Some(trace::fmt_index);
+        self
+    }
+
+    #[allow(unused_variables)]
+    fn traced<F: Future>(
+        &self,
+        lock: F,
+        kind: &'static str,
+        index: &T::Idx,
+    ) -> impl Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        return tracing::Instrument::instrument(
+            lock,
+            tracing::trace_span!("lock_cell", kind, index = ?TraceIndex(index, self.fmt_index)),
+        );
+        #[cfg(not(feature = "tracing"))]
+        lock
+    }
+
     /// Locks cell at the index with shared read access, causing the current task to yield until the lock has been acquired.
     ///
     /// This function will return `None` if there is no cell with such index.
     ///
     /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the locks acquired so far are released.
+    #[must_use = "locking has no effect unless the guard is used"]
     pub async fn read_cell(
         &'a self,
         index: impl Borrow<T::Idx> + Send,
     ) -> Option<ReadCellGuard<'a, T>> {
         let global_lock_guard = self.global_lock.read().await;
+        // Hash maps hash the index once for both the shadow locks and the collection.
+        let hash = unsafe { &*self.collection.get() }.hash_index(index.borrow());
         let index_locks = unsafe { &*self.index_locks.get() };
-        let index_lock_guard = index_locks.index(index.borrow())?.read().await;
+        let index_lock = index_locks.index_hashed(index.borrow(), hash)?;
+        let index_lock_guard = self.traced(index_lock.read(), "read", index.borrow()).await;
         let collection = unsafe { &*self.collection.get() };
-        collection
-            .index(index)
-            .map(|v| ReadCellGuard::new(v, global_lock_guard, index_lock_guard))
+        collection.index_hashed(index.borrow(), hash).map(|v| {
+            ReadCellGuard::new(
+                v,
+                (&self.global_lock, global_lock_guard),
+                (index_lock, index_lock_guard),
+            )
+        })
     }
 
     /// Locks cell at the index with exclusive write access, causing the current task to yield until the lock has been acquired.
@@ -68,35 +200,977 @@ where
     /// This function will return `None` if there is no cell with such index.
     ///
     /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the locks acquired so far are released.
+    #[must_use = "locking has no effect unless the guard is used"]
     pub async fn write_cell(
         &'a self,
         index: impl Borrow<T::Idx> + Send,
     ) -> Option<WriteCellGuard<'a, T>> {
         let global_lock_guard = self.global_lock.read().await;
+        // Hash maps hash the index once for both the shadow locks and the collection.
+        let hash = unsafe { &*self.collection.get() }.hash_index(index.borrow());
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index_hashed(index.borrow(), hash)?;
+        let index_lock_guard = self
+            .traced(index_lock.write(), "write", index.borrow())
+            .await;
+        let collection = unsafe { &mut *self.collection.get() };
+        collection
+            .index_mut_hashed(index.borrow(), hash)
+            .map(|v| WriteCellGuard::new(v, global_lock_guard, index_lock_guard))
+    }
+
+    /// Locks cell at the index with shared read access like [`read_cell`](Self::read_cell), for indices known to be valid.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no cell with such index. The message includes the index.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+    /// assert_eq!(2, *locker_room.read_cell_expect(1).await);
+    /// # });
+    /// ```
+    pub async fn read_cell_expect(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+    ) -> ReadCellGuard<'a, T>
+    where
+        T::Idx: Debug + Sync,
+    {
+        let index = index.borrow();
+        match self.read_cell(index).await {
+            Some(guard) => guard,
+            None => panic!("no cell at index {index:?}"),
+        }
+    }
+
+    /// Locks cell at the index with exclusive write access like [`write_cell`](Self::write_cell), for indices known
+    /// to be valid.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no cell with such index. The message includes the index.
+    pub async fn write_cell_expect(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+    ) -> WriteCellGuard<'a, T>
+    where
+        T::Idx: Debug + Sync,
+    {
+        let index = index.borrow();
+        match self.write_cell(index).await {
+            Some(guard) => guard,
+            None => panic!("no cell at index {index:?}"),
+        }
+    }
+
+    /// Locks cell at the index with exclusive write access like [`write_cell`](Self::write_cell) and returns a clone
+    /// of its value along with the guard.
+    ///
+    /// The value is cloned under the write lock, so it's exactly the value the guard starts with. This function
+    /// will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![10, 20].into();
+    /// let (previous, mut cell) = locker_room.write_cell_with_previous(1).await.unwrap();
+    /// *cell += 5;
+    /// assert_eq!(5, *cell - previous);
+    /// # });
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub async fn write_cell_with_previous(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+    ) -> Option<(T::Output, WriteCellGuard<'a, T>)>
+    where
+        T::Output: Clone,
+    {
+        let guard = self.write_cell(index).await?;
+        Some((T::Output::clone(&guard), guard))
+    }
+
+    /// Locks cell at the index with exclusive write access like [`write_cell`](Self::write_cell), but gives up
+    /// if the lock hasn't been acquired within `duration`.
+    ///
+    /// This function will return [`Elapsed`](tokio::time::error::Elapsed) on timeout, e.g. while whole collection
+    /// is locked by [`lock_room`](Self::lock_room). Nothing stays locked then: the lock of whole collection is
+    /// released with the dropped `write_cell` future. It must be called within a tokio runtime with the time driver.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+    /// let cell = locker_room.write_cell(1).await.unwrap();
+    /// assert!(locker_room.write_cell_timeout(1, Duration::from_millis(10)).await.is_err());
+    /// drop(cell);
+    /// assert!(locker_room.write_cell_timeout(1, Duration::from_millis(10)).await.unwrap().is_some());
+    /// # });
+    /// ```
+    #[cfg(any(feature = "time", doc))]
+    #[doc(cfg(feature = "time"))]
+    pub async fn write_cell_timeout(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        duration: Duration,
+    ) -> Result<Option<WriteCellGuard<'a, T>>, tokio::time::error::Elapsed> {
+        tokio::time::timeout(duration, self.write_cell(index)).await
+    }
+
+    /// Returns a handle of the cell at the index, which locks the cell on demand with
+    /// [`read`](CellHandle::read) and [`write`](CellHandle::write).
+    ///
+    /// Nothing is locked and the index isn't checked until the handle is used.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![0, 1, 2, 3].into();
+    /// let cell = locker_room.cell(3);
+    /// *cell.write().await.unwrap() += 1;
+    /// assert_eq!(4, *cell.read().await.unwrap());
+    /// assert!(locker_room.cell(4).read().await.is_none());
+    /// # });
+    /// ```
+    pub fn cell(&'a self, index: T::Idx) -> CellHandle<'a, T> {
+        CellHandle::new(self, index)
+    }
+
+    /// Locks cell at the index with exclusive write access once `cond` holds for its value.
+    ///
+    /// The condition is checked while the cell is locked. If it isn't met, the cell is released and the task waits
+    /// until [`notify_cell`](Self::notify_cell) is called, then it checks again. So whoever changes the value in a way
+    /// the waiter may care about has to call `notify_cell` after releasing the cell.
+    ///
+    /// This function will return `None` if there is no cell with such index, including the case when the cell is
+    /// removed while the task waits.
+    ///
+    /// Returns an RAII guard which will release this task's exclusive write access once it is dropped.
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![None, None].into());
+    /// let producer = tokio::spawn({
+    ///     let locker_room = locker_room.clone();
+    ///     async move {
+    ///         *locker_room.write_cell(1).await.unwrap() = Some("ready");
+    ///         locker_room.notify_cell(1);
+    ///     }
+    /// });
+    /// let mut guard = locker_room.wait_write_cell(1, Option::is_some).await.unwrap();
+    /// assert_eq!(Some("ready"), guard.take());
+    /// # drop(guard);
+    /// # producer.await.unwrap();
+    /// # });
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub async fn wait_write_cell(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        cond: impl Fn(&T::Output) -> bool,
+    ) -> Option<WriteCellGuard<'a, T>>
+    where
+        T::Idx: Hash + Sync,
+    {
+        loop {
+            // Registered before the check, so a notification sent after the check isn't missed.
+            let registration = self.notifier.register(index.borrow());
+            let notified = registration.notify.notified();
+            let mut notified = pin!(notified);
+            notified.as_mut().enable();
+            let guard = self.write_cell(index.borrow()).await?;
+            if cond(&guard) {
+                return Some(guard);
+            }
+            drop(guard);
+            notified.await;
+        }
+    }
+
+    /// Wakes the tasks waiting in [`wait_write_cell`](Self::wait_write_cell) for the cell at the index to check
+    /// their conditions again.
+    ///
+    /// Waiters of other cells stay asleep, unless their indices have the same hash as this one. Those wake up, find
+    /// their conditions unchanged and go back to waiting.
+    pub fn notify_cell(&'a self, index: impl Borrow<T::Idx>)
+    where
+        T::Idx: Hash,
+    {
+        self.notifier.notify(index.borrow());
+    }
+
+    /// Locks cell at the index with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if called within an asynchronous execution context, just like
+    /// [`RwLock::blocking_read`](tokio::sync::RwLock::blocking_read). Call it from synchronous code only,
+    /// for example from a closure passed to [`spawn_blocking`](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html).
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn blocking_read_cell(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+    ) -> Option<ReadCellGuard<'a, T>> {
+        let global_lock_guard = self.global_lock.blocking_read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index(index.borrow())?;
+        let index_lock_guard = index_lock.blocking_read();
+        let collection = unsafe { &*self.collection.get() };
+        collection.index(index).map(|v| {
+            ReadCellGuard::new(
+                v,
+                (&self.global_lock, global_lock_guard),
+                (index_lock, index_lock_guard),
+            )
+        })
+    }
+
+    /// Locks cell at the index with exclusive write access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if called within an asynchronous execution context, just like
+    /// [`RwLock::blocking_write`](tokio::sync::RwLock::blocking_write). Call it from synchronous code only,
+    /// for example from a closure passed to [`spawn_blocking`](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html).
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn blocking_write_cell(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+    ) -> Option<WriteCellGuard<'a, T>> {
+        let global_lock_guard = self.global_lock.blocking_read();
         let index_locks = unsafe { &*self.index_locks.get() };
-        let index_lock_guard = index_locks.index(index.borrow())?.write().await;
+        let index_lock_guard = index_locks.index(index.borrow())?.blocking_write();
         let collection = unsafe { &mut *self.collection.get() };
         collection
             .index_mut(index)
             .map(|v| WriteCellGuard::new(v, global_lock_guard, index_lock_guard))
     }
 
+    /// Locks cells at the indices with shared read access, causing the current task to yield until all of them have been acquired.
+    ///
+    /// Cells are locked in [`CanonicalOrder`] of indices, which is ascending for [`Ord`] ones, and repeated indices
+    /// are locked once, so concurrent calls of `read_cells` and [`write_cells`](Self::write_cells) can't deadlock each other.
+    ///
+    /// This function will return `None` if there is no cell with one of the indices.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access to all the cells once it is dropped.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the locks acquired so far are released.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub async fn read_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx> + Send,
+    ) -> Option<MultiReadGuard<'a, T>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.global_lock.read().await;
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let collection = unsafe { &*self.collection.get() };
+        let mut cells = Vec::with_capacity(indices.len());
+        for index in indices {
+            let index_lock = index_locks.index(&index)?;
+            let index_lock_guard = self.traced(index_lock.read(), "read", &index).await;
+            let value = collection.index(&index)?;
+            cells.push((index, value, index_lock_guard));
+        }
+        Some(MultiReadGuard::new(cells, global_lock_guard))
+    }
+
+    /// Locks cells at the indices with exclusive write access, causing the current task to yield until all of them have been acquired.
+    ///
+    /// Cells are locked in [`CanonicalOrder`] of indices, which is ascending for [`Ord`] ones, and repeated indices
+    /// are locked once, so concurrent calls of [`read_cells`](Self::read_cells) and `write_cells` can't deadlock each other.
+    ///
+    /// This function will return `None` if there is no cell with one of the indices.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access to all the cells once it is dropped.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the locks acquired so far are released.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub async fn write_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx> + Send,
+    ) -> Option<MultiWriteGuard<'a, T>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.global_lock.read().await;
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let mut cells = Vec::with_capacity(indices.len());
+        for index in indices {
+            let index_lock = index_locks.index(&index)?;
+            let index_lock_guard = self.traced(index_lock.write(), "write", &index).await;
+            // Indices are unique, so every cell is borrowed mutably only once.
+            let value = unsafe { &mut *self.collection.get() }.index_mut(&index)?;
+            cells.push((index, value, index_lock_guard));
+        }
+        Some(MultiWriteGuard::new(cells, global_lock_guard))
+    }
+
+    /// Attempts to lock cells at the indices with exclusive write access without yielding.
+    ///
+    /// Cells are tried in the same order as [`write_cells`](Self::write_cells) locks them. This function will return
+    /// `None` if there is no cell with one of the indices or if any of the cells or whole collection is locked by
+    /// someone else. The cells locked so far are released then, so the caller can back off and retry instead of
+    /// waiting while holding a part of the cells.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3].into();
+    /// let cell = locker_room.read_cell(2).await.unwrap();
+    /// assert!(locker_room.try_write_cells([0, 2]).is_none());
+    /// drop(cell);
+    /// assert!(locker_room.try_write_cells([0, 2]).is_some());
+    /// # });
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn try_write_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> Option<MultiWriteGuard<'a, T>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.global_lock.try_read().ok()?;
+        let index_locks = unsafe { &*self.index_locks.get() };
+        // Guards of the cells locked so far are dropped with `cells` on every early return.
+        let mut cells = Vec::with_capacity(indices.len());
+        for index in indices {
+            let index_lock_guard = index_locks.index(&index)?.try_write().ok()?;
+            // Indices are unique, so every cell is borrowed mutably only once.
+            let value = unsafe { &mut *self.collection.get() }.index_mut(&index)?;
+            cells.push((index, value, index_lock_guard));
+        }
+        Some(MultiWriteGuard::new(cells, global_lock_guard))
+    }
+
+    /// Locks cells at the indices with shared read access like [`read_cells`](Self::read_cells), calls `f` with
+    /// references to their values and releases the cells.
+    ///
+    /// Values are passed in [`CanonicalOrder`] of indices without repeats. The locks can't outlive `f`,
+    /// so this is harder to misuse than holding the guard of `read_cells`.
+    ///
+    /// This function will return `None` without calling `f` if there is no cell with one of the indices.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3].into();
+    /// let sum = locker_room.with_cells([2, 0], |values| values.iter().copied().sum()).await;
+    /// assert_eq!(Some(4), sum);
+    /// assert_eq!(None, locker_room.with_cells([0, 3], |values| values.len()).await);
+    /// # });
+    /// ```
+    pub async fn with_cells<R>(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx> + Send,
+        f: impl FnOnce(&[&T::Output]) -> R,
+    ) -> Option<R>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let guard = self.read_cells(indices).await?;
+        let values: Vec<_> = guard.iter().map(|(_, value)| value).collect();
+        Some(f(&values))
+    }
+
+    /// Locks cells at the indices with exclusive write access like [`write_cells`](Self::write_cells), calls `f`
+    /// with mutable references to their values and releases the cells.
+    ///
+    /// Values are passed in [`CanonicalOrder`] of indices without repeats. The locks can't outlive `f`,
+    /// so this is harder to misuse than holding the guard of `write_cells`.
+    ///
+    /// This function will return `None` without calling `f` if there is no cell with one of the indices.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3].into();
+    /// locker_room
+    ///     .with_cells_mut([2, 0], |values| values.iter_mut().for_each(|value| **value *= 10))
+    ///     .await;
+    /// assert_eq!(vec![10, 2, 30], locker_room.into_inner());
+    /// # });
+    /// ```
+    pub async fn with_cells_mut<R>(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx> + Send,
+        f: impl FnOnce(&mut [&mut T::Output]) -> R,
+    ) -> Option<R>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut guard = self.write_cells(indices).await?;
+        let mut values: Vec<_> = guard.iter_mut().map(|(_, value)| value).collect();
+        Some(f(&mut values))
+    }
+
+    /// Swaps values of the cells at two indices, locking both of them with exclusive write access like [`write_cells`](Self::write_cells).
+    ///
+    /// This function will return `false` if there is no cell with one of the indices. Swapping a cell with itself does nothing.
+    pub async fn swap_cells(&'a self, a: T::Idx, b: T::Idx) -> bool
+    where
+        T::Idx: CanonicalOrder + Send,
+        T::Output: Sized,
+    {
+        let Some(mut guard) = self.write_cells([a, b]).await else {
+            return false;
+        };
+        let mut values = guard.iter_mut().map(|(_, value)| value);
+        if let (Some(a), Some(b)) = (values.next(), values.next()) {
+            mem::swap(a, b);
+        }
+        true
+    }
+
+    /// Copies the value of the cell at the index out. The cell is locked with shared read access only while it's copied.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async  {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+    /// assert_eq!(Some(2), locker_room.get_cell(1).await);
+    /// assert_eq!(None, locker_room.get_cell(2).await);
+    /// # });
+    /// ```
+    pub async fn get_cell(&'a self, index: impl Borrow<T::Idx> + Send) -> Option<T::Output>
+    where
+        T::Output: Copy,
+    {
+        self.read_cell(index).await.map(|guard| *guard)
+    }
+
+    /// Clones the values of the cells at the indices into a new collection, locking every cell with shared read
+    /// access only while its value is cloned.
+    ///
+    /// Maps keep the indices as keys, while vectors get the values in the order of the indices, see [`FromCells`].
+    /// Missing cells are skipped. At most one cell is locked at a time, so it can't deadlock with other tasks, but
+    /// the values are not a consistent snapshot: a cell may be written after the previous one was cloned.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3, 4].into();
+    /// assert_eq!(vec![4, 2], locker_room.project([3, 1, 9]).await);
+    /// # });
+    /// ```
+    pub async fn project(&'a self, indices: impl IntoIterator<Item = T::Idx> + Send) -> T
+    where
+        T: FromCells,
+        T::Idx: Sync,
+        T::Value: Clone,
+    {
+        let mut cells = Vec::new();
+        for index in indices {
+            if let Some(guard) = self.read_cell(&index).await {
+                let value = T::Value::clone(&*guard);
+                drop(guard);
+                cells.push((index, value));
+            }
+        }
+        T::from_cells(cells)
+    }
+
+    /// Overwrites the value of the cell at the index. The cell is locked with exclusive write access only while it's written.
+    ///
+    /// This function will return `false` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async  {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+    /// assert!(locker_room.set_cell(1, 3).await);
+    /// assert!(!locker_room.set_cell(2, 3).await);
+    /// assert_eq!(Some(3), locker_room.get_cell(1).await);
+    /// # });
+    /// ```
+    pub async fn set_cell(&'a self, index: impl Borrow<T::Idx> + Send, value: T::Output) -> bool
+    where
+        T::Output: Sized,
+    {
+        self.write_cell(index)
+            .await
+            .map(|mut guard| *guard = value)
+            .is_some()
+    }
+
+    /// Replaces the value of the cell at the index, locking it with exclusive write access, and returns the old value.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async  {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+    /// assert_eq!(Some(2), locker_room.replace_cell(1, 3).await);
+    /// assert_eq!(None, locker_room.replace_cell(2, 3).await);
+    /// assert_eq!(3, *locker_room.read_cell(1).await.unwrap());
+    /// # });
+    /// ```
+    pub async fn replace_cell(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        value: T::Output,
+    ) -> Option<T::Output>
+    where
+        T::Output: Sized,
+    {
+        self.write_cell(index)
+            .await
+            .map(|mut guard| mem::replace(&mut *guard, value))
+    }
+
+    /// Takes the value of the cell at the index, locking it with exclusive write access, and leaves `Default::default()` in its place.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async  {
+    /// let locker_room: LockerRoomAsync<_> = vec![String::from("a")].into();
+    /// assert_eq!(Some(String::from("a")), locker_room.take_cell(0).await);
+    /// assert_eq!(None, locker_room.take_cell(1).await);
+    /// assert!(locker_room.read_cell(0).await.unwrap().is_empty());
+    /// # });
+    /// ```
+    pub async fn take_cell(&'a self, index: impl Borrow<T::Idx> + Send) -> Option<T::Output>
+    where
+        T::Output: Default,
+    {
+        self.write_cell(index)
+            .await
+            .map(|mut guard| mem::take(&mut *guard))
+    }
+
+    /// Locks the cell at the index with exclusive write access and replaces its value with `new` if it's equal to `expected`.
+    ///
+    /// Otherwise the value isn't changed and `new` is given back in the error: [`CompareAndSwapError::Mismatch`] if the value
+    /// isn't equal to `expected` and [`CompareAndSwapError::NoCell`] if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::{CompareAndSwapError, LockerRoomAsync};
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![0].into();
+    /// // Optimistic increment: read the value without holding the lock while computing, then retry if it has changed.
+    /// loop {
+    ///     let current = *locker_room.read_cell(0).await.unwrap();
+    ///     match locker_room.compare_and_swap_cell(0, &current, current + 1).await {
+    ///         Ok(()) => break,
+    ///         Err(CompareAndSwapError::Mismatch(_)) => continue,
+    ///         Err(CompareAndSwapError::NoCell(_)) => unreachable!(),
+    ///     }
+    /// }
+    /// assert_eq!(1, *locker_room.read_cell(0).await.unwrap());
+    /// assert_eq!(Err(CompareAndSwapError::Mismatch(3)), locker_room.compare_and_swap_cell(0, &0, 3).await);
+    /// assert_eq!(Err(CompareAndSwapError::NoCell(3)), locker_room.compare_and_swap_cell(1, &0, 3).await);
+    /// # });
+    /// ```
+    pub async fn compare_and_swap_cell(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        expected: &T::Output,
+        new: T::Output,
+    ) -> Result<(), CompareAndSwapError<T::Output>>
+    where
+        T::Output: PartialEq + Sized,
+    {
+        let Some(mut guard) = self.write_cell(index).await else {
+            return Err(CompareAndSwapError::NoCell(new));
+        };
+        if *guard != *expected {
+            return Err(CompareAndSwapError::Mismatch(new));
+        }
+        *guard = new;
+        Ok(())
+    }
+
+    /// Locks the cell at the index with exclusive write access and applies `f` to its value.
+    ///
+    /// The lock is released as soon as `f` returns. This function will return `false` if there is no cell with such index.
+    pub async fn update_cell(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        f: impl FnOnce(&mut T::Output),
+    ) -> bool {
+        self.modify_cell(index, f).await.is_some()
+    }
+
+    /// Locks the cell at the index with exclusive write access and applies `f` to its value, returning the result of `f`.
+    ///
+    /// The lock is released as soon as `f` returns. This function will return `None` if there is no cell with such index.
+    pub async fn modify_cell<R>(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        f: impl FnOnce(&mut T::Output) -> R,
+    ) -> Option<R> {
+        self.write_cell(index).await.map(|mut guard| f(&mut guard))
+    }
+
+    /// Locks the cell at the index with exclusive write access and applies `f` to its value, where `f` reports whether it
+    /// changed the value. Returns that report, e.g. to mark the cell as dirty or invalidate caches.
+    ///
+    /// The report isn't checked, the crate trusts `f`. The lock is released as soon as `f` returns.
+    /// This function will return `None` if there is no cell with such index.
+    pub async fn write_cell_if(
+        &'a self,
+        index: impl Borrow<T::Idx> + Send,
+        f: impl FnOnce(&mut T::Output) -> bool,
+    ) -> Option<bool> {
+        self.modify_cell(index, f).await
+    }
+
+    /// Applies `f` to the value of every cell for which `pred` returns `true`, returning the number of such cells.
+    ///
+    /// Whole collection isn't locked: `pred` is called while a cell is locked with shared read access and, if it
+    /// matches, the cell is locked again with exclusive write access for `f`, one cell at a time. So other cells stay
+    /// available, but the cell may be written by someone else in between; use [`write_where_checked`](Self::write_where_checked)
+    /// if `f` must only see values matching `pred`.
+    ///
+    /// Cells are visited by a snapshot of [`indices`](Self::indices), so cells inserted meanwhile are not visited,
+    /// and removed cells are skipped.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3, 4].into();
+    /// assert_eq!(2, locker_room.write_where(|_, v| v % 2 == 0, |v| *v = 0).await);
+    /// assert_eq!(vec![1, 0, 3, 0], locker_room.into_inner());
+    /// # });
+    /// ```
+    pub async fn write_where(
+        &'a self,
+        pred: impl Fn(&T::Idx, &T::Output) -> bool,
+        f: impl FnMut(&mut T::Output),
+    ) -> usize
+    where
+        T::Idx: Sync,
+    {
+        self.write_where_with(pred, f, false).await
+    }
+
+    /// Like [`write_where`](Self::write_where), but calls `pred` once more under the write lock and skips the cell
+    /// if it doesn't match anymore.
+    pub async fn write_where_checked(
+        &'a self,
+        pred: impl Fn(&T::Idx, &T::Output) -> bool,
+        f: impl FnMut(&mut T::Output),
+    ) -> usize
+    where
+        T::Idx: Sync,
+    {
+        self.write_where_with(pred, f, true).await
+    }
+
+    async fn write_where_with(
+        &'a self,
+        pred: impl Fn(&T::Idx, &T::Output) -> bool,
+        mut f: impl FnMut(&mut T::Output),
+        recheck: bool,
+    ) -> usize
+    where
+        T::Idx: Sync,
+    {
+        let mut written = 0;
+        for index in self.indices().await {
+            let matches = self
+                .read_cell(&index)
+                .await
+                .is_some_and(|guard| pred(&index, &guard));
+            if !matches {
+                continue;
+            }
+            let Some(mut guard) = self.write_cell(&index).await else {
+                continue;
+            };
+            if recheck && !pred(&index, &guard) {
+                continue;
+            }
+            f(&mut guard);
+            written += 1;
+        }
+        written
+    }
+
+    /// Returns a [`Stream`] which locks cells at the indices with exclusive write access one by one, in the given order.
+    ///
+    /// Each cell is locked only when the next item is polled, so the consumer can process a cell as soon as its lock is free.
+    /// Indices of missing cells are skipped.
+    ///
+    /// Every yielded guard also holds the whole collection lock with shared read access. If a consumer keeps previous guards
+    /// while polling the next item, it can deadlock with [`lock_room`](Self::lock_room) waiting in another task,
+    /// so drop each guard before polling the next one or use [`write_cells`](Self::write_cells) instead.
+    #[cfg(any(feature = "stream", doc))]
+    #[doc(cfg(feature = "stream"))]
+    pub fn write_cells_stream(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> impl Stream<Item = (T::Idx, WriteCellGuard<'a, T>)> {
+        stream::iter(indices).filter_map(move |index| async move {
+            let global_lock_guard = self.global_lock.read().await;
+            let index_locks = unsafe { &*self.index_locks.get() };
+            let index_lock = index_locks.index(&index)?;
+            let index_lock_guard = self.traced(index_lock.write(), "write", &index).await;
+            let collection = unsafe { &mut *self.collection.get() };
+            let value = collection.index_mut(&index)?;
+            Some((
+                index,
+                WriteCellGuard::new(value, global_lock_guard, index_lock_guard),
+            ))
+        })
+    }
+
     /// Exclusively locks whole collection with right access.
     ///
     /// No cell locks can be acquired by other threads when locked whole collection.
     ///
     /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the locks acquired so far are released.
     pub async fn lock_room(&'a self) -> RoomGuard<'a, T> {
-        let global_lock_guard = self.global_lock.write().await;
+        let lock = self.global_lock.write();
+        #[cfg(feature = "tracing")]
+        let lock = tracing::Instrument::instrument(lock, tracing::trace_span!("lock_room"));
+        let global_lock_guard = lock.await;
         let index_locks = unsafe { &mut *self.index_locks.get() };
         let collection = unsafe { &mut *self.collection.get() };
-        RoomGuard::new(collection, index_locks, global_lock_guard)
+        RoomGuard::new(collection, index_locks, global_lock_guard, self.reader_cap)
+    }
+
+    /// Exclusively locks whole collection with write access like [`lock_room`](Self::lock_room), but the returned
+    /// guard owns a clone of the `Arc`, so it's `'static` and can be moved into a spawned task. See [`OwnedRoomGuard`].
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the locks acquired so far are released.
+    pub async fn lock_room_owned(self: &Arc<Self>) -> OwnedRoomGuard<T>
+    where
+        T: 'static,
+    {
+        OwnedRoomGuard::new(Arc::clone(self)).await
+    }
+
+    /// Calls `f` with a reference to whole collection and returns its result. The lock can't outlive `f`.
+    ///
+    /// Cells are written while whole collection is locked only with shared access, so it's exclusively locked while
+    /// `f` runs, like [`lock_room`](Self::lock_room) does.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3].into();
+    /// assert_eq!(6, locker_room.with_ref(|v| v.iter().sum::<i32>()).await);
+    /// # });
+    /// ```
+    pub async fn with_ref<R>(&'a self, f: impl FnOnce(&T) -> R) -> R {
+        let _global_lock_guard = self.global_lock.write().await;
+        f(unsafe { &*self.collection.get() })
+    }
+
+    /// Calls `f` with a mutable reference to whole collection under [`lock_room`](Self::lock_room) and returns its
+    /// result. Shadow locks are updated after `f` returns, so it may insert and remove cells.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3].into();
+    /// assert_eq!(4, locker_room.with_mut(|v| { v.push(4); v.len() }).await);
+    /// assert_eq!(Some(4), locker_room.get_cell(3).await);
+    /// # });
+    /// ```
+    pub async fn with_mut<R>(&'a self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut *self.lock_room().await)
+    }
+
+    /// Returns the lock state of the cell at the index, or `None` if there is no cell with such index.
+    ///
+    /// The state is probed by trying to lock the cell for a moment, so it is best-effort and may be outdated once
+    /// the function returns. The number of readers isn't exposed by [`RwLock`], so only the kind of access is reported.
+    /// It is intended for debugging of contention hot spots.
+    pub async fn cell_lock_state(&'a self, index: impl Borrow<T::Idx> + Send) -> Option<LockState> {
+        let _global_lock_guard = self.global_lock.read().await;
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index(index)?;
+        Some(if index_lock.try_write().is_ok() {
+            LockState::Unlocked
+        } else if index_lock.try_read().is_ok() {
+            LockState::Read
+        } else {
+            LockState::Write
+        })
+    }
+
+    /// Returns the indices of all cells, taking the whole collection lock with shared read access only while collecting them.
+    ///
+    /// This is a snapshot: once the function returns, [`lock_room`](Self::lock_room) can add or remove cells,
+    /// so locking a cell with a returned index may still give `None`.
+    pub async fn indices(&'a self) -> Vec<T::Idx> {
+        let _global_lock_guard = self.global_lock.read().await;
+        let collection = unsafe { &*self.collection.get() };
+        collection.indices().collect()
+    }
+
+    /// Creates an independent `LockerRoomAsync` with a clone of the collection, waiting for the exclusive lock
+    /// of whole collection like [`lock_room`](Self::lock_room) does while cloning.
+    ///
+    /// It's the asynchronous version of [`Clone::clone`]. The clone gets its own shadow locks, all of them unlocked.
+    pub async fn clone_async(&'a self) -> Self
+    where
+        T: Clone,
+    {
+        let global_lock_guard = self.global_lock.write().await;
+        let collection = unsafe { &*self.collection.get() }.clone();
+        drop(global_lock_guard);
+        let cloned = match self.reader_cap {
+            Some(cap) => Self::with_reader_cap(collection, cap),
+            None => collection.into(),
+        };
+        #[cfg(any(feature = "tracing", doc))]
+        let cloned = Self {
+            fmt_index: self.fmt_index,
+            ..cloned
+        };
+        cloned
     }
 
     /// Consumes this `LockerRoomAsync`, returning the underlying data.
     pub fn into_inner(self) -> T {
         self.collection.into_inner()
     }
+
+    /// Consumes the `Arc` of this `LockerRoomAsync`, returning the underlying data if it's the only strong reference.
+    /// Otherwise the `Arc` is given back.
+    ///
+    /// This is an associated function that needs to be used as `LockerRoomAsync::try_into_inner_arc(arc)`.
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0, 0].into());
+    /// let handles: Vec<_> = (0..2)
+    ///     .map(|i| {
+    ///         let locker_room = Arc::clone(&locker_room);
+    ///         tokio::spawn(async move { *locker_room.write_cell(i).await.unwrap() += 1 })
+    ///     })
+    ///     .collect();
+    /// for handle in handles {
+    ///     handle.await.unwrap();
+    /// }
+    ///
+    /// let cloned = Arc::clone(&locker_room);
+    /// let locker_room = LockerRoomAsync::try_into_inner_arc(locker_room).unwrap_err();
+    /// drop(cloned);
+    /// assert_eq!(Some(vec![1, 1]), LockerRoomAsync::try_into_inner_arc(locker_room).ok());
+    /// # });
+    /// ```
+    pub fn try_into_inner_arc(arc: Arc<Self>) -> Result<T, Arc<Self>> {
+        Arc::try_unwrap(arc).map(Self::into_inner)
+    }
+}
+
+impl<'a, T> LockerRoomAsync<T>
+where
+    T: MapCollection,
+{
+    /// Locks the cell with the key with exclusive write access, inserting the result of `f` first if there is no such cell.
+    ///
+    /// If the cell exists, only the cell is locked like [`write_cell`](Self::write_cell) does. Otherwise the task waits
+    /// for the exclusive lock of whole collection like [`lock_room`](Self::lock_room) does, checks the presence of the
+    /// cell again in case some other task inserted it in between, calls `f` and inserts the result if the cell is still
+    /// absent. Then the lock of whole collection is atomically turned into the lock of the cell.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<HashMap<&str, Vec<i32>>> = Default::default();
+    /// locker_room.get_or_insert_with("a", Vec::new).await.push(1);
+    /// locker_room.get_or_insert_with("a", Vec::new).await.push(2);
+    /// assert_eq!(vec![1, 2], *locker_room.read_cell("a").await.unwrap());
+    /// # });
+    /// ```
+    pub async fn get_or_insert_with(
+        &'a self,
+        key: T::Idx,
+        f: impl FnOnce() -> T::Value,
+    ) -> WriteCellGuard<'a, T>
+    where
+        T::Idx: Clone + Send,
+    {
+        if let Some(guard) = self.write_cell(key.clone()).await {
+            return guard;
+        }
+        let mut room_guard = self.lock_room().await;
+        if room_guard.index(&key).is_none() {
+            room_guard.insert_cell(key.clone(), f());
+        }
+        room_guard
+            .into_write_cell(&key)
+            .expect("cell exists while whole collection is locked")
+    }
+}
+
+impl<V> LockerRoomAsync<Vec<V>> {
+    /// Resizes the vector to `new_len` cells like [`Vec::resize`], filling the new cells with clones of `value`.
+    ///
+    /// The task waits for the exclusive lock of whole collection like [`lock_room`](Self::lock_room) does.
+    /// The shadow locks are resized before whole collection is released, so the new cells can be locked as soon as
+    /// this function returns.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1].into();
+    /// locker_room.resize(3, 0).await;
+    /// *locker_room.write_cell(2).await.unwrap() = 3;
+    /// assert_eq!(vec![1, 0, 3], locker_room.into_inner());
+    /// # });
+    /// ```
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the vector isn't changed.
+    pub async fn resize(&self, new_len: usize, value: V)
+    where
+        V: Clone,
+    {
+        self.lock_room().await.resize(new_len, value);
+    }
+
+    /// Shortens the vector to `len` cells like [`Vec::truncate`]. It has no effect if the vector is already shorter.
+    ///
+    /// The task waits for the exclusive lock of whole collection like in [`resize`](Self::resize), and the shadow
+    /// locks of the removed cells are dropped before it's released.
+    /// ```
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<_> = vec![1, 2, 3].into();
+    /// locker_room.truncate(1).await;
+    /// assert!(locker_room.read_cell(1).await.is_none());
+    /// assert_eq!(vec![1], locker_room.into_inner());
+    /// # });
+    /// ```
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe. If the returned future is dropped before completion, the vector isn't changed.
+    pub async fn truncate(&self, len: usize) {
+        self.lock_room().await.truncate(len);
+    }
 }
 
 impl<T> From<T> for LockerRoomAsync<T>
@@ -109,18 +1183,114 @@ where
             collection: UnsafeCell::new(value),
             global_lock: Default::default(),
             index_locks: UnsafeCell::new(index_locks),
+            notifier: Default::default(),
+            reader_cap: None,
+            #[cfg(any(feature = "tracing", doc))]
+            fmt_index: None,
             phantom: Default::default(),
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use std::{ops::DerefMut, sync::Arc};
+impl<K, V, const N: usize> From<[(K, V); N]> for LockerRoomAsync<HashMap<K, V>>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates a `LockerRoomAsync` from a [`HashMap`] of the key-value pairs, like [`HashMap::from`].
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<HashMap<_, _>> = [("a", 1), ("b", 2)].into();
+    /// assert_eq!(2, *locker_room.read_cell("b").await.unwrap());
+    /// # });
+    /// ```
+    fn from(pairs: [(K, V); N]) -> Self {
+        HashMap::from(pairs).into()
+    }
+}
 
-    use tokio::task::JoinSet;
+impl<K, V, const N: usize> From<[(K, V); N]> for LockerRoomAsync<BTreeMap<K, V>>
+where
+    K: Ord + Clone,
+{
+    /// Creates a `LockerRoomAsync` from a [`BTreeMap`] of the key-value pairs, like [`BTreeMap::from`].
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use lockerroom::LockerRoomAsync;
+    /// # tokio_test::block_on(async {
+    /// let locker_room: LockerRoomAsync<BTreeMap<_, _>> = [("a", 1), ("b", 2)].into();
+    /// assert_eq!(1, *locker_room.read_cell("a").await.unwrap());
+    /// # });
+    /// ```
+    fn from(pairs: [(K, V); N]) -> Self {
+        BTreeMap::from(pairs).into()
+    }
+}
 
-    use super::LockerRoomAsync;
+impl<T> Default for LockerRoomAsync<T>
+where
+    T: Collection + Default,
+{
+    /// Creates a `LockerRoomAsync` from the `Default` value of the collection.
+    fn default() -> Self {
+        T::default().into()
+    }
+}
+
+impl<T> Clone for LockerRoomAsync<T>
+where
+    T: Collection + Clone,
+{
+    /// Creates an independent `LockerRoomAsync` with a clone of the collection.
+    ///
+    /// Whole collection is exclusively locked while it's cloned, like [`lock_room`](Self::lock_room) does, so the clone
+    /// never sees a half-written cell. The clone gets its own shadow locks and all of them are unlocked regardless of
+    /// the state of the source.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if called within an asynchronous execution context, just like
+    /// [`RwLock::blocking_write`](tokio::sync::RwLock::blocking_write). Use [`clone_async`](Self::clone_async) there.
+    fn clone(&self) -> Self {
+        let global_lock_guard = self.global_lock.blocking_write();
+        let collection = unsafe { &*self.collection.get() }.clone();
+        drop(global_lock_guard);
+        let cloned = match self.reader_cap {
+            Some(cap) => Self::with_reader_cap(collection, cap),
+            None => collection.into(),
+        };
+        #[cfg(any(feature = "tracing", doc))]
+        let cloned = Self {
+            fmt_index: self.fmt_index,
+            ..cloned
+        };
+        cloned
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+#[allow(clippy::needless_range_loop, clippy::redundant_pattern_matching)]
+mod test {
+    use std::{
+        collections::{BTreeMap, HashMap, VecDeque},
+        ops::DerefMut,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use tokio::{
+        select,
+        task::{spawn_blocking, JoinSet},
+    };
+
+    use crate::{Indexed, LockState};
+
+    use super::{
+        LockerRoomAsync, MultiReadGuard, MultiWriteGuard, ReadCellGuard, RoomGuard, WriteCellGuard,
+    };
 
     #[test]
     fn t() {
@@ -162,4 +1332,726 @@ mod test {
             assert_eq!(i, v[i + LEN]);
         }
     }
+
+    #[test]
+    fn default() {
+        tokio_test::block_on(async {
+            let locker_room: LockerRoomAsync<Vec<usize>> = Default::default();
+            assert!(locker_room.read_cell(0).await.is_none());
+            locker_room.lock_room().await.push(1);
+            assert_eq!(1, *locker_room.read_cell(0).await.unwrap());
+
+            let locker_room: LockerRoomAsync<HashMap<&str, usize>> = Default::default();
+            assert!(locker_room.read_cell("a").await.is_none());
+            locker_room.lock_room().await.insert("a", 1);
+            assert_eq!(1, *locker_room.read_cell("a").await.unwrap());
+
+            let locker_room: LockerRoomAsync<BTreeMap<&str, usize>> = Default::default();
+            assert!(locker_room.read_cell("a").await.is_none());
+            locker_room.lock_room().await.insert("a", 1);
+            assert_eq!(1, *locker_room.read_cell("a").await.unwrap());
+        });
+    }
+
+    #[test]
+    fn clone() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        let cloned = locker_room.clone();
+        tokio_test::block_on(async {
+            let cloned_async = locker_room.clone_async().await;
+            *locker_room.write_cell(0).await.unwrap() += 10;
+            locker_room.lock_room().await.push(2);
+            let _guard = locker_room.write_cell(1).await.unwrap();
+            assert_eq!(Some(LockState::Unlocked), cloned.cell_lock_state(1).await);
+            *cloned.write_cell(1).await.unwrap() += 10;
+            assert_eq!(vec![0, 1], cloned_async.into_inner());
+        });
+        assert_eq!(vec![0, 11], cloned.into_inner());
+    }
+
+    #[test]
+    fn indices() {
+        tokio_test::block_on(async {
+            let locker_room: LockerRoomAsync<_> = vec![0; 3].into();
+            assert_eq!(vec![0, 1, 2], locker_room.indices().await);
+            locker_room.lock_room().await.push(0);
+            assert_eq!(vec![0, 1, 2, 3], locker_room.indices().await);
+
+            let locker_room: LockerRoomAsync<_> = BTreeMap::from([("a", 0), ("b", 1)]).into();
+            assert_eq!(vec!["a", "b"], locker_room.indices().await);
+        });
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LockerRoomAsync<Vec<i32>>>();
+        assert_send_sync::<ReadCellGuard<Vec<i32>>>();
+        assert_send_sync::<WriteCellGuard<Vec<i32>>>();
+        assert_send_sync::<MultiReadGuard<Vec<i32>>>();
+        assert_send_sync::<MultiWriteGuard<Vec<i32>>>();
+        assert_send_sync::<RoomGuard<Vec<i32>>>();
+    }
+
+    #[test]
+    fn clone_guard() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            let guard = locker_room.read_cell(0).await.unwrap();
+            let cloned = ReadCellGuard::clone_guard(&guard).unwrap();
+            {
+                let mut write = tokio_test::task::spawn(locker_room.write_cell(0));
+                assert!(write.poll().is_pending());
+                assert!(ReadCellGuard::clone_guard(&guard).is_none());
+            }
+            drop(guard);
+            assert_eq!(0, *cloned);
+            assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(0).await);
+            drop(cloned);
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(0).await
+            );
+        });
+    }
+
+    #[test]
+    fn cell_lock_state() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(0).await
+            );
+            let read_guard = locker_room.read_cell(0).await.unwrap();
+            let write_guard = locker_room.write_cell(1).await.unwrap();
+            assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(0).await);
+            assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(1).await);
+            assert_eq!(None, locker_room.cell_lock_state(2).await);
+            drop((read_guard, write_guard));
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(1).await
+            );
+        });
+    }
+
+    #[test]
+    fn multi_cells() {
+        let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0, 1, 2, 3].into());
+        tokio_test::block_on(async {
+            {
+                let mut guard = locker_room.write_cells([3, 1, 3]).await.unwrap();
+                assert_eq!(2, guard.len());
+                assert!(guard.indices().eq(&[1, 3]));
+                for (i, v) in guard.iter_mut() {
+                    *v += i;
+                }
+                *guard.get_mut(1).unwrap() += 1;
+                assert!(guard.get(0).is_none());
+            }
+            let guard = locker_room.read_cells([0, 1, 2, 3]).await.unwrap();
+            let cells: Vec<_> = guard.iter().map(|(i, v)| (*i, *v)).collect();
+            assert_eq!(vec![(0, 0), (1, 3), (2, 2), (3, 6)], cells);
+            drop(guard);
+            assert!(locker_room.write_cells([0, 4]).await.is_none());
+            assert!(locker_room.read_cells([4]).await.is_none());
+
+            let mut join_set = JoinSet::new();
+            for _ in 0..100 {
+                let locker_room_cloned = Arc::clone(&locker_room);
+                join_set.spawn(async move {
+                    for _ in 0..100 {
+                        let mut guard = locker_room_cloned.write_cells([0, 3]).await.unwrap();
+                        *guard.get_mut(0).unwrap() += 1;
+                        *guard.get_mut(3).unwrap() += 1;
+                    }
+                });
+                let locker_room_cloned = Arc::clone(&locker_room);
+                join_set.spawn(async move {
+                    for _ in 0..100 {
+                        let mut guard = locker_room_cloned.write_cells([3, 0]).await.unwrap();
+                        *guard.get_mut(3).unwrap() += 2;
+                        *guard.get_mut(0).unwrap() += 2;
+                    }
+                });
+            }
+            while join_set.join_next().await.is_some() {}
+            assert_eq!(30000, *locker_room.read_cell(0).await.unwrap());
+            assert_eq!(30006, *locker_room.read_cell(3).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn try_write_cells() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1, 2, 3].into();
+        tokio_test::block_on(async {
+            let blocker = locker_room.read_cell(2).await.unwrap();
+            // Cells 0 and 1 are locked before 2 is found held and must be released.
+            assert!(locker_room.try_write_cells([3, 0, 2, 1]).is_none());
+            for index in [0, 1, 3] {
+                assert_eq!(
+                    Some(LockState::Unlocked),
+                    locker_room.cell_lock_state(index).await
+                );
+            }
+            drop(blocker);
+            assert!(locker_room.try_write_cells([0, 4]).is_none());
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(0).await
+            );
+            {
+                let mut guard = locker_room.try_write_cells([3, 1, 3]).unwrap();
+                assert_eq!(2, guard.len());
+                *guard.get_mut(3).unwrap() += 1;
+                assert!(locker_room.try_write_cells([1]).is_none());
+            }
+            let room_guard = locker_room.lock_room().await;
+            assert!(locker_room.try_write_cells([0]).is_none());
+            drop(room_guard);
+        });
+        assert_eq!(vec![0, 1, 2, 4], locker_room.into_inner());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn write_cell_timeout() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            let timeout = std::time::Duration::from_millis(10);
+            let room_guard = locker_room.lock_room().await;
+            assert!(locker_room.write_cell_timeout(0, timeout).await.is_err());
+            drop(room_guard);
+            // The timed out call doesn't keep the lock of whole collection.
+            assert!(locker_room.global_lock.try_write().is_ok());
+            *locker_room
+                .write_cell_timeout(0, timeout)
+                .await
+                .unwrap()
+                .unwrap() += 1;
+            assert!(locker_room
+                .write_cell_timeout(2, timeout)
+                .await
+                .unwrap()
+                .is_none());
+        });
+        assert_eq!(vec![1, 1], locker_room.into_inner());
+    }
+
+    #[test]
+    fn room_cell() {
+        let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+        tokio_test::block_on(async {
+            let mut room = locker_room.lock_room().await;
+            *room.write_cell_mut(0).unwrap() += room.read_cell(1).copied().unwrap();
+            assert!(room.read_cell(2).is_none());
+        });
+        assert_eq!(vec![3, 2], locker_room.into_inner());
+    }
+
+    #[test]
+    fn cancel_safety() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            let write_guard = locker_room.write_cell(0).await.unwrap();
+            select! {
+                biased;
+                _ = locker_room.read_cell(0) => unreachable!(),
+                _ = async {} => {}
+            }
+            select! {
+                biased;
+                _ = locker_room.write_cells([1, 0]) => unreachable!(),
+                _ = async {} => {}
+            }
+            drop(write_guard);
+            assert!(locker_room.global_lock.try_write().is_ok());
+            select! {
+                biased;
+                guard = locker_room.write_cells([0, 1]) => assert!(guard.is_some()),
+                _ = async {} => panic!("cell locks weren't released"),
+            }
+
+            let room_guard = locker_room.lock_room().await;
+            select! {
+                biased;
+                _ = locker_room.write_cell(0) => unreachable!(),
+                _ = async {} => {}
+            }
+            drop(room_guard);
+            assert!(locker_room.global_lock.try_write().is_ok());
+        });
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn write_cells_stream() {
+        use futures_util::StreamExt;
+
+        let locker_room: LockerRoomAsync<_> = vec![0, 1, 2].into();
+        tokio_test::block_on(async {
+            let stream = locker_room.write_cells_stream([2, 5, 0]);
+            let indices: Vec<_> = stream
+                .map(|(index, mut guard)| {
+                    *guard += 10;
+                    index
+                })
+                .collect()
+                .await;
+            assert_eq!(vec![2, 0], indices);
+            assert_eq!(vec![10, 1, 12], locker_room.lock_room().await.clone());
+        });
+    }
+
+    #[test]
+    fn swap_cells() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1, 2].into();
+        tokio_test::block_on(async {
+            assert!(locker_room.swap_cells(2, 0).await);
+            assert!(locker_room.swap_cells(1, 1).await);
+            assert!(!locker_room.swap_cells(1, 3).await);
+            assert!(!locker_room.swap_cells(3, 3).await);
+        });
+        assert_eq!(vec![2, 1, 0], locker_room.into_inner());
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let locker_room: Arc<LockerRoomAsync<HashMap<usize, usize>>> = Default::default();
+        tokio_test::block_on(async {
+            let mut join_set = JoinSet::new();
+            for _ in 0..8 {
+                let locker_room_cloned = Arc::clone(&locker_room);
+                join_set.spawn(async move {
+                    for i in 0..100 {
+                        *locker_room_cloned.get_or_insert_with(i % 10, || 0).await += 1;
+                    }
+                });
+            }
+            while join_set.join_next().await.is_some() {}
+        });
+        let map = Arc::into_inner(locker_room).unwrap().into_inner();
+        assert_eq!(HashMap::from_iter((0..10).map(|i| (i, 80))), map);
+    }
+
+    #[test]
+    fn get_set_cell() {
+        let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![(0, 0); 4].into());
+        tokio_test::block_on(async {
+            let mut join_set = JoinSet::new();
+            for i in 1..=4 {
+                let locker_room_cloned = Arc::clone(&locker_room);
+                join_set.spawn(async move {
+                    for j in 0..1000 {
+                        assert!(locker_room_cloned.set_cell(j % 4, (i * j, i * j)).await);
+                    }
+                });
+            }
+            let locker_room_cloned = Arc::clone(&locker_room);
+            join_set.spawn(async move {
+                for j in 0..4000 {
+                    let (a, b) = locker_room_cloned.get_cell(j % 4).await.unwrap();
+                    assert_eq!(a, b);
+                }
+            });
+            while let Some(result) = join_set.join_next().await {
+                result.unwrap();
+            }
+            assert!(!locker_room.set_cell(4, (1, 1)).await);
+            assert_eq!(None, locker_room.get_cell(4).await);
+            // No guard outlives the calls.
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(0).await
+            );
+        });
+    }
+
+    #[test]
+    fn wait_write_cell() {
+        let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0; 2].into());
+        tokio_test::block_on(async {
+            let mut join_set = JoinSet::new();
+            for i in 0..2 {
+                let locker_room_cloned = Arc::clone(&locker_room);
+                join_set.spawn(async move {
+                    // Every consumer takes the value once the producer has put it.
+                    for round in 1..=100 {
+                        let mut guard = locker_room_cloned
+                            .wait_write_cell(i, |value| *value == round)
+                            .await
+                            .unwrap();
+                        *guard = -round;
+                        drop(guard);
+                        locker_room_cloned.notify_cell(i);
+                    }
+                });
+            }
+            for round in 1..=100 {
+                for i in 0..2 {
+                    let mut guard = locker_room
+                        .wait_write_cell(i, |value| *value == 1 - round)
+                        .await
+                        .unwrap();
+                    *guard = round;
+                    drop(guard);
+                    locker_room.notify_cell(i);
+                }
+            }
+            while let Some(result) = join_set.join_next().await {
+                result.unwrap();
+            }
+            assert_eq!(Some(-100), locker_room.get_cell(1).await);
+            assert!(locker_room.wait_write_cell(2, |_| true).await.is_none());
+        });
+    }
+
+    #[test]
+    fn notify_cell() {
+        let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0; 2].into());
+        let checks = Arc::new(AtomicUsize::new(0));
+        tokio_test::block_on(async {
+            let waiter = tokio::spawn({
+                let locker_room = Arc::clone(&locker_room);
+                let checks = Arc::clone(&checks);
+                async move {
+                    let guard = locker_room
+                        .wait_write_cell(0, |value| {
+                            checks.fetch_add(1, Ordering::SeqCst);
+                            *value == 1
+                        })
+                        .await;
+                    assert_eq!(1, *guard.unwrap());
+                }
+            });
+            while checks.load(Ordering::SeqCst) == 0 {
+                tokio::task::yield_now().await;
+            }
+            // The waiter is registered before its first check, so it would be woken by now.
+            *locker_room.write_cell(1).await.unwrap() = 1;
+            locker_room.notify_cell(1);
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(1, checks.load(Ordering::SeqCst));
+            *locker_room.write_cell(0).await.unwrap() = 1;
+            locker_room.notify_cell(0);
+            waiter.await.unwrap();
+        });
+        assert_eq!(2, checks.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn with_ref_mut() {
+        let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0; 4].into());
+        tokio_test::block_on(async {
+            let mut join_set = JoinSet::new();
+            for i in 0..4 {
+                let locker_room_cloned = Arc::clone(&locker_room);
+                join_set.spawn(async move {
+                    for _ in 0..100 {
+                        *locker_room_cloned.write_cell(i).await.unwrap() += 1;
+                        let max = locker_room_cloned
+                            .with_ref(|v| *v.iter().max().unwrap())
+                            .await;
+                        assert!(max <= 100);
+                    }
+                });
+            }
+            while let Some(result) = join_set.join_next().await {
+                result.unwrap();
+            }
+            assert_eq!(400, locker_room.with_ref(|v| v.iter().sum::<i32>()).await);
+            locker_room.with_mut(|v| v.push(0)).await;
+            *locker_room.write_cell(4).await.unwrap() += 1;
+            assert_eq!(Some(1), locker_room.get_cell(4).await);
+        });
+    }
+
+    #[test]
+    fn update_cell() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            assert!(locker_room.update_cell(1, |v| *v += 1).await);
+            assert!(!locker_room.update_cell(2, |v| *v += 1).await);
+            assert_eq!(Some(4), locker_room.modify_cell(1, |v| *v * 2).await);
+            assert_eq!(None, locker_room.modify_cell(2, |v| *v * 2).await);
+        });
+        assert_eq!(vec![0, 2], locker_room.into_inner());
+    }
+
+    #[test]
+    fn cell_expect() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            *locker_room.write_cell_expect(1).await += 1;
+            assert_eq!(2, *locker_room.read_cell_expect(1).await);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "no cell at index 2")]
+    fn read_cell_expect_missing() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            let _ = locker_room.read_cell_expect(2).await;
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "no cell at index 2")]
+    fn write_cell_expect_missing() {
+        let locker_room: LockerRoomAsync<_> = vec![0, 1].into();
+        tokio_test::block_on(async {
+            let _ = locker_room.write_cell_expect(2).await;
+        });
+    }
+
+    #[test]
+    fn project() {
+        let locker_room: LockerRoomAsync<_> = HashMap::from([("a", 0), ("b", 1), ("c", 2)]).into();
+        tokio_test::block_on(async {
+            let writer = async {
+                for _ in 0..100 {
+                    *locker_room.write_cell("b").await.unwrap() += 1;
+                    tokio::task::yield_now().await;
+                }
+            };
+            let reader = async {
+                for _ in 0..100 {
+                    let projected = locker_room.project(["c", "a", "d"]).await;
+                    assert_eq!(HashMap::from([("a", 0), ("c", 2)]), projected);
+                    tokio::task::yield_now().await;
+                }
+            };
+            tokio::join!(writer, reader);
+            assert_eq!(Some(101), locker_room.get_cell("b").await);
+        });
+    }
+
+    #[test]
+    fn lock_room_owned() {
+        tokio_test::block_on(async {
+            let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0, 1].into());
+            let mut room = locker_room.lock_room_owned().await;
+            let maintenance = tokio::spawn(async move {
+                room.resize(4, 10);
+                *room.write_cell_mut(0).unwrap() = 5;
+            });
+            maintenance.await.unwrap();
+            *locker_room.write_cell(3).await.unwrap() += 1;
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(2).await
+            );
+            assert_eq!(
+                Some(vec![5, 1, 10, 11]),
+                LockerRoomAsync::try_into_inner_arc(locker_room).ok()
+            );
+        });
+    }
+
+    #[test]
+    fn write_cell_with_previous() {
+        let locker_room: LockerRoomAsync<_> = vec![String::from("a")].into();
+        tokio_test::block_on(async {
+            let (previous, mut cell) = locker_room.write_cell_with_previous(0).await.unwrap();
+            cell.push('b');
+            assert_eq!(("a", "ab"), (previous.as_str(), cell.as_str()));
+            drop(cell);
+            assert!(locker_room.write_cell_with_previous(1).await.is_none());
+        });
+        assert_eq!(vec![String::from("ab")], locker_room.into_inner());
+    }
+
+    #[test]
+    fn deque_ends() {
+        let locker_room: LockerRoomAsync<_> = VecDeque::from([1, 2]).into();
+        tokio_test::block_on(async {
+            let mut room = locker_room.lock_room().await;
+            room.push_front(0);
+            room.push_back(3);
+            assert_eq!(Some(3), room.pop_back());
+            assert_eq!(Some(0), room.pop_front());
+            room.push_front(-1);
+            room.insert(1, 0);
+            assert_eq!(Some(1), room.remove(2));
+            room.rotate_left(1);
+            room.rotate_right(2);
+            drop(room);
+            *locker_room.write_cell(0).await.unwrap() += 1;
+            assert!(locker_room.read_cell(3).await.is_none());
+        });
+        assert_eq!(VecDeque::from([3, -1, 0]), locker_room.into_inner());
+    }
+
+    #[test]
+    fn write_where() {
+        let locker_room: LockerRoomAsync<_> = vec![1, 2, 3, 4, 5].into();
+        tokio_test::block_on(async {
+            let calls = AtomicUsize::new(0);
+            let pred = |&i: &usize, _: &i32| {
+                i >= 3 && calls.fetch_add(1, Ordering::Relaxed).is_multiple_of(2)
+            };
+            assert_eq!(0, locker_room.write_where_checked(pred, |v| *v = 0).await);
+            assert_eq!(
+                2,
+                locker_room.write_where(|&i, _| i >= 3, |v| *v *= 10).await
+            );
+        });
+        assert_eq!(vec![1, 2, 3, 40, 50], locker_room.into_inner());
+    }
+
+    #[test]
+    fn write_cell_if() {
+        let locker_room: LockerRoomAsync<_> = vec![1, 5].into();
+        tokio_test::block_on(async {
+            let inc_odd = |v: &mut i32| {
+                let odd = *v % 2 == 1;
+                *v += odd as i32;
+                odd
+            };
+            assert_eq!(Some(true), locker_room.write_cell_if(0, inc_odd).await);
+            assert_eq!(Some(false), locker_room.write_cell_if(0, inc_odd).await);
+            assert_eq!(None, locker_room.write_cell_if(2, inc_odd).await);
+        });
+        assert_eq!(vec![2, 5], locker_room.into_inner());
+    }
+
+    #[test]
+    fn blocking() {
+        let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![0, 1].into());
+        tokio_test::block_on(async {
+            let locker_room_cloned = Arc::clone(&locker_room);
+            spawn_blocking(move || {
+                *locker_room_cloned.blocking_write_cell(0).unwrap() += 1;
+                assert!(locker_room_cloned.blocking_write_cell(2).is_none());
+                assert_eq!(1, *locker_room_cloned.blocking_read_cell(0).unwrap());
+            })
+            .await
+            .unwrap();
+            assert_eq!(1, *locker_room.read_cell(0).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn reader_cap_lets_writer_through() {
+        const READERS: usize = 64;
+        const CAP: u32 = 4;
+        let locker_room = Arc::new(LockerRoomAsync::with_reader_cap(vec![0], CAP));
+        let reads = Arc::new(AtomicUsize::new(0));
+        tokio_test::block_on(async {
+            let mut join_set = JoinSet::new();
+            for _ in 0..READERS {
+                let locker_room = Arc::clone(&locker_room);
+                let reads = Arc::clone(&reads);
+                join_set.spawn(async move {
+                    while *locker_room.read_cell(0).await.unwrap() == 0 {
+                        reads.fetch_add(1, Ordering::Relaxed);
+                        tokio::task::yield_now().await;
+                    }
+                });
+            }
+            tokio::task::yield_now().await;
+            let reads_before = reads.load(Ordering::Relaxed);
+            *locker_room.write_cell(0).await.unwrap() = 1;
+            // Every reader waits behind the writer after at most one more read.
+            let reads_waited = reads.load(Ordering::Relaxed) - reads_before;
+            assert!(
+                reads_waited <= READERS,
+                "writer waited for {reads_waited} reads"
+            );
+            while join_set.join_next().await.is_some() {}
+        });
+    }
+
+    #[test]
+    fn reader_cap_per_cell() {
+        let locker_room = LockerRoomAsync::with_reader_cap(vec![0, 1], 2);
+        tokio_test::block_on(async {
+            let guards = [
+                locker_room.read_cell(0).await.unwrap(),
+                locker_room.read_cell(0).await.unwrap(),
+            ];
+            let mut third = tokio_test::task::spawn(locker_room.read_cell(0));
+            assert!(third.poll().is_pending());
+            // Readers of cell 1 don't count against the cap of cell 0.
+            let others = [
+                locker_room.read_cell(1).await.unwrap(),
+                locker_room.read_cell(1).await.unwrap(),
+            ];
+            drop((guards, others));
+            assert!(matches!(third.poll(), std::task::Poll::Ready(Some(_))));
+            drop(third);
+
+            // Inserted cells are capped too, and so are the cells of a clone.
+            locker_room.lock_room().await.push(2);
+            let cloned = locker_room.clone_async().await;
+            for locker_room in [&locker_room, &cloned] {
+                let guards = [
+                    locker_room.read_cell(2).await.unwrap(),
+                    locker_room.read_cell(2).await.unwrap(),
+                ];
+                assert!(tokio_test::task::spawn(locker_room.read_cell(2))
+                    .poll()
+                    .is_pending());
+                drop(guards);
+            }
+        });
+    }
+
+    #[test]
+    fn indexed() {
+        #[derive(Clone, Copy)]
+        struct RowId(usize);
+        impl From<usize> for RowId {
+            fn from(id: usize) -> Self {
+                Self(id)
+            }
+        }
+        impl From<RowId> for usize {
+            fn from(id: RowId) -> Self {
+                id.0
+            }
+        }
+
+        let locker_room: LockerRoomAsync<Indexed<_, RowId>> = Indexed::new(vec![1, 2]).into();
+        tokio_test::block_on(async {
+            *locker_room.write_cell(RowId(1)).await.unwrap() += 1;
+            assert!(locker_room.read_cell(RowId(2)).await.is_none());
+            locker_room.lock_room().await.push(4);
+            assert_eq!(4, *locker_room.read_cell(RowId(2)).await.unwrap());
+        });
+        assert_eq!(vec![1, 3, 4], locker_room.into_inner().into_inner());
+    }
+
+    #[test]
+    fn resize_truncate() {
+        let locker_room: LockerRoomAsync<_> = vec![1, 2].into();
+        tokio_test::block_on(async {
+            locker_room.resize(4, 0).await;
+            assert_eq!(4, unsafe { &*locker_room.index_locks.get() }.len());
+            *locker_room.write_cell(3).await.unwrap() = 4;
+            locker_room.truncate(1).await;
+            assert_eq!(1, unsafe { &*locker_room.index_locks.get() }.len());
+            assert!(locker_room.read_cell(1).await.is_none());
+        });
+        assert_eq!(vec![1], locker_room.into_inner());
+    }
+
+    #[test]
+    #[should_panic]
+    fn reader_cap_positive() {
+        let _ = LockerRoomAsync::with_reader_cap(vec![0], 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn blocking_in_async_context() {
+        let locker_room: LockerRoomAsync<_> = vec![0].into();
+        tokio_test::block_on(async {
+            let _guard = locker_room.blocking_read_cell(0);
+        });
+    }
 }
@@ -1,7 +1,12 @@
 //! `LockerRoomAsync` and its necessary types.
 
 mod guard;
+mod handle;
 mod locker_room;
+mod notify;
+mod shared;
 
 pub use guard::*;
+pub use handle::CellHandle;
 pub use locker_room::LockerRoomAsync;
+pub use shared::{OwnedRoomGuard, SharedLockerRoomAsync};
@@ -0,0 +1,70 @@
+//! Wake-ups of tasks waiting for a condition on a cell.
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::sync::Notify;
+
+/// [`Notify`]s of cells for [`wait_write_cell`](super::LockerRoomAsync::wait_write_cell), keyed by hashes of their
+/// indices.
+///
+/// A cell gets its `Notify` while some task waits for it, so a notification wakes only the waiters of that cell, or of
+/// a cell whose index has the same hash.
+#[derive(Default)]
+pub(crate) struct Notifier {
+    cells: Mutex<HashMap<u64, (Arc<Notify>, usize)>>,
+}
+
+impl Notifier {
+    fn key(index: &impl Hash) -> u64 {
+        BuildHasherDefault::<DefaultHasher>::default().hash_one(index)
+    }
+
+    // Never held across an await, so it's a blocking mutex.
+    fn cells(&self) -> MutexGuard<'_, HashMap<u64, (Arc<Notify>, usize)>> {
+        self.cells.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Registers the current task as a waiter of the cell at the index.
+    pub(crate) fn register(&self, index: &impl Hash) -> Registration<'_> {
+        let key = Self::key(index);
+        let mut cells = self.cells();
+        let (notify, count) = cells.entry(key).or_default();
+        *count += 1;
+        Registration {
+            notifier: self,
+            key,
+            notify: notify.clone(),
+        }
+    }
+
+    pub(crate) fn notify(&self, index: &impl Hash) {
+        if let Some((notify, _)) = self.cells().get(&Self::key(index)) {
+            notify.notify_waiters();
+        }
+    }
+}
+
+/// Waiter of a cell, which is unregistered once dropped.
+pub(crate) struct Registration<'a> {
+    notifier: &'a Notifier,
+    key: u64,
+    /// Woken by notifications of the cell.
+    pub(crate) notify: Arc<Notify>,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut cells = self.notifier.cells();
+        let (_, count) = cells
+            .get_mut(&self.key)
+            .expect("registered cell has waiters");
+        *count -= 1;
+        if *count == 0 {
+            cells.remove(&self.key);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use crate::Collection;
+
+use super::{LockerRoomAsync, RoomGuard};
+
+/// A [`LockerRoomAsync`] behind an [`Arc`], so it can be cloned cheaply and every clone can be moved to another task.
+///
+/// It packages the common `Arc<LockerRoomAsync<T>>` pattern: clones share the same collection and dereference to the
+/// `LockerRoomAsync`.
+/// ```
+/// # use lockerroom::r#async::SharedLockerRoomAsync;
+/// # tokio_test::block_on(async {
+/// let locker_room = SharedLockerRoomAsync::new(vec![0; 4]);
+/// let handles: Vec<_> = (0..4)
+///     .map(|i| {
+///         let locker_room = locker_room.clone();
+///         tokio::spawn(async move { *locker_room.write_cell(i).await.unwrap() += i })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.await.unwrap();
+/// }
+/// assert_eq!(Some(vec![0, 1, 2, 3]), locker_room.try_into_inner().ok());
+/// # });
+/// ```
+pub struct SharedLockerRoomAsync<T>
+where
+    T: Collection,
+{
+    locker_room: Arc<LockerRoomAsync<T>>,
+}
+
+impl<T> SharedLockerRoomAsync<T>
+where
+    T: Collection,
+{
+    /// Creates a `SharedLockerRoomAsync` of the collection.
+    pub fn new(collection: T) -> Self {
+        LockerRoomAsync::from(collection).into()
+    }
+
+    /// Returns the underlying data if this is the only clone, or gives the `SharedLockerRoomAsync` back otherwise.
+    /// See [`LockerRoomAsync::try_into_inner_arc`].
+    pub fn try_into_inner(self) -> Result<T, Self> {
+        LockerRoomAsync::try_into_inner_arc(self.locker_room)
+            .map_err(|locker_room| Self { locker_room })
+    }
+
+    /// Returns the shared `Arc` of the `LockerRoomAsync`.
+    pub fn into_arc(self) -> Arc<LockerRoomAsync<T>> {
+        self.locker_room
+    }
+}
+
+impl<T> Clone for SharedLockerRoomAsync<T>
+where
+    T: Collection,
+{
+    /// Makes another handle of the same `LockerRoomAsync`. Unlike [`LockerRoomAsync::clone`] it doesn't copy
+    /// the collection.
+    fn clone(&self) -> Self {
+        Self {
+            locker_room: Arc::clone(&self.locker_room),
+        }
+    }
+}
+
+impl<T> Deref for SharedLockerRoomAsync<T>
+where
+    T: Collection,
+{
+    type Target = LockerRoomAsync<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.locker_room
+    }
+}
+
+impl<T> From<LockerRoomAsync<T>> for SharedLockerRoomAsync<T>
+where
+    T: Collection,
+{
+    fn from(locker_room: LockerRoomAsync<T>) -> Self {
+        Arc::new(locker_room).into()
+    }
+}
+
+impl<T> From<Arc<LockerRoomAsync<T>>> for SharedLockerRoomAsync<T>
+where
+    T: Collection,
+{
+    fn from(locker_room: Arc<LockerRoomAsync<T>>) -> Self {
+        Self { locker_room }
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a whole collection lock when dropped, which owns
+/// an [`Arc`] of the [`LockerRoomAsync`], so it can be moved into a spawned task.
+///
+/// Like [`RoomGuard`], it updates the shadow locks to the indices of the collection once it is dropped.
+///
+/// This structure is created by the [`lock_room_owned`](LockerRoomAsync::lock_room_owned) method on
+/// [`LockerRoomAsync`].
+/// ```
+/// # use std::sync::Arc;
+/// # use lockerroom::LockerRoomAsync;
+/// # tokio_test::block_on(async {
+/// let locker_room: Arc<LockerRoomAsync<_>> = Arc::new(vec![1, 2, 3].into());
+/// let mut room = locker_room.lock_room_owned().await;
+/// tokio::spawn(async move { room.retain(|v| v % 2 == 1) }).await.unwrap();
+/// assert_eq!(Some(3), locker_room.get_cell(1).await);
+/// assert_eq!(None, locker_room.get_cell(2).await);
+/// # });
+/// ```
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct OwnedRoomGuard<T>
+where
+    T: Collection + 'static,
+{
+    // Borrows from `locker_room`, so it's dropped before it.
+    guard: ManuallyDrop<RoomGuard<'static, T>>,
+    #[allow(dead_code)]
+    locker_room: Arc<LockerRoomAsync<T>>,
+}
+
+impl<T> OwnedRoomGuard<T>
+where
+    T: Collection + 'static,
+{
+    pub(crate) async fn new(locker_room: Arc<LockerRoomAsync<T>>) -> Self {
+        // SAFETY: the `LockerRoomAsync` doesn't move and lives as long as `locker_room`, which outlives the guard.
+        let borrowed: &'static LockerRoomAsync<T> = unsafe { &*Arc::as_ptr(&locker_room) };
+        let guard = borrowed.lock_room().await;
+        Self {
+            guard: ManuallyDrop::new(guard),
+            locker_room,
+        }
+    }
+}
+
+impl<T> Deref for OwnedRoomGuard<T>
+where
+    T: Collection + 'static,
+{
+    type Target = RoomGuard<'static, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for OwnedRoomGuard<T>
+where
+    T: Collection + 'static,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for OwnedRoomGuard<T>
+where
+    T: Collection + 'static,
+{
+    fn drop(&mut self) {
+        // SAFETY: the guard is dropped once, here, while `locker_room` still keeps the `LockerRoomAsync` alive.
+        // Dropping it updates the shadow locks before the lock of whole collection is released.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+    }
+}
@@ -0,0 +1,72 @@
+//! Locks shared by blocks of consecutive cells.
+
+use std::borrow::Borrow;
+
+use crate::{primitive::RwLock, ShadowLock, ShadowLocksCollection};
+
+/// Locks of index-based collections where every lock is shared by a block of `block_size` consecutive cells:
+/// cell `i` is guarded by the lock of block `i / block_size`.
+///
+/// It takes fewer locks and keeps them closer in memory than a lock per cell, which pays off when neighbouring
+/// cells are accessed together. The price is coarser granularity: two cells of the same block can't be locked
+/// with exclusive write access concurrently, nor can one of them be written while the other is read. So holding
+/// a guard of one cell while locking another cell of the same block deadlocks. Locking several cells at once, e.g.
+/// with [`write_cells`](crate::LockerRoom::write_cells), locks the lock of a block once for all its cells instead.
+///
+/// Created by [`LockerRoom::with_block_size`](crate::LockerRoom::with_block_size).
+pub struct BlockLocks<L = RwLock<()>> {
+    block_size: usize,
+    len: usize,
+    locks: Vec<L>,
+}
+
+impl<L: Default> BlockLocks<L> {
+    /// Creates locks for no cells with `block_size` cells per lock.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `block_size` is zero.
+    pub fn new(block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must be positive");
+        Self {
+            block_size,
+            len: 0,
+            locks: Vec::new(),
+        }
+    }
+
+    /// Returns the number of cells guarded by every lock.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+}
+
+impl<L> ShadowLocksCollection for BlockLocks<L>
+where
+    L: ShadowLock,
+{
+    type Idx = usize;
+    type Lock = L;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
+        let index = *index.borrow();
+        if index >= self.len {
+            return None;
+        }
+        self.locks.get(index / self.block_size)
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.len = indices.count();
+        self.locks
+            .resize_with(self.len.div_ceil(self.block_size), L::default);
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.locks.shrink_to_fit();
+    }
+}
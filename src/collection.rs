@@ -3,11 +3,18 @@
 
 use std::{
     borrow::Borrow,
-    collections::{BTreeMap, HashMap, VecDeque},
-    hash::Hash,
-    sync::RwLock,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, Hash},
+    iter, mem, ptr,
 };
 
+use crate::{primitive::RwLock, ShadowLock};
+
+// Creates locks for index-based collections without walking their indices.
+fn new_locks<L: Default, C: FromIterator<L>>(count: usize) -> C {
+    iter::repeat_with(L::default).take(count).collect()
+}
+
 /// Trait describes functionality of collection that necessary for creating [`LockerRoom`](crate::LockerRoom)
 /// and [`LockerRoomAsync`](crate::LockerRoomAsync).
 pub trait Collection {
@@ -33,6 +40,11 @@ pub trait Collection {
     /// Performs the mutable indexing operation. But unlike the [`IndexMut::index_mut`](std::ops::IndexMut::index_mut), it doesn't panic, and return None.
     fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output>;
     /// An iterator visiting all indices.
+    ///
+    /// It must yield every index exactly once, and only indices at which [`index`](Self::index) returns a cell.
+    /// Otherwise shadow locks may be miscounted, e.g. a `Vec` of locks gets a lock per yielded index. Debug builds
+    /// check the number of locks when a `LockerRoom` is created, and [`LockerRoom::validate`](crate::LockerRoom::validate)
+    /// checks the whole contract on demand.
     fn indices(&self) -> impl Iterator<Item = Self::Idx>;
     /// Creates collection which stores [`RwLock`]s.
     ///
@@ -44,6 +56,32 @@ pub trait Collection {
     ///
     /// Used in [`LockerRoomAsync`](crate::LockerRoomAsync).
     fn shadow_locks_async(&self) -> Self::ShadowLocksAsync;
+    /// Shrinks the capacity of the collection as much as possible. Does nothing by default.
+    ///
+    /// Used by [`RoomGuard::shrink_to_fit`](crate::sync::RoomGuard::shrink_to_fit).
+    fn shrink_to_fit(&mut self) {}
+
+    /// Cells of hash maps are looked up by the hash of the index in both the collection and its shadow locks.
+    /// Maps which can look up by a precomputed hash return it here, so `read_cell` and `write_cell` hash the
+    /// index once. Returns `None` by default.
+    #[doc(hidden)]
+    fn hash_index(&self, _index: &Self::Idx) -> Option<u64> {
+        None
+    }
+    /// Like [`index`](Self::index), but may reuse the hash returned by [`hash_index`](Self::hash_index).
+    #[doc(hidden)]
+    fn index_hashed(&self, index: &Self::Idx, _hash: Option<u64>) -> Option<&Self::Output> {
+        self.index(index)
+    }
+    /// Like [`index_mut`](Self::index_mut), but may reuse the hash returned by [`hash_index`](Self::hash_index).
+    #[doc(hidden)]
+    fn index_mut_hashed(
+        &mut self,
+        index: &Self::Idx,
+        _hash: Option<u64>,
+    ) -> Option<&mut Self::Output> {
+        self.index_mut(index)
+    }
 }
 
 impl<T> Collection for [T] {
@@ -67,15 +105,47 @@ impl<T> Collection for [T] {
     }
 
     fn shadow_locks(&self) -> Self::ShadowLocks {
-        self.indices().map(|_| RwLock::new(())).collect::<Vec<_>>()
+        new_locks(self.len())
     }
 
     #[cfg(any(feature = "async", doc))]
     #[doc(cfg(feature = "async"))]
     fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
-        self.indices()
-            .map(|_| tokio::sync::RwLock::new(()))
-            .collect::<Vec<_>>()
+        new_locks(self.len())
+    }
+}
+
+/// Boxed slices can't change their length, so the cells are fixed like those of an array, but the length is chosen
+/// at runtime. Shared slices like `Arc<[T]>` can't be collections, since cells are written through `&mut`; share
+/// the `LockerRoom` instead, e.g. as [`SharedSlice`](crate::sync::SharedSlice).
+impl<T> Collection for Box<[T]> {
+    type Idx = usize;
+    type Output = T;
+    type ShadowLocks = Vec<RwLock<()>>;
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    type ShadowLocksAsync = Vec<tokio::sync::RwLock<()>>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
+        self.get(*index.borrow())
+    }
+
+    fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output> {
+        self.get_mut(*index.borrow())
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Self::Idx> {
+        0..self.len()
+    }
+
+    fn shadow_locks(&self) -> Self::ShadowLocks {
+        new_locks(self.len())
+    }
+
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+        new_locks(self.len())
     }
 }
 
@@ -100,15 +170,13 @@ impl<T, const N: usize> Collection for [T; N] {
     }
 
     fn shadow_locks(&self) -> Self::ShadowLocks {
-        self.indices().map(|_| RwLock::new(())).collect::<Vec<_>>()
+        new_locks(self.len())
     }
 
     #[cfg(any(feature = "async", doc))]
     #[doc(cfg(feature = "async"))]
     fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
-        self.indices()
-            .map(|_| tokio::sync::RwLock::new(()))
-            .collect::<Vec<_>>()
+        new_locks(self.len())
     }
 }
 
@@ -132,16 +200,18 @@ impl<T> Collection for Vec<T> {
         0..self.len()
     }
 
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self);
+    }
+
     fn shadow_locks(&self) -> Self::ShadowLocks {
-        self.indices().map(|_| RwLock::new(())).collect::<Vec<_>>()
+        new_locks(self.len())
     }
 
     #[cfg(any(feature = "async", doc))]
     #[doc(cfg(feature = "async"))]
     fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
-        self.indices()
-            .map(|_| tokio::sync::RwLock::new(()))
-            .collect::<Vec<_>>()
+        new_locks(self.len())
     }
 }
 
@@ -165,31 +235,34 @@ impl<T> Collection for VecDeque<T> {
         0..self.len()
     }
 
+    fn shrink_to_fit(&mut self) {
+        VecDeque::shrink_to_fit(self);
+    }
+
     fn shadow_locks(&self) -> Self::ShadowLocks {
-        self.indices()
-            .map(|_| RwLock::new(()))
-            .collect::<VecDeque<_>>()
+        new_locks(self.len())
     }
 
     #[cfg(any(feature = "async", doc))]
     #[doc(cfg(feature = "async"))]
     fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
-        self.indices()
-            .map(|_| tokio::sync::RwLock::new(()))
-            .collect::<VecDeque<_>>()
+        new_locks(self.len())
     }
 }
 
-impl<K, V> Collection for HashMap<K, V>
+/// The shadow locks are stored in a [`HashMap`] with a clone of the hasher of the collection.
+#[allow(clippy::needless_maybe_sized)]
+impl<K, V, H> Collection for HashMap<K, V, H>
 where
     K: Eq + Hash + Clone + ?Sized,
+    H: BuildHasher + Clone,
 {
     type Idx = K;
     type Output = V;
-    type ShadowLocks = HashMap<Self::Idx, RwLock<()>>;
+    type ShadowLocks = HashMap<Self::Idx, RwLock<()>, H>;
     #[cfg(any(feature = "async", doc))]
     #[doc(cfg(feature = "async"))]
-    type ShadowLocksAsync = HashMap<Self::Idx, tokio::sync::RwLock<()>>;
+    type ShadowLocksAsync = HashMap<Self::Idx, tokio::sync::RwLock<()>, H>;
 
     fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
         self.get(index.borrow())
@@ -203,21 +276,29 @@ where
         self.keys().cloned()
     }
 
+    fn shrink_to_fit(&mut self) {
+        HashMap::shrink_to_fit(self);
+    }
+
     fn shadow_locks(&self) -> Self::ShadowLocks {
-        self.indices()
-            .map(|index| (index, RwLock::new(())))
-            .collect::<HashMap<_, _>>()
+        let mut shadow_locks = HashMap::with_capacity_and_hasher(self.len(), self.hasher().clone());
+        shadow_locks.extend(self.indices().map(|index| (index, RwLock::new(()))));
+        shadow_locks
     }
 
     #[cfg(any(feature = "async", doc))]
     #[doc(cfg(feature = "async"))]
     fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
-        self.indices()
-            .map(|index| (index, tokio::sync::RwLock::new(())))
-            .collect::<HashMap<_, _>>()
+        let mut shadow_locks = HashMap::with_capacity_and_hasher(self.len(), self.hasher().clone());
+        shadow_locks.extend(
+            self.indices()
+                .map(|index| (index, tokio::sync::RwLock::new(()))),
+        );
+        shadow_locks
     }
 }
 
+#[allow(clippy::needless_maybe_sized)]
 impl<K, V> Collection for BTreeMap<K, V>
 where
     K: Ord + Clone + ?Sized,
@@ -256,70 +337,622 @@ where
     }
 }
 
+/// Cells of the slab are indexed by its keys, which stay stable as entries are inserted and removed.
+///
+/// Keys are sparse, so the shadow locks are stored in a [`HashMap`].
+#[cfg(any(feature = "slab", doc))]
+#[doc(cfg(feature = "slab"))]
+impl<T> Collection for slab::Slab<T> {
+    type Idx = usize;
+    type Output = T;
+    type ShadowLocks = HashMap<Self::Idx, RwLock<()>>;
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    type ShadowLocksAsync = HashMap<Self::Idx, tokio::sync::RwLock<()>>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
+        self.get(*index.borrow())
+    }
+
+    fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output> {
+        self.get_mut(*index.borrow())
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Self::Idx> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        slab::Slab::shrink_to_fit(self);
+    }
+
+    fn shadow_locks(&self) -> Self::ShadowLocks {
+        self.indices()
+            .map(|index| (index, RwLock::new(())))
+            .collect::<HashMap<_, _>>()
+    }
+
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+        self.indices()
+            .map(|index| (index, tokio::sync::RwLock::new(())))
+            .collect::<HashMap<_, _>>()
+    }
+}
+
+/// Cells are looked up by the hash of the key, which `read_cell` and `write_cell` compute once for both the map
+/// and its shadow locks.
+///
+/// The shadow locks are stored in a `hashbrown::HashMap` with a clone of the hasher of the collection.
+#[cfg(any(feature = "hashbrown", doc))]
+#[doc(cfg(feature = "hashbrown"))]
+impl<K, V, H> Collection for hashbrown::HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher + Clone,
+{
+    type Idx = K;
+    type Output = V;
+    type ShadowLocks = hashbrown::HashMap<Self::Idx, RwLock<()>, H>;
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    type ShadowLocksAsync = hashbrown::HashMap<Self::Idx, tokio::sync::RwLock<()>, H>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
+        self.get(index.borrow())
+    }
+
+    fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output> {
+        self.get_mut(index.borrow())
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Self::Idx> {
+        self.keys().cloned()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        hashbrown::HashMap::shrink_to_fit(self);
+    }
+
+    fn shadow_locks(&self) -> Self::ShadowLocks {
+        let mut shadow_locks =
+            hashbrown::HashMap::with_capacity_and_hasher(self.len(), self.hasher().clone());
+        shadow_locks.extend(self.indices().map(|index| (index, RwLock::new(()))));
+        shadow_locks
+    }
+
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+        let mut shadow_locks =
+            hashbrown::HashMap::with_capacity_and_hasher(self.len(), self.hasher().clone());
+        shadow_locks.extend(
+            self.indices()
+                .map(|index| (index, tokio::sync::RwLock::new(()))),
+        );
+        shadow_locks
+    }
+
+    fn hash_index(&self, index: &Self::Idx) -> Option<u64> {
+        Some(self.hasher().hash_one(index))
+    }
+
+    fn index_hashed(&self, index: &Self::Idx, hash: Option<u64>) -> Option<&Self::Output> {
+        match hash {
+            Some(hash) => get_hashed(self, index, hash),
+            None => self.get(index),
+        }
+    }
+
+    fn index_mut_hashed(
+        &mut self,
+        index: &Self::Idx,
+        hash: Option<u64>,
+    ) -> Option<&mut Self::Output> {
+        let Some(hash) = hash else {
+            return self.get_mut(index);
+        };
+        match self.raw_entry_mut().from_hash(hash, |key| key == index) {
+            hashbrown::hash_map::RawEntryMut::Occupied(entry) => Some(entry.into_mut()),
+            hashbrown::hash_map::RawEntryMut::Vacant(_) => None,
+        }
+    }
+}
+
+// Looks the key up by its hash computed with the hasher of the map.
+#[cfg(any(feature = "hashbrown", doc))]
+fn get_hashed<'a, K, V, H>(
+    map: &'a hashbrown::HashMap<K, V, H>,
+    key: &K,
+    hash: u64,
+) -> Option<&'a V>
+where
+    K: Eq,
+{
+    map.raw_entry()
+        .from_hash(hash, |stored| stored == key)
+        .map(|(_, value)| value)
+}
+
+/// [`Collection`] which maps keys to values and so can get new cells at any key, like [`HashMap`] and [`BTreeMap`].
+///
+/// Used by [`LockerRoom::entry`](crate::LockerRoom::entry).
+pub trait MapCollection: Collection<Output = Self::Value> {
+    /// Type of values.
+    type Value;
+
+    /// Inserts the value at the key, returning the previous value if there was one.
+    fn insert_cell(&mut self, key: Self::Idx, value: Self::Value) -> Option<Self::Value>;
+}
+
+impl<K, V, H> MapCollection for HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher + Clone,
+{
+    type Value = V;
+
+    fn insert_cell(&mut self, key: Self::Idx, value: Self::Value) -> Option<Self::Value> {
+        self.insert(key, value)
+    }
+}
+
+impl<K, V> MapCollection for BTreeMap<K, V>
+where
+    K: Ord + Clone,
+{
+    type Value = V;
+
+    fn insert_cell(&mut self, key: Self::Idx, value: Self::Value) -> Option<Self::Value> {
+        self.insert(key, value)
+    }
+}
+
+/// [`Collection`] whose cells can all be moved out at once, like [`Vec::drain`] and [`HashMap::drain`] do.
+///
+/// Used by [`RoomGuard::drain`](crate::sync::RoomGuard::drain).
+pub trait DrainCollection: Collection<Output = Self::Value> {
+    /// Type of values.
+    type Value;
+
+    /// Moves all cells out of the collection with their indices, leaving it empty. Cells which aren't iterated
+    /// over are dropped with the iterator.
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)>;
+}
+
+impl<T> DrainCollection for Vec<T> {
+    type Value = T;
+
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)> {
+        self.drain(..).enumerate()
+    }
+}
+
+impl<T> DrainCollection for VecDeque<T> {
+    type Value = T;
+
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)> {
+        self.drain(..).enumerate()
+    }
+}
+
+impl<K, V, H> DrainCollection for HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher + Clone,
+{
+    type Value = V;
+
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)> {
+        self.drain()
+    }
+}
+
+impl<K, V> DrainCollection for BTreeMap<K, V>
+where
+    K: Ord + Clone,
+{
+    type Value = V;
+
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)> {
+        mem::take(self).into_iter()
+    }
+}
+
+#[cfg(any(feature = "slab", doc))]
+#[doc(cfg(feature = "slab"))]
+impl<T> DrainCollection for slab::Slab<T> {
+    type Value = T;
+
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)> {
+        mem::take(self).into_iter()
+    }
+}
+
+#[cfg(any(feature = "hashbrown", doc))]
+#[doc(cfg(feature = "hashbrown"))]
+impl<K, V, H> DrainCollection for hashbrown::HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher + Clone,
+{
+    type Value = V;
+
+    fn drain_cells(&mut self) -> impl Iterator<Item = (Self::Idx, Self::Value)> {
+        self.drain()
+    }
+}
+
+/// [`Collection`] which can be built from cells with their indices. Maps keep the indices as keys, while sequences
+/// like [`Vec`] store the values in the given order and ignore the indices.
+///
+/// Used by [`LockerRoom::project`](crate::LockerRoom::project).
+pub trait FromCells: Collection<Output = Self::Value> {
+    /// Type of values.
+    type Value;
+
+    /// Creates the collection from the cells.
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self;
+}
+
+impl<T> FromCells for Vec<T> {
+    type Value = T;
+
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self {
+        cells.into_iter().map(|(_, value)| value).collect()
+    }
+}
+
+impl<T> FromCells for VecDeque<T> {
+    type Value = T;
+
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self {
+        cells.into_iter().map(|(_, value)| value).collect()
+    }
+}
+
+impl<K, V, H> FromCells for HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher + Clone + Default,
+{
+    type Value = V;
+
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self {
+        cells.into_iter().collect()
+    }
+}
+
+impl<K, V> FromCells for BTreeMap<K, V>
+where
+    K: Ord + Clone,
+{
+    type Value = V;
+
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self {
+        cells.into_iter().collect()
+    }
+}
+
+/// Cells keep their keys.
+#[cfg(any(feature = "slab", doc))]
+#[doc(cfg(feature = "slab"))]
+impl<T> FromCells for slab::Slab<T> {
+    type Value = T;
+
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self {
+        cells.into_iter().collect()
+    }
+}
+
+#[cfg(any(feature = "hashbrown", doc))]
+#[doc(cfg(feature = "hashbrown"))]
+impl<K, V, H> FromCells for hashbrown::HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher + Clone + Default,
+{
+    type Value = V;
+
+    fn from_cells(cells: impl IntoIterator<Item = (Self::Idx, Self::Value)>) -> Self {
+        cells.into_iter().collect()
+    }
+}
+
+/// [`Collection`] whose cells can be pointed to without creating a reference to them, like the elements of a slice.
+///
+/// Used by [`LockerRoom::get_cell_seq`](crate::LockerRoom::get_cell_seq), which copies the value of a cell while a
+/// writer may hold a mutable reference to it, so a shared reference to the cell must never exist meanwhile.
+///
+/// # Safety
+///
+/// [`index_ptr`](Self::index_ptr) must return a pointer to the same cell as [`index`](Collection::index) does and
+/// must not create a reference to any cell on the way.
+pub unsafe trait PtrCollection: Collection {
+    /// Returns a pointer to the cell at the index, or `None` if there is no such cell.
+    ///
+    /// # Safety
+    ///
+    /// `this` must point to a valid collection which isn't mutated other than through its cells while the function
+    /// runs.
+    unsafe fn index_ptr(this: *const Self, index: &Self::Idx) -> Option<*const Self::Output>;
+}
+
+unsafe impl<T> PtrCollection for [T] {
+    unsafe fn index_ptr(this: *const Self, index: &Self::Idx) -> Option<*const Self::Output> {
+        (*index < this.len()).then(|| unsafe { this.cast::<T>().add(*index) })
+    }
+}
+
+unsafe impl<T> PtrCollection for Box<[T]> {
+    unsafe fn index_ptr(this: *const Self, index: &Self::Idx) -> Option<*const Self::Output> {
+        unsafe { <[T]>::index_ptr(ptr::addr_of!(**this), index) }
+    }
+}
+
+unsafe impl<T, const N: usize> PtrCollection for [T; N] {
+    unsafe fn index_ptr(this: *const Self, index: &Self::Idx) -> Option<*const Self::Output> {
+        (*index < N).then(|| unsafe { this.cast::<T>().add(*index) })
+    }
+}
+
+unsafe impl<T> PtrCollection for Vec<T> {
+    unsafe fn index_ptr(this: *const Self, index: &Self::Idx) -> Option<*const Self::Output> {
+        // The reference covers the vector itself, not its cells, and `as_ptr` doesn't create one to them.
+        let vec = unsafe { &*this };
+        (*index < vec.len()).then(|| unsafe { vec.as_ptr().add(*index) })
+    }
+}
+
+/// Specifies maps whose cells can be looked up by a borrowed form of the key, like [`HashMap::get`] does, e.g. by
+/// `&str` for `String` keys without allocating.
+///
+/// Used by [`LockerRoom::read_cell_borrowed`](crate::LockerRoom::read_cell_borrowed) and
+/// [`LockerRoom::write_cell_borrowed`](crate::LockerRoom::write_cell_borrowed).
+pub trait BorrowedIndex<Q: ?Sized>: Collection {
+    /// Performs the indexing operation by the borrowed key.
+    fn index_borrowed(&self, key: &Q) -> Option<&Self::Output>;
+    /// Performs the mutable indexing operation by the borrowed key.
+    fn index_borrowed_mut(&mut self, key: &Q) -> Option<&mut Self::Output>;
+}
+
+impl<K, V, H, Q> BorrowedIndex<Q> for HashMap<K, V, H>
+where
+    K: Eq + Hash + Clone + Borrow<Q>,
+    H: BuildHasher + Clone,
+    Q: Eq + Hash + ?Sized,
+{
+    fn index_borrowed(&self, key: &Q) -> Option<&Self::Output> {
+        self.get(key)
+    }
+
+    fn index_borrowed_mut(&mut self, key: &Q) -> Option<&mut Self::Output> {
+        self.get_mut(key)
+    }
+}
+
+impl<K, V, Q> BorrowedIndex<Q> for BTreeMap<K, V>
+where
+    K: Ord + Clone + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    fn index_borrowed(&self, key: &Q) -> Option<&Self::Output> {
+        self.get(key)
+    }
+
+    fn index_borrowed_mut(&mut self, key: &Q) -> Option<&mut Self::Output> {
+        self.get_mut(key)
+    }
+}
+
 /// Specifies structures that can be used as [`Collection::ShadowLocks`].
+///
+/// Implementors must keep a lock for every index of the collection: after [`Collection::shadow_locks`] or
+/// [`update_indices`](Self::update_indices), [`index`](Self::index) must return a lock for each of
+/// [`Collection::indices`] and [`len`](Self::len) must return their number. Otherwise `LockerRoom` silently returns
+/// `None` for cells that exist. Debug builds check the number of locks when a `LockerRoom` is created.
 pub trait ShadowLocksCollection {
     /// Type that should be used as index.
     type Idx;
+    /// Type of lock stored for every cell. The same type is used for the lock of whole collection.
+    type Lock: ShadowLock;
 
     /// Performs the indexing operation returning lock for the cell.
-    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>>;
-    /// Update internal state to store [`RwLock`]'s with new indices.
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock>;
+    /// Update internal state to store locks with new indices.
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>);
+    /// Returns the number of indices which have locks.
+    fn len(&self) -> usize;
+    /// Returns `true` if no index has a lock.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Shrinks the capacity of the structure as much as possible. Does nothing by default.
+    ///
+    /// Used by [`RoomGuard::shrink_to_fit`](crate::sync::RoomGuard::shrink_to_fit).
+    fn shrink_to_fit(&mut self) {}
+
+    /// Like [`index`](Self::index), but may reuse the hash returned by [`Collection::hash_index`]. The hash comes from the hasher
+    /// of the collection, so it may only be trusted to find the lock, not to rule the index out.
+    #[doc(hidden)]
+    fn index_hashed(&self, index: &Self::Idx, _hash: Option<u64>) -> Option<&Self::Lock> {
+        self.index(index)
+    }
 }
 
-impl ShadowLocksCollection for Vec<RwLock<()>> {
+impl<L> ShadowLocksCollection for Vec<L>
+where
+    L: ShadowLock,
+{
     type Idx = usize;
+    type Lock = L;
 
-    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>> {
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
         self.get(*index.borrow())
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.resize_with(indices.count(), || RwLock::new(()));
+        self.resize_with(indices.count(), Default::default);
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self);
     }
 }
 
-impl ShadowLocksCollection for VecDeque<RwLock<()>> {
+impl<L> ShadowLocksCollection for VecDeque<L>
+where
+    L: ShadowLock,
+{
     type Idx = usize;
+    type Lock = L;
 
-    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>> {
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
         self.get(*index.borrow())
     }
 
+    // Only the new length is known, so locks are added or removed at the back. Changes at the front are mirrored
+    // by the methods of `RoomGuard` for deques as they're made.
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.resize_with(indices.count(), || RwLock::new(()));
+        self.resize_with(indices.count(), Default::default);
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        VecDeque::shrink_to_fit(self);
     }
 }
 
-impl<K> ShadowLocksCollection for HashMap<K, RwLock<()>>
+#[allow(clippy::needless_maybe_sized)]
+impl<K, L, H> ShadowLocksCollection for HashMap<K, L, H>
 where
     K: Eq + Hash + Clone + ?Sized,
+    L: ShadowLock,
+    H: BuildHasher,
 {
     type Idx = K;
+    type Lock = L;
 
-    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>> {
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
         self.get(index.borrow())
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.clear();
-        self.extend(indices.map(|index| (index, RwLock::new(()))));
+        // Locks of the remaining indices are kept, so they stay the same locks across rehashes of the collection.
+        let indices: HashSet<_> = indices.collect();
+        self.retain(|index, _| indices.contains(index));
+        for index in indices {
+            self.entry(index).or_default();
+        }
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        HashMap::shrink_to_fit(self);
     }
 }
 
-impl<K> ShadowLocksCollection for BTreeMap<K, RwLock<()>>
+#[allow(clippy::needless_maybe_sized)]
+impl<K, L> ShadowLocksCollection for BTreeMap<K, L>
 where
     K: Ord + Clone + ?Sized,
+    L: ShadowLock,
+{
+    type Idx = K;
+    type Lock = L;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
+        self.get(index.borrow())
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        let indices: BTreeSet<_> = indices.collect();
+        self.retain(|index, _| indices.contains(index));
+        for index in indices {
+            self.entry(index).or_default();
+        }
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+}
+
+#[cfg(any(feature = "hashbrown", doc))]
+#[doc(cfg(feature = "hashbrown"))]
+impl<K, L, H> ShadowLocksCollection for hashbrown::HashMap<K, L, H>
+where
+    K: Eq + Hash + Clone,
+    L: ShadowLock,
+    H: BuildHasher,
 {
     type Idx = K;
+    type Lock = L;
 
-    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>> {
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
         self.get(index.borrow())
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.clear();
-        self.extend(indices.map(|index| (index, RwLock::new(()))));
+        let indices: HashSet<_> = indices.collect();
+        self.retain(|index, _| indices.contains(index));
+        for index in indices {
+            self.entry(index).or_default();
+        }
+    }
+
+    fn len(&self) -> usize {
+        hashbrown::HashMap::len(self)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        hashbrown::HashMap::shrink_to_fit(self);
+    }
+
+    fn index_hashed(&self, index: &Self::Idx, hash: Option<u64>) -> Option<&Self::Lock> {
+        // Shadow locks with another hasher don't find the lock by the hash, so it's looked up once more.
+        hash.and_then(|hash| get_hashed(self, index, hash))
+            .or_else(|| self.get(index))
+    }
+}
+
+/// Specifies shadow locks of maps which can be looked up by a borrowed form of the key, see [`BorrowedIndex`].
+pub trait BorrowedShadowLocks<Q: ?Sized>: ShadowLocksCollection {
+    /// Performs the indexing operation by the borrowed key, returning the stored key along with the lock.
+    fn index_borrowed(&self, key: &Q) -> Option<(&Self::Idx, &Self::Lock)>;
+}
+
+impl<K, L, H, Q> BorrowedShadowLocks<Q> for HashMap<K, L, H>
+where
+    K: Eq + Hash + Clone + Borrow<Q>,
+    L: ShadowLock,
+    H: BuildHasher,
+    Q: Eq + Hash + ?Sized,
+{
+    fn index_borrowed(&self, key: &Q) -> Option<(&Self::Idx, &Self::Lock)> {
+        self.get_key_value(key)
+    }
+}
+
+impl<K, L, Q> BorrowedShadowLocks<Q> for BTreeMap<K, L>
+where
+    K: Ord + Clone + Borrow<Q>,
+    L: ShadowLock,
+    Q: Ord + ?Sized,
+{
+    fn index_borrowed(&self, key: &Q) -> Option<(&Self::Idx, &Self::Lock)> {
+        self.get_key_value(key)
     }
 }
 
@@ -334,6 +967,28 @@ pub trait ShadowLocksCollectionAsync {
     fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&tokio::sync::RwLock<()>>;
     /// Update internal state to store tokio's [`RwLock`](tokio::sync::RwLock)'s with new indices.
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>);
+    /// Like [`update_indices`](Self::update_indices), but the locks of new indices are made by `new_lock`, e.g.
+    /// with the reader cap of [`LockerRoomAsync::with_reader_cap`](crate::LockerRoomAsync::with_reader_cap).
+    ///
+    /// The provided implementation ignores `new_lock`, so the cap isn't put on such shadow locks.
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        _new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        self.update_indices(indices);
+    }
+
+    /// Like [`index`](Self::index), but may reuse the hash returned by [`Collection::hash_index`], see
+    /// [`ShadowLocksCollection::index_hashed`].
+    #[doc(hidden)]
+    fn index_hashed(
+        &self,
+        index: &Self::Idx,
+        _hash: Option<u64>,
+    ) -> Option<&tokio::sync::RwLock<()>> {
+        self.index(index)
+    }
 }
 
 #[cfg(any(feature = "async", doc))]
@@ -346,7 +1001,15 @@ impl ShadowLocksCollectionAsync for Vec<tokio::sync::RwLock<()>> {
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.resize_with(indices.count(), || tokio::sync::RwLock::new(()));
+        self.update_indices_with(indices, || tokio::sync::RwLock::new(()));
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        self.resize_with(indices.count(), new_lock);
     }
 }
 
@@ -360,15 +1023,25 @@ impl ShadowLocksCollectionAsync for VecDeque<tokio::sync::RwLock<()>> {
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.resize_with(indices.count(), || tokio::sync::RwLock::new(()));
+        self.update_indices_with(indices, || tokio::sync::RwLock::new(()));
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        self.resize_with(indices.count(), new_lock);
     }
 }
 
 #[cfg(any(feature = "async", doc))]
 #[doc(cfg(feature = "async"))]
-impl<K> ShadowLocksCollectionAsync for HashMap<K, tokio::sync::RwLock<()>>
+#[allow(clippy::needless_maybe_sized)]
+impl<K, H> ShadowLocksCollectionAsync for HashMap<K, tokio::sync::RwLock<()>, H>
 where
     K: Eq + Hash + Clone + ?Sized,
+    H: BuildHasher,
 {
     type Idx = K;
 
@@ -377,13 +1050,25 @@ where
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.clear();
-        self.extend(indices.map(|index| (index, tokio::sync::RwLock::new(()))));
+        self.update_indices_with(indices, || tokio::sync::RwLock::new(()));
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        mut new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        let indices: HashSet<_> = indices.collect();
+        self.retain(|index, _| indices.contains(index));
+        for index in indices {
+            self.entry(index).or_insert_with(&mut new_lock);
+        }
     }
 }
 
 #[cfg(any(feature = "async", doc))]
 #[doc(cfg(feature = "async"))]
+#[allow(clippy::needless_maybe_sized)]
 impl<K> ShadowLocksCollectionAsync for BTreeMap<K, tokio::sync::RwLock<()>>
 where
     K: Ord + Clone + ?Sized,
@@ -395,7 +1080,57 @@ where
     }
 
     fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
-        self.clear();
-        self.extend(indices.map(|index| (index, tokio::sync::RwLock::new(()))));
+        self.update_indices_with(indices, || tokio::sync::RwLock::new(()));
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        mut new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        let indices: BTreeSet<_> = indices.collect();
+        self.retain(|index, _| indices.contains(index));
+        for index in indices {
+            self.entry(index).or_insert_with(&mut new_lock);
+        }
+    }
+}
+
+#[cfg(all(any(feature = "async", doc), any(feature = "hashbrown", doc)))]
+#[doc(cfg(all(feature = "async", feature = "hashbrown")))]
+impl<K, H> ShadowLocksCollectionAsync for hashbrown::HashMap<K, tokio::sync::RwLock<()>, H>
+where
+    K: Eq + Hash + Clone,
+    H: BuildHasher,
+{
+    type Idx = K;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&tokio::sync::RwLock<()>> {
+        self.get(index.borrow())
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.update_indices_with(indices, || tokio::sync::RwLock::new(()));
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        mut new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        let indices: HashSet<_> = indices.collect();
+        self.retain(|index, _| indices.contains(index));
+        for index in indices {
+            self.entry(index).or_insert_with(&mut new_lock);
+        }
+    }
+
+    fn index_hashed(
+        &self,
+        index: &Self::Idx,
+        hash: Option<u64>,
+    ) -> Option<&tokio::sync::RwLock<()>> {
+        hash.and_then(|hash| get_hashed(self, index, hash))
+            .or_else(|| self.get(index))
     }
 }
@@ -0,0 +1,237 @@
+//! Detection of deadlocks between cell locks of [`LockerRoom`](crate::LockerRoom) in debug builds with feature
+//! `deadlock-detect`. Without them cell guards are stored as is and nothing is tracked.
+
+#[cfg(all(feature = "deadlock-detect", debug_assertions))]
+pub(crate) use detect::{acquire, hold, hold_again, Held};
+
+use std::{borrow::Cow, marker::PhantomData};
+
+#[cfg(all(feature = "deadlock-detect", debug_assertions))]
+use crate::trace::{FmtIndex, TraceIndex};
+
+/// Names cells in deadlock reports by their indices, formatted as set by `trace_indices`, or by `_`. It's empty
+/// without detection.
+pub(crate) struct CellNames<Idx> {
+    #[cfg(all(feature = "deadlock-detect", debug_assertions))]
+    fmt_index: Option<FmtIndex<Idx>>,
+    phantom: PhantomData<fn(&Idx)>,
+}
+
+impl<Idx> Clone for CellNames<Idx> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Idx> Copy for CellNames<Idx> {}
+
+#[cfg(all(feature = "deadlock-detect", debug_assertions))]
+impl<Idx> CellNames<Idx> {
+    pub(crate) fn new(fmt_index: Option<FmtIndex<Idx>>) -> Self {
+        Self {
+            fmt_index,
+            phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn name(&self, index: &Idx) -> Cow<'static, str> {
+        match self.fmt_index {
+            Some(_) => format!("{:?}", TraceIndex(index, self.fmt_index)).into(),
+            None => "_".into(),
+        }
+    }
+}
+
+#[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+impl<Idx> CellNames<Idx> {
+    pub(crate) fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn name(&self, _index: &Idx) -> Cow<'static, str> {
+        Cow::Borrowed("")
+    }
+}
+
+#[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+pub(crate) type Held<G> = G;
+
+#[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+pub(crate) fn acquire<L, G>(
+    _lock: &L,
+    _write: bool,
+    _name: impl FnOnce() -> Cow<'static, str>,
+    lock: impl FnOnce() -> G,
+) -> Held<G> {
+    lock()
+}
+
+#[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+pub(crate) fn hold<L, G>(
+    _lock: &L,
+    _write: bool,
+    _name: impl FnOnce() -> Cow<'static, str>,
+    guard: G,
+) -> Held<G> {
+    guard
+}
+
+#[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+pub(crate) fn hold_again<L, G>(_lock: &L, _write: bool, guard: G) -> Held<G> {
+    guard
+}
+
+#[cfg(all(feature = "deadlock-detect", debug_assertions))]
+mod detect {
+    use std::{
+        borrow::Cow,
+        cell::RefCell,
+        collections::{HashMap, HashSet},
+        fmt::Write,
+        sync::{LazyLock, Mutex},
+        thread::{self, ThreadId},
+    };
+
+    // Cell lock held by a thread: address of the lock, name of the cell and the kind of access.
+    type Hold = (usize, Cow<'static, str>, bool);
+
+    // Cell lock a thread waits for: address of the lock, the kind of access and cell locks held by the thread.
+    struct Wait {
+        lock: usize,
+        write: bool,
+        held: Vec<Hold>,
+    }
+
+    // Only threads that wait for a cell lock while holding others can be a part of a cycle, so only they are
+    // published in the graph. The rest of threads aren't slowed down by the global mutex.
+    static WAITS: LazyLock<Mutex<HashMap<ThreadId, Wait>>> = LazyLock::new(Default::default);
+
+    thread_local! {
+        static HELD: RefCell<Vec<Hold>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn waits() -> std::sync::MutexGuard<'static, HashMap<ThreadId, Wait>> {
+        WAITS.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // Searches for a path from the lock back to the current thread through holders and the locks they wait for.
+    fn cycle(waits: &HashMap<ThreadId, Wait>, thread: ThreadId, wait: &Wait) -> Option<String> {
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+        if !search(waits, (thread, wait), wait, &mut path, &mut visited) {
+            return None;
+        }
+        let mut report = format!("deadlock detected: {thread:?}");
+        for (step, (name, holder)) in path.into_iter().enumerate() {
+            let waiter = if step == 0 { "" } else { ", which" };
+            write!(report, "{waiter} waits for cell {name} held by {holder:?}").unwrap();
+        }
+        Some(report)
+    }
+
+    fn search<'a>(
+        waits: &'a HashMap<ThreadId, Wait>,
+        (thread, start): (ThreadId, &'a Wait),
+        wait: &'a Wait,
+        path: &mut Vec<(&'a str, ThreadId)>,
+        visited: &mut HashSet<(usize, bool)>,
+    ) -> bool {
+        if !visited.insert((wait.lock, wait.write)) {
+            return false;
+        }
+        let holders = [(thread, start)].into_iter().chain(
+            waits
+                .iter()
+                .filter(|(holder, _)| **holder != thread)
+                .map(|(holder, wait)| (*holder, wait)),
+        );
+        for (holder, holder_wait) in holders {
+            // Readers only wait for a writer.
+            let Some((_, name, _)) = holder_wait
+                .held
+                .iter()
+                .find(|(lock, _, holds_write)| *lock == wait.lock && (wait.write || *holds_write))
+            else {
+                continue;
+            };
+            path.push((name, holder));
+            if holder == thread || search(waits, (thread, start), holder_wait, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    /// Guard of a cell lock which is tracked by the current thread until it's dropped.
+    pub(crate) struct Held<G> {
+        lock: usize,
+        // Dropped after the guard is forgotten by the thread.
+        #[allow(dead_code)]
+        guard: G,
+    }
+
+    impl<G> Drop for Held<G> {
+        fn drop(&mut self) {
+            HELD.with_borrow_mut(|held| {
+                if let Some(position) = held.iter().rposition(|(lock, _, _)| *lock == self.lock) {
+                    held.swap_remove(position);
+                }
+            });
+        }
+    }
+
+    /// Locks the cell with `lock`, panicking instead if the current thread would deadlock waiting for it.
+    pub(crate) fn acquire<L, G>(
+        lock_ref: &L,
+        write: bool,
+        name: impl FnOnce() -> Cow<'static, str>,
+        lock: impl FnOnce() -> G,
+    ) -> Held<G> {
+        // A thread which holds no cells can't close a cycle.
+        if HELD.with_borrow(Vec::is_empty) {
+            return hold(lock_ref, write, name, lock());
+        }
+        let thread = thread::current().id();
+        let wait = Wait {
+            lock: lock_ref as *const L as usize,
+            write,
+            held: HELD.with_borrow(Vec::clone),
+        };
+        let mut waits_guard = waits();
+        if let Some(report) = cycle(&waits_guard, thread, &wait) {
+            drop(waits_guard);
+            panic!("{report}");
+        }
+        waits_guard.insert(thread, wait);
+        drop(waits_guard);
+        let guard = lock();
+        waits().remove(&thread);
+        hold(lock_ref, write, name, guard)
+    }
+
+    /// Tracks the guard of the cell lock which has already been acquired by the current thread.
+    pub(crate) fn hold<L, G>(
+        lock_ref: &L,
+        write: bool,
+        name: impl FnOnce() -> Cow<'static, str>,
+        guard: G,
+    ) -> Held<G> {
+        let lock = lock_ref as *const L as usize;
+        HELD.with_borrow_mut(|held| held.push((lock, name(), write)));
+        Held { lock, guard }
+    }
+
+    /// Tracks one more guard of the cell lock held by the current thread, under the name it's held by.
+    pub(crate) fn hold_again<L, G>(lock_ref: &L, write: bool, guard: G) -> Held<G> {
+        let lock = lock_ref as *const L as usize;
+        let name = HELD.with_borrow(|held| {
+            held.iter()
+                .find(|(held, _, _)| *held == lock)
+                .map_or("_".into(), |(_, name, _)| name.clone())
+        });
+        hold(lock_ref, write, || name, guard)
+    }
+}
@@ -0,0 +1,69 @@
+//! This module describes errors returned by [`LockerRoom`](crate::LockerRoom) and [`LockerRoomAsync`](crate::LockerRoomAsync).
+
+use std::{error::Error, fmt};
+
+/// Error of [`compare_and_swap_cell`](crate::LockerRoom::compare_and_swap_cell). It gives the new value back to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareAndSwapError<V> {
+    /// The value of the cell isn't equal to the expected one.
+    Mismatch(V),
+    /// There is no cell with such index.
+    NoCell(V),
+}
+
+impl<V> CompareAndSwapError<V> {
+    /// Returns the new value which wasn't stored.
+    pub fn into_inner(self) -> V {
+        match self {
+            Self::Mismatch(value) | Self::NoCell(value) => value,
+        }
+    }
+}
+
+impl<V> fmt::Display for CompareAndSwapError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mismatch(_) => f.write_str("value of the cell isn't equal to the expected one"),
+            Self::NoCell(_) => f.write_str("there is no cell with such index"),
+        }
+    }
+}
+
+impl<V: fmt::Debug> Error for CompareAndSwapError<V> {}
+
+/// Error of [`validate`](crate::LockerRoom::validate) describing how a [`Collection`](crate::Collection) or its
+/// shadow locks break their contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectionError {
+    /// [`Collection::indices`](crate::Collection::indices) yields the same index more than once.
+    DuplicateIndex,
+    /// There is no cell at an index yielded by `indices`.
+    NoCell,
+    /// The shadow locks have no lock for an index yielded by `indices`.
+    NoLock,
+    /// The number of locks differs from the number of indices.
+    LenMismatch {
+        /// The number of indices yielded by `indices`.
+        indices: usize,
+        /// The number of locks returned by [`ShadowLocksCollection::len`](crate::ShadowLocksCollection::len).
+        locks: usize,
+    },
+}
+
+impl fmt::Display for CollectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateIndex => f.write_str("indices of the collection have duplicates"),
+            Self::NoCell => f.write_str("there is no cell at an index of the collection"),
+            Self::NoLock => f.write_str("there is no lock for an index of the collection"),
+            Self::LenMismatch { indices, locks } => {
+                write!(
+                    f,
+                    "collection has {indices} indices, but there are {locks} locks"
+                )
+            }
+        }
+    }
+}
+
+impl Error for CollectionError {}
@@ -0,0 +1,126 @@
+//! Support of [`ndarray`](https://docs.rs/ndarray/latest/ndarray/index.html)'s two-dimensional arrays.
+
+use std::borrow::Borrow;
+
+use ndarray::Array2;
+
+use crate::{primitive::RwLock, Collection, ShadowLock, ShadowLocksCollection};
+
+/// Locks of cells of a two-dimensional grid stored in a flat [`Vec`] in row-major order.
+///
+/// Used as [`Collection::ShadowLocks`] of [`Array2`](https://docs.rs/ndarray/latest/ndarray/type.Array2.html).
+pub struct GridLocks<L = RwLock<()>> {
+    columns: usize,
+    locks: Vec<L>,
+}
+
+impl<L: Default> GridLocks<L> {
+    /// Creates locks for a grid with `rows` rows and `columns` columns.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            columns,
+            locks: std::iter::repeat_with(L::default)
+                .take(rows * columns)
+                .collect(),
+        }
+    }
+
+    fn offset(&self, (row, column): (usize, usize)) -> Option<usize> {
+        if column >= self.columns {
+            return None;
+        }
+        row.checked_mul(self.columns)?.checked_add(column)
+    }
+
+    // Indices of a grid go in row-major order, so the last one tells the shape.
+    fn reshape(
+        &mut self,
+        indices: impl Iterator<Item = (usize, usize)>,
+        new_lock: impl FnMut() -> L,
+    ) {
+        let (rows, columns) = indices
+            .last()
+            .map_or((0, 0), |(row, column)| (row + 1, column + 1));
+        self.columns = columns;
+        self.locks.resize_with(rows * columns, new_lock);
+    }
+}
+
+impl<L> ShadowLocksCollection for GridLocks<L>
+where
+    L: ShadowLock,
+{
+    type Idx = (usize, usize);
+    type Lock = L;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
+        self.locks.get(self.offset(*index.borrow())?)
+    }
+
+    /// Indices must go in row-major order, as [`Collection::indices`] of `Array2` does.
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.reshape(indices, L::default);
+    }
+
+    fn len(&self) -> usize {
+        self.locks.len()
+    }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[doc(cfg(feature = "async"))]
+impl crate::ShadowLocksCollectionAsync for GridLocks<tokio::sync::RwLock<()>> {
+    type Idx = (usize, usize);
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&tokio::sync::RwLock<()>> {
+        self.locks.get(self.offset(*index.borrow())?)
+    }
+
+    /// Indices must go in row-major order, as [`Collection::indices`] of `Array2` does.
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.reshape(indices, Default::default);
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        self.reshape(indices, new_lock);
+    }
+}
+
+/// Cells of the array are indexed by `(row, column)`.
+impl<T> Collection for Array2<T> {
+    type Idx = (usize, usize);
+    type Output = T;
+    type ShadowLocks = GridLocks;
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    type ShadowLocksAsync = GridLocks<tokio::sync::RwLock<()>>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
+        self.get(*index.borrow())
+    }
+
+    fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output> {
+        self.get_mut(*index.borrow())
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Self::Idx> {
+        let (rows, columns) = self.dim();
+        (0..rows).flat_map(move |row| (0..columns).map(move |column| (row, column)))
+    }
+
+    fn shadow_locks(&self) -> Self::ShadowLocks {
+        let (rows, columns) = self.dim();
+        GridLocks::new(rows, columns)
+    }
+
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+        let (rows, columns) = self.dim();
+        GridLocks::new(rows, columns)
+    }
+}
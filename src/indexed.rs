@@ -0,0 +1,218 @@
+//! Support of index newtypes for collections indexed by `usize`.
+
+use std::{
+    borrow::Borrow,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{Collection, ShadowLocksCollection};
+
+/// Collection indexed by `usize` whose cells are indexed by the newtype `I` instead, e.g. `struct RowId(usize)`.
+///
+/// Indices are converted with [`Into<usize>`] when cells are looked up and with [`From<usize>`] when they are
+/// listed, so a `LockerRoom` of rows can't be indexed by an id of something else by mistake. It dereferences to
+/// the wrapped collection, so it's changed like the collection itself once whole collection is locked.
+/// ```
+/// # use lockerroom::{Indexed, LockerRoom};
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct RowId(usize);
+/// # impl From<usize> for RowId {
+/// #     fn from(id: usize) -> Self {
+/// #         Self(id)
+/// #     }
+/// # }
+/// # impl From<RowId> for usize {
+/// #     fn from(id: RowId) -> Self {
+/// #         id.0
+/// #     }
+/// # }
+///
+/// let locker_room: LockerRoom<Indexed<_, RowId>> = Indexed::new(vec![1, 2]).into();
+/// *locker_room.write_cell(RowId(1)).unwrap() += 1;
+/// locker_room.lock_room().push(4);
+/// assert_eq!(vec![RowId(0), RowId(1), RowId(2)], locker_room.indices());
+/// assert_eq!(vec![1, 3, 4], locker_room.into_inner().into_inner());
+/// ```
+pub struct Indexed<C, I> {
+    collection: C,
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<C, I> Indexed<C, I> {
+    /// Wraps the collection, so its cells are indexed by `I`.
+    pub fn new(collection: C) -> Self {
+        Self {
+            collection,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped collection.
+    pub fn into_inner(self) -> C {
+        self.collection
+    }
+}
+
+impl<C, I> From<C> for Indexed<C, I> {
+    fn from(collection: C) -> Self {
+        Self::new(collection)
+    }
+}
+
+impl<C: Default, I> Default for Indexed<C, I> {
+    fn default() -> Self {
+        Self::new(C::default())
+    }
+}
+
+impl<C: Clone, I> Clone for Indexed<C, I> {
+    fn clone(&self) -> Self {
+        Self::new(self.collection.clone())
+    }
+}
+
+impl<C: fmt::Debug, I> fmt::Debug for Indexed<C, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Indexed").field(&self.collection).finish()
+    }
+}
+
+impl<C: PartialEq, I> PartialEq for Indexed<C, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.collection == other.collection
+    }
+}
+
+impl<C: Eq, I> Eq for Indexed<C, I> {}
+
+impl<C, I> Deref for Indexed<C, I> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.collection
+    }
+}
+
+impl<C, I> DerefMut for Indexed<C, I> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.collection
+    }
+}
+
+/// Shadow locks indexed by `usize` whose locks are indexed by the newtype `I` instead.
+///
+/// Used as [`Collection::ShadowLocks`] of [`Indexed`].
+pub struct IndexedLocks<S, I> {
+    locks: S,
+    phantom: PhantomData<fn() -> I>,
+}
+
+impl<S, I> IndexedLocks<S, I> {
+    /// Wraps the locks, so they are indexed by `I`.
+    pub fn new(locks: S) -> Self {
+        Self {
+            locks,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S: Default, I> Default for IndexedLocks<S, I> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+impl<S, I> ShadowLocksCollection for IndexedLocks<S, I>
+where
+    S: ShadowLocksCollection<Idx = usize>,
+    I: Clone + Into<usize>,
+{
+    type Idx = I;
+    type Lock = S::Lock;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
+        self.locks.index(index.borrow().clone().into())
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.locks.update_indices(indices.map(Into::into));
+    }
+
+    fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.locks.shrink_to_fit();
+    }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[doc(cfg(feature = "async"))]
+impl<S, I> crate::ShadowLocksCollectionAsync for IndexedLocks<S, I>
+where
+    S: crate::ShadowLocksCollectionAsync<Idx = usize>,
+    I: Clone + Into<usize>,
+{
+    type Idx = I;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&tokio::sync::RwLock<()>> {
+        self.locks.index(index.borrow().clone().into())
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.locks.update_indices(indices.map(Into::into));
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        self.locks
+            .update_indices_with(indices.map(Into::into), new_lock);
+    }
+}
+
+/// Cells are indexed by `I`, converted to the `usize` indices of the wrapped collection.
+impl<C, I> Collection for Indexed<C, I>
+where
+    C: Collection<Idx = usize>,
+    I: Clone + Into<usize> + From<usize>,
+{
+    type Idx = I;
+    type Output = C::Output;
+    type ShadowLocks = IndexedLocks<C::ShadowLocks, I>;
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    type ShadowLocksAsync = IndexedLocks<C::ShadowLocksAsync, I>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
+        self.collection.index(index.borrow().clone().into())
+    }
+
+    fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output> {
+        self.collection.index_mut(index.borrow().clone().into())
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Self::Idx> {
+        self.collection.indices().map(I::from)
+    }
+
+    fn shadow_locks(&self) -> Self::ShadowLocks {
+        IndexedLocks::new(self.collection.shadow_locks())
+    }
+
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+        IndexedLocks::new(self.collection.shadow_locks_async())
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.collection.shrink_to_fit();
+    }
+}
@@ -0,0 +1,175 @@
+//! Support of jagged two-dimensional collections, stored as a [`Vec`] of rows of varying lengths.
+
+use std::borrow::Borrow;
+
+use crate::{primitive::RwLock, Collection, ShadowLock, ShadowLocksCollection};
+
+/// Rows of cells which may have different lengths. Cells are indexed by `(row, column)`.
+///
+/// Unlike `Vec<Vec<T>>` itself, whose cells are whole rows, every element of every row is a cell here,
+/// so elements of the same row can be written concurrently.
+/// ```
+/// # use lockerroom::{Jagged, LockerRoom};
+/// let locker_room: LockerRoom<_> = Jagged(vec![vec![1, 2, 3], vec![], vec![4]]).into();
+/// *locker_room.write_cell((0, 2)).unwrap() += 1;
+/// assert_eq!(Some(4), locker_room.get_cell((0, 2)));
+/// assert_eq!(None, locker_room.get_cell((1, 0)));
+///
+/// locker_room.lock_room().0[1].push(5);
+/// assert_eq!(Some(5), locker_room.get_cell((1, 0)));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Jagged<T>(pub Vec<Vec<T>>);
+
+impl<T> From<Vec<Vec<T>>> for Jagged<T> {
+    fn from(rows: Vec<Vec<T>>) -> Self {
+        Self(rows)
+    }
+}
+
+/// Locks of cells of a jagged collection stored in a flat [`Vec`] in row-major order.
+///
+/// Used as [`Collection::ShadowLocks`] of [`Jagged`].
+pub struct JaggedLocks<L = RwLock<()>> {
+    // Offset of the first lock of every row, followed by the number of locks.
+    offsets: Vec<usize>,
+    locks: Vec<L>,
+}
+
+impl<L: Default> JaggedLocks<L> {
+    /// Creates locks for rows with the lengths.
+    pub fn new(row_lengths: impl IntoIterator<Item = usize>) -> Self {
+        let mut locks = Self {
+            offsets: Vec::new(),
+            locks: Vec::new(),
+        };
+        locks.reshape(row_lengths, L::default);
+        locks
+    }
+
+    fn offset(&self, (row, column): (usize, usize)) -> Option<usize> {
+        let start = *self.offsets.get(row)?;
+        let end = *self.offsets.get(row + 1)?;
+        start.checked_add(column).filter(|&offset| offset < end)
+    }
+
+    fn reshape(
+        &mut self,
+        row_lengths: impl IntoIterator<Item = usize>,
+        new_lock: impl FnMut() -> L,
+    ) {
+        self.offsets.clear();
+        self.offsets.push(0);
+        let mut len = 0;
+        for row_length in row_lengths {
+            len += row_length;
+            self.offsets.push(len);
+        }
+        self.locks.resize_with(len, new_lock);
+    }
+
+    // Trailing empty rows have no indices, but they have no cells either.
+    fn update(
+        &mut self,
+        indices: impl Iterator<Item = (usize, usize)>,
+        new_lock: impl FnMut() -> L,
+    ) {
+        let mut row_lengths = Vec::new();
+        for (row, column) in indices {
+            if row >= row_lengths.len() {
+                row_lengths.resize(row + 1, 0);
+            }
+            row_lengths[row] = row_lengths[row].max(column + 1);
+        }
+        self.reshape(row_lengths, new_lock);
+    }
+}
+
+impl<L> ShadowLocksCollection for JaggedLocks<L>
+where
+    L: ShadowLock,
+{
+    type Idx = (usize, usize);
+    type Lock = L;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Lock> {
+        self.locks.get(self.offset(*index.borrow())?)
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.update(indices, L::default);
+    }
+
+    fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.offsets.shrink_to_fit();
+        self.locks.shrink_to_fit();
+    }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[doc(cfg(feature = "async"))]
+impl crate::ShadowLocksCollectionAsync for JaggedLocks<tokio::sync::RwLock<()>> {
+    type Idx = (usize, usize);
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&tokio::sync::RwLock<()>> {
+        self.locks.get(self.offset(*index.borrow())?)
+    }
+
+    fn update_indices(&mut self, indices: impl Iterator<Item = Self::Idx>) {
+        self.update(indices, Default::default);
+    }
+
+    fn update_indices_with(
+        &mut self,
+        indices: impl Iterator<Item = Self::Idx>,
+        new_lock: impl FnMut() -> tokio::sync::RwLock<()>,
+    ) {
+        self.update(indices, new_lock);
+    }
+}
+
+/// Cells of the rows are indexed by `(row, column)`.
+impl<T> Collection for Jagged<T> {
+    type Idx = (usize, usize);
+    type Output = T;
+    type ShadowLocks = JaggedLocks;
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    type ShadowLocksAsync = JaggedLocks<tokio::sync::RwLock<()>>;
+
+    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&Self::Output> {
+        let (row, column) = *index.borrow();
+        self.0.get(row)?.get(column)
+    }
+
+    fn index_mut(&mut self, index: impl Borrow<Self::Idx>) -> Option<&mut Self::Output> {
+        let (row, column) = *index.borrow();
+        self.0.get_mut(row)?.get_mut(column)
+    }
+
+    fn indices(&self) -> impl Iterator<Item = Self::Idx> {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| (0..cells.len()).map(move |column| (row, column)))
+    }
+
+    fn shadow_locks(&self) -> Self::ShadowLocks {
+        JaggedLocks::new(self.0.iter().map(Vec::len))
+    }
+
+    #[cfg(any(feature = "async", doc))]
+    #[doc(cfg(feature = "async"))]
+    fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+        JaggedLocks::new(self.0.iter().map(Vec::len))
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.0.iter_mut().for_each(Vec::shrink_to_fit);
+        self.0.shrink_to_fit();
+    }
+}
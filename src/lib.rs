@@ -9,7 +9,26 @@
 //! 3. Exclusive writer access to whole collection with [`LockerRoom::lock_room`] and [`LockerRoomAsync::lock_room`].
 //!
 //! But `LockerRoomAsync` is optional - you need to enable feature `async` to use it. It depends on
-//! [`tokio`](https://docs.rs/tokio/latest/tokio/index.html)'s [`RwLock`](https://docs.rs/tokio/latest/tokio/sync/struct.RwLock.html).
+//! [`tokio`](https://docs.rs/tokio/latest/tokio/index.html)'s [`RwLock`](https://docs.rs/tokio/latest/tokio/sync/struct.RwLock.html). Feature `time` adds
+//! [`LockerRoomAsync::write_cell_timeout`], which gives up waiting after a timeout of [`tokio::time`].
+//!
+//! `LockerRoom` uses [`std::sync::RwLock`] by default, but other locks can be chosen with [`LockerRoom::builder`].
+//! For example, feature `parking_lot` enables [`parking_lot`](https://docs.rs/parking_lot/latest/parking_lot/index.html)'s
+//! task-fair `RwLock` and feature `spin` enables [`spin`](https://docs.rs/spin/latest/spin/index.html)'s spinning
+//! `RwLock` for short critical sections, while [`AdaptiveLocks`] spin for a bounded number of times before they park
+//! the thread. And [`SeqLocks`] let [`LockerRoom::get_cell_seq`]
+//! copy small `Copy` values out of cells without locking them, while [`ReentrantReadLocks`] let a thread read-lock
+//! the same cell recursively.
+//!
+//! If cells are never inserted or removed, [`sync::LockerRoomCells`] spares the lock of whole collection, so its guards
+//! hold only the locks of their cells.
+//! And cells which already have their own locks, like `Vec<RwLock<T>>`, get the lock of whole collection from
+//! [`sync::LockedCells`] without moving out of their locks.
+//! [`sync::Colocated`] moves the values of a vector into such locks, so a cell is locked and read in one place.
+//!
+//! Feature `tracing` makes both structures enter [`tracing`](https://docs.rs/tracing/latest/tracing/index.html) spans
+//! while they wait for locks, see [`LockerRoom::trace_indices`]. Feature `stats` makes `LockerRoom` count acquired
+//! locks, see [`LockerRoom::stats`].
 //!
 //! ## `LockerRoom` example
 //! ```
@@ -58,9 +77,23 @@
 //!                                        |  let _w2 = locker_room.write_cell(0);
 //! ```
 //!
+//! To lock several cells at once use [`LockerRoom::write_cells`] (or [`LockerRoom::read_cells`]), which locks cells in ascending order of indices.
+//! [`LockerRoom::try_write_cells`] does the same without blocking and releases the cells it got if any cell is held.
+//! Indices which aren't [`Ord`], e.g. some keys of hash maps, choose the order by implementing [`CanonicalOrder`].
+//!
+//! In debug builds feature `deadlock-detect` makes `LockerRoom` track cells held by every thread and panic
+//! instead of waiting for a lock if the wait would close a cycle like the one above. The report names the
+//! involved cells by their indices if [`LockerRoom::trace_indices`] is called. Release builds aren't affected.
+//!
 //! ## Collections?
-//! By default you can create `LockerRoom` and `LockerRoomAsync` from [`array`], [`Vec`], [`VecDeque`](std::collections::VecDeque),
+//! By default you can create `LockerRoom` and `LockerRoomAsync` from [`array`], [`Vec`], boxed slices, [`VecDeque`](std::collections::VecDeque),
 //! [`HashMap`](std::collections::HashMap) and [`BTreeMap`](std::collections::BTreeMap).
+//! Feature `ndarray` adds [`ndarray`](https://docs.rs/ndarray/latest/ndarray/index.html)'s `Array2`, whose cells
+//! are indexed by `(row, column)`, and feature `slab` adds [`slab`](https://docs.rs/slab/latest/slab/index.html)'s `Slab`,
+//! whose cells are indexed by stable keys. Feature `hashbrown` adds [`hashbrown`](https://docs.rs/hashbrown/latest/hashbrown/index.html)'s
+//! `HashMap`, whose key `read_cell` and `write_cell` hash only once for both the map and its shadow locks.
+//! Rows of varying lengths can be wrapped into [`Jagged`], whose cells are the elements of the rows indexed by `(row, column)`.
+//! And collections indexed by `usize` can be wrapped into [`Indexed`], whose cells are indexed by a newtype like `RowId(usize)`.
 //!
 //! But the crate provides traits, by which implementing to your collection, you can make it compatible with `LockerRoom` and `LockerRoomAsync`.
 //!
@@ -135,6 +168,7 @@
 //!
 //! impl ShadowLocksCollection for NucleotideShadowLocks {
 //!     type Idx = Nucleotide;
+//!     type Lock = RwLock<()>;
 //!
 //!     fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>> {
 //!         Some(match index.borrow() {
@@ -148,6 +182,10 @@
 //!     fn update_indices(&mut self, _indices: impl Iterator<Item = Self::Idx>) {
 //!         // No need to reindex because NucleotideShadowLocks has static structure.
 //!     }
+//!
+//!     fn len(&self) -> usize {
+//!         4
+//!     }
 //! }
 //! # #[derive(Default)]
 //! # struct NucleotideShadowLocksAsync {
@@ -234,6 +272,7 @@
 //! # }
 //! # impl ShadowLocksCollection for NucleotideShadowLocks {
 //! #    type Idx = Nucleotide;
+//! #    type Lock = RwLock<()>;
 //! #    fn index(&self, index: impl Borrow<Self::Idx>) -> Option<&RwLock<()>> {
 //! #        Some(match index.borrow() {
 //! #            Nucleotide::A => &self.a,
@@ -245,6 +284,9 @@
 //! #    fn update_indices(&mut self, _indices: impl Iterator<Item = Self::Idx>) {
 //! #        // No need to reindex because NucleotideShadowLocks has static structure.
 //! #    }
+//! #    fn len(&self) -> usize {
+//! #        4
+//! #    }
 //! # }
 //! # #[derive(Default)]
 //! struct NucleotideShadowLocksAsync {
@@ -272,14 +314,42 @@
 //! }
 //! ```
 
+mod adaptive;
 #[cfg(any(feature = "async", doc))]
 #[doc(cfg(feature = "async"))]
 pub mod r#async;
+mod block;
 mod collection;
+mod deadlock;
+mod error;
+#[cfg(any(feature = "ndarray", doc))]
+mod grid;
+mod indexed;
+mod jagged;
+mod lock;
+mod order;
+mod primitive;
+mod reentrant;
+mod release;
+mod seqlock;
 pub mod sync;
+#[cfg(any(feature = "tracing", feature = "deadlock-detect", doc))]
+mod trace;
 
+pub use adaptive::*;
+pub use block::*;
 pub use collection::*;
+pub use error::*;
+#[cfg(any(feature = "ndarray", doc))]
+#[doc(cfg(feature = "ndarray"))]
+pub use grid::*;
+pub use indexed::*;
+pub use jagged::*;
+pub use lock::*;
+pub use order::*;
 #[cfg(any(feature = "async", doc))]
 #[doc(cfg(feature = "async"))]
 pub use r#async::LockerRoomAsync;
+pub use reentrant::*;
+pub use seqlock::*;
 pub use sync::LockerRoom;
@@ -0,0 +1,233 @@
+//! This module describes readers-writer locks which [`LockerRoom`](crate::LockerRoom) can use for cells and whole collection.
+
+use std::{
+    sync::TryLockError,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::primitive::{self, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// State of a readers-writer lock at some moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockState {
+    /// Nobody holds the lock.
+    Unlocked,
+    /// The lock is held with shared read access by one or more readers.
+    Read,
+    /// The lock is held with exclusive write access.
+    Write,
+}
+
+/// Readers-writer lock which can be stored in [`ShadowLocksCollection`](crate::ShadowLocksCollection)
+/// and used by [`LockerRoom`](crate::LockerRoom).
+///
+/// Implementors differ in their fairness policy:
+/// - [`std::sync::RwLock`] - the policy depends on the platform. For example, on Linux writers are preferred,
+///   so readers may starve under heavy write contention;
+/// - [`parking_lot::RwLock`](https://docs.rs/parking_lot/latest/parking_lot/type.RwLock.html) (feature `parking_lot`) -
+///   task-fair policy that avoids both reader and writer starvation;
+/// - [`spin::RwLock`](https://docs.rs/spin/latest/spin/type.RwLock.html) (feature `spin`) - spinlock which never
+///   parks the thread, so it only suits short critical sections. It's unfair to writers: they may starve while
+///   readers keep coming.
+///
+/// The lock can be chosen with [`LockerRoomBuilder`](crate::sync::LockerRoomBuilder). `cargo bench --bench
+/// writer_starvation` measures how long writers wait under heavy read load with each of them.
+pub trait ShadowLock: Default {
+    /// RAII guard of shared read access.
+    type ReadGuard<'a>
+    where
+        Self: 'a;
+    /// RAII guard of exclusive write access.
+    type WriteGuard<'a>
+    where
+        Self: 'a;
+
+    /// Locks with shared read access, blocking the current thread until it can be acquired.
+    fn read(&self) -> Self::ReadGuard<'_>;
+    /// Locks with exclusive write access, blocking the current thread until it can be acquired.
+    fn write(&self) -> Self::WriteGuard<'_>;
+    /// Attempts to lock with shared read access without blocking.
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>>;
+    /// Attempts to lock with exclusive write access without blocking.
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>>;
+
+    /// Atomically downgrades exclusive write access to shared read access, so no writer can take the lock in between.
+    fn downgrade<'a>(guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a>
+    where
+        Self: 'a;
+
+    /// Attempts to lock with shared read access, blocking the current thread no longer than `timeout`.
+    ///
+    /// By default [`try_read`](Self::try_read) is retried with exponential backoff until the timeout expires.
+    fn try_read_for(&self, timeout: Duration) -> Option<Self::ReadGuard<'_>> {
+        retry_for(timeout, || self.try_read())
+    }
+
+    /// Attempts to lock with exclusive write access, blocking the current thread no longer than `timeout`.
+    ///
+    /// By default [`try_write`](Self::try_write) is retried with exponential backoff until the timeout expires.
+    fn try_write_for(&self, timeout: Duration) -> Option<Self::WriteGuard<'_>> {
+        retry_for(timeout, || self.try_write())
+    }
+
+    /// Clears poisoning of the lock left by a thread which panicked while holding it. `LockerRoom` ignores poisoning,
+    /// but it's still observable through the lock itself.
+    ///
+    /// Does nothing by default, which suits locks without poisoning.
+    fn clear_poison(&self) {}
+
+    /// Returns the state of the lock. It may change right after the function returns, so it's only useful for
+    /// debugging and monitoring.
+    ///
+    /// By default the state is probed with [`try_write`](Self::try_write) and [`try_read`](Self::try_read), so
+    /// the lock is acquired for a moment. The result is best-effort: e.g. a lock which prefers writers refuses
+    /// new readers while a writer waits, so a read-locked lock can be reported as write-locked.
+    fn state(&self) -> LockState {
+        if self.try_write().is_some() {
+            LockState::Unlocked
+        } else if self.try_read().is_some() {
+            LockState::Read
+        } else {
+            LockState::Write
+        }
+    }
+}
+
+// Retries the attempt with exponential backoff until it succeeds or the timeout expires.
+fn retry_for<G>(timeout: Duration, mut attempt: impl FnMut() -> Option<G>) -> Option<G> {
+    let deadline = Instant::now() + timeout;
+    let mut backoff = Duration::from_micros(1);
+    loop {
+        if let Some(guard) = attempt() {
+            return Some(guard);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        thread::sleep(backoff.min(deadline - now));
+        backoff = (backoff * 2).min(Duration::from_millis(1));
+    }
+}
+
+impl ShadowLock for RwLock<()> {
+    type ReadGuard<'a> = RwLockReadGuard<'a, ()>;
+    type WriteGuard<'a> = RwLockWriteGuard<'a, ()>;
+
+    fn read(&self) -> Self::ReadGuard<'_> {
+        RwLock::read(self).unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn write(&self) -> Self::WriteGuard<'_> {
+        RwLock::write(self).unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn downgrade<'a>(guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+        primitive::downgrade(guard)
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        match RwLock::try_read(self) {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        match RwLock::try_write(self) {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    fn clear_poison(&self) {
+        primitive::clear_poison(self);
+    }
+}
+
+#[cfg(any(feature = "parking_lot", doc))]
+#[doc(cfg(feature = "parking_lot"))]
+impl ShadowLock for parking_lot::RwLock<()> {
+    type ReadGuard<'a> = parking_lot::RwLockReadGuard<'a, ()>;
+    type WriteGuard<'a> = parking_lot::RwLockWriteGuard<'a, ()>;
+
+    fn read(&self) -> Self::ReadGuard<'_> {
+        parking_lot::RwLock::read(self)
+    }
+
+    fn write(&self) -> Self::WriteGuard<'_> {
+        parking_lot::RwLock::write(self)
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        parking_lot::RwLock::try_read(self)
+    }
+
+    fn downgrade<'a>(guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+        parking_lot::RwLockWriteGuard::downgrade(guard)
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        parking_lot::RwLock::try_write(self)
+    }
+
+    fn try_read_for(&self, timeout: Duration) -> Option<Self::ReadGuard<'_>> {
+        parking_lot::RwLock::try_read_for(self, timeout)
+    }
+
+    fn try_write_for(&self, timeout: Duration) -> Option<Self::WriteGuard<'_>> {
+        parking_lot::RwLock::try_write_for(self, timeout)
+    }
+
+    /// Returns the exact state of the lock without acquiring it.
+    fn state(&self) -> LockState {
+        if self.is_locked_exclusive() {
+            LockState::Write
+        } else if self.is_locked() {
+            LockState::Read
+        } else {
+            LockState::Unlocked
+        }
+    }
+}
+
+#[cfg(any(feature = "spin", doc))]
+#[doc(cfg(feature = "spin"))]
+impl ShadowLock for spin::RwLock<()> {
+    type ReadGuard<'a> = spin::RwLockReadGuard<'a, ()>;
+    type WriteGuard<'a> = spin::RwLockWriteGuard<'a, ()>;
+
+    fn read(&self) -> Self::ReadGuard<'_> {
+        spin::RwLock::read(self)
+    }
+
+    fn write(&self) -> Self::WriteGuard<'_> {
+        spin::RwLock::write(self)
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        spin::RwLock::try_read(self)
+    }
+
+    fn downgrade<'a>(guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+        spin::RwLockWriteGuard::downgrade(guard)
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        spin::RwLock::try_write(self)
+    }
+
+    /// Returns the state of the lock by its counters without acquiring it.
+    fn state(&self) -> LockState {
+        if self.writer_count() > 0 {
+            LockState::Write
+        } else if self.reader_count() > 0 {
+            LockState::Read
+        } else {
+            LockState::Unlocked
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Order in which several cells are locked at once.
+
+use std::{
+    cmp::Ordering,
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash},
+};
+
+/// Order in which [`read_cells`](crate::LockerRoom::read_cells), [`write_cells`](crate::LockerRoom::write_cells)
+/// and the like lock cells.
+///
+/// Several cells can be locked without deadlocks only if every caller locks them in the same order, and this trait
+/// is the single source of that order for an index type. It's implemented for every [`Ord`] type as ascending order.
+/// Indices which aren't `Ord`, e.g. keys of a [`HashMap`](std::collections::HashMap), may implement it with
+/// [`cmp_by_hash`]:
+/// ```
+/// # use std::cmp::Ordering;
+/// # use std::collections::HashMap;
+/// # use lockerroom::{cmp_by_hash, CanonicalOrder, LockerRoom};
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Key(&'static str);
+///
+/// impl CanonicalOrder for Key {
+///     fn canonical_cmp(&self, other: &Self) -> Ordering {
+///         cmp_by_hash(self, other)
+///     }
+/// }
+///
+/// let locker_room: LockerRoom<_> = HashMap::from([(Key("a"), 1), (Key("b"), 2)]).into();
+/// let mut cells = locker_room.write_cells([Key("b"), Key("a")]).unwrap();
+/// *cells.get_mut(Key("a")).unwrap() += 10;
+/// assert_eq!(Some(&11), cells.get(Key("a")));
+/// ```
+pub trait CanonicalOrder: Eq {
+    /// Compares two indices. Equal indices must compare [`Ordering::Equal`], and the order must be the same in
+    /// every thread for the lifetime of the `LockerRoom`.
+    fn canonical_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl<T: Ord> CanonicalOrder for T {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+/// Compares the values by their hashes, which are the same in every thread of the process.
+///
+/// Distinct values with equal hashes compare [`Ordering::Equal`] and are locked in the order they are given, so
+/// in the unlikely case of a collision of 64-bit hashes two calls locking both values in different orders can
+/// still deadlock.
+pub fn cmp_by_hash<T: Hash + ?Sized>(a: &T, b: &T) -> Ordering {
+    let hasher = BuildHasherDefault::<DefaultHasher>::default();
+    hasher.hash_one(a).cmp(&hasher.hash_one(b))
+}
+
+// Sorts the indices in canonical order and removes repeats, also among tied distinct indices.
+pub(crate) fn sort_indices<I: CanonicalOrder>(indices: &mut Vec<I>) {
+    indices.sort_by(I::canonical_cmp);
+    let mut sorted: Vec<I> = Vec::with_capacity(indices.len());
+    // Start of the run of indices tied with the last one.
+    let mut run = 0;
+    for index in indices.drain(..) {
+        if sorted
+            .get(run)
+            .is_some_and(|first| first.canonical_cmp(&index).is_ne())
+        {
+            run = sorted.len();
+        }
+        if !sorted[run..].contains(&index) {
+            sorted.push(index);
+        }
+    }
+    *indices = sorted;
+}
+
+// Finds the position of the index among indices sorted by `sort_indices`.
+pub(crate) fn position<I: CanonicalOrder, E>(
+    cells: &[E],
+    index: &I,
+    idx: impl Fn(&E) -> &I,
+) -> Option<usize> {
+    let start = cells.partition_point(|cell| idx(cell).canonical_cmp(index).is_lt());
+    cells[start..]
+        .iter()
+        .take_while(|cell| idx(cell).canonical_cmp(index).is_eq())
+        .position(|cell| idx(cell) == index)
+        .map(|offset| start + offset)
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::cmp::Ordering;
+
+    use super::{position, sort_indices, CanonicalOrder};
+
+    // Every key ties with every other, like keys with colliding hashes.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Tied(u8);
+
+    impl CanonicalOrder for Tied {
+        fn canonical_cmp(&self, _other: &Self) -> Ordering {
+            Ordering::Equal
+        }
+    }
+
+    #[test]
+    fn ties() {
+        let mut indices = vec![Tied(1), Tied(2), Tied(1), Tied(3), Tied(2)];
+        sort_indices(&mut indices);
+        assert_eq!(vec![Tied(1), Tied(2), Tied(3)], indices);
+        assert_eq!(Some(2), position(&indices, &Tied(3), |index| index));
+        assert_eq!(None, position(&indices, &Tied(4), |index| index));
+
+        let mut indices = vec![3, 1, 3, 2, 1];
+        sort_indices(&mut indices);
+        assert_eq!(vec![1, 2, 3], indices);
+        assert_eq!(Some(1), position(&indices, &2, |index| index));
+        assert_eq!(None, position(&indices, &0, |index| index));
+    }
+}
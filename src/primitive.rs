@@ -0,0 +1,125 @@
+//! Synchronization primitives of [`LockerRoom`](crate::LockerRoom), which are
+//! replaced with [`loom`](https://docs.rs/loom/latest/loom/index.html)'s models under `cfg(loom)`.
+//!
+//! Run the models with `RUSTFLAGS="--cfg loom" cargo test --lib --release loom`.
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::{
+    atomic::{fence, AtomicUsize, Ordering},
+    Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+
+#[cfg(loom)]
+pub(crate) use loom::sync::{
+    atomic::{fence, AtomicUsize, Ordering},
+    Condvar, Mutex, MutexGuard, RwLockReadGuard,
+};
+
+#[cfg(loom)]
+pub(crate) use model::{RwLock, RwLockWriteGuard};
+
+/// Signals that the current thread is busy-waiting.
+pub(crate) fn spin_loop() {
+    #[cfg(not(loom))]
+    std::hint::spin_loop();
+    // The model must switch to another thread, otherwise it would spin forever.
+    #[cfg(loom)]
+    loom::thread::yield_now();
+}
+
+/// Atomically downgrades exclusive write access to shared read access.
+pub(crate) fn downgrade<'a>(guard: RwLockWriteGuard<'a, ()>) -> RwLockReadGuard<'a, ()> {
+    #[cfg(not(loom))]
+    return RwLockWriteGuard::downgrade(guard);
+    // The model can't downgrade, so the write lock is released while the gate keeps other writers out.
+    #[cfg(loom)]
+    {
+        let lock = guard.lock;
+        let _gate = lock.gate.lock().unwrap();
+        drop(guard);
+        lock.lock.read().unwrap()
+    }
+}
+
+/// Clears poisoning of the lock.
+pub(crate) fn clear_poison(lock: &RwLock<()>) {
+    #[cfg(not(loom))]
+    lock.clear_poison();
+    // Locks of the model are never poisoned by the panics it's interested in.
+    #[cfg(loom)]
+    let _ = lock;
+}
+
+/// Model of [`std::sync::RwLock`] that can be downgraded.
+#[cfg(loom)]
+mod model {
+    use std::{
+        ops::{Deref, DerefMut},
+        sync::{LockResult, TryLockError, TryLockResult},
+    };
+
+    /// loom's `RwLock` which is write locked only under the gate,
+    /// so the holder of the gate can swap a write lock for a read one with no writer in between.
+    #[derive(Debug, Default)]
+    pub struct RwLock<T> {
+        pub(super) lock: loom::sync::RwLock<T>,
+        pub(super) gate: loom::sync::Mutex<()>,
+    }
+
+    impl<T> RwLock<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self {
+                lock: loom::sync::RwLock::new(value),
+                gate: loom::sync::Mutex::new(()),
+            }
+        }
+
+        pub(crate) fn read(&self) -> LockResult<loom::sync::RwLockReadGuard<'_, T>> {
+            self.lock.read()
+        }
+
+        pub(crate) fn try_read(&self) -> TryLockResult<loom::sync::RwLockReadGuard<'_, T>> {
+            self.lock.try_read()
+        }
+
+        pub(crate) fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+            loop {
+                match self.try_write() {
+                    Err(TryLockError::WouldBlock) => loom::thread::yield_now(),
+                    Err(TryLockError::Poisoned(_)) => {
+                        unreachable!("locks of the model are never poisoned")
+                    }
+                    Ok(guard) => return Ok(guard),
+                }
+            }
+        }
+
+        pub(crate) fn try_write(&self) -> TryLockResult<RwLockWriteGuard<'_, T>> {
+            let _gate = self.gate.lock().unwrap();
+            match self.lock.try_write() {
+                Ok(guard) => Ok(RwLockWriteGuard { lock: self, guard }),
+                Err(_) => Err(TryLockError::WouldBlock),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct RwLockWriteGuard<'a, T> {
+        pub(super) lock: &'a RwLock<T>,
+        guard: loom::sync::RwLockWriteGuard<'a, T>,
+    }
+
+    impl<T> Deref for RwLockWriteGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.guard
+        }
+    }
+
+    impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.guard
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! This module describes readers-writer locks which a thread can lock with shared read access recursively.
+
+use std::{
+    collections::HashMap,
+    mem,
+    thread::{self, ThreadId},
+};
+
+use crate::{
+    primitive::{Condvar, Mutex, MutexGuard},
+    LockState, ShadowLock,
+};
+
+/// Readers-writer lock which the thread already holding it with shared read access can lock with shared read access
+/// again without waiting.
+///
+/// Recursive [`read_cell`](crate::LockerRoom::read_cell) of one cell isn't safe with other locks: e.g. on Linux
+/// [`std::sync::RwLock`] makes new readers wait for a queued writer, which waits for the first read guard of the same
+/// thread, so the thread deadlocks. This lock counts read guards held by every thread, so readers wait for queued
+/// writers unless they already hold the lock, which keeps writers from starving without blocking recursion.
+/// The lock of whole collection is the same type, so its recursion is also safe.
+///
+/// Only shared read access is reentrant: a thread holding exclusive write access still deadlocks if it locks the same
+/// cell one more time. And bookkeeping of readers behind a mutex makes every lock and unlock slower than that of
+/// `RwLock`, so choose it only for recursive algorithms.
+/// ```
+/// # use std::{sync::Arc, thread, time::Duration};
+/// # use lockerroom::{LockerRoom, ReentrantReadLocks};
+/// let locker_room = Arc::new(
+///     LockerRoom::builder(vec![1, 2, 3])
+///         .shadow_locks::<ReentrantReadLocks>()
+///         .build(),
+/// );
+/// let outer = locker_room.read_cell(0).unwrap();
+/// let writer = thread::spawn({
+///     let locker_room = Arc::clone(&locker_room);
+///     move || *locker_room.write_cell(0).unwrap() += 10
+/// });
+/// thread::sleep(Duration::from_millis(10));
+/// // The writer waits for the outer guard, but doesn't make the inner one wait.
+/// let inner = locker_room.read_cell(0).unwrap();
+/// assert_eq!(2, *outer + *inner);
+/// drop((outer, inner));
+/// writer.join().unwrap();
+/// assert_eq!(11, *locker_room.read_cell(0).unwrap());
+/// ```
+#[derive(Default)]
+pub struct ReentrantReadLock {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+/// Shadow locks of index-based collections made of [`ReentrantReadLock`].
+pub type ReentrantReadLocks = Vec<ReentrantReadLock>;
+
+#[derive(Default)]
+struct State {
+    writer: bool,
+    // Number of read guards held by every reading thread.
+    readers: HashMap<ThreadId, usize>,
+    waiting_writers: usize,
+}
+
+/// RAII guard of [`ReentrantReadLock`]'s shared read access.
+///
+/// It's counted for the thread which locked it, even if it's dropped by another thread.
+pub struct ReentrantReadGuard<'a> {
+    lock: &'a ReentrantReadLock,
+    thread: ThreadId,
+}
+
+/// RAII guard of [`ReentrantReadLock`]'s exclusive write access.
+pub struct ReentrantWriteGuard<'a> {
+    lock: &'a ReentrantReadLock,
+}
+
+impl State {
+    // Locks with shared read access if the thread already holds it or nobody writes or waits to write.
+    fn read(&mut self, thread: ThreadId) -> bool {
+        if let Some(depth) = self.readers.get_mut(&thread) {
+            *depth += 1;
+            return true;
+        }
+        if self.writer || self.waiting_writers > 0 {
+            return false;
+        }
+        self.readers.insert(thread, 1);
+        true
+    }
+}
+
+impl ReentrantReadLock {
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.condvar
+            .wait(state)
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn read_guard(&self, thread: ThreadId) -> ReentrantReadGuard<'_> {
+        ReentrantReadGuard { lock: self, thread }
+    }
+}
+
+impl Drop for ReentrantReadGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.lock.lock_state();
+        let depth = state
+            .readers
+            .get_mut(&self.thread)
+            .expect("every read guard is counted");
+        *depth -= 1;
+        if *depth == 0 {
+            state.readers.remove(&self.thread);
+            if state.readers.is_empty() {
+                self.lock.condvar.notify_all();
+            }
+        }
+    }
+}
+
+impl Drop for ReentrantWriteGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.lock_state().writer = false;
+        self.lock.condvar.notify_all();
+    }
+}
+
+impl ShadowLock for ReentrantReadLock {
+    type ReadGuard<'a> = ReentrantReadGuard<'a>;
+    type WriteGuard<'a> = ReentrantWriteGuard<'a>;
+
+    fn read(&self) -> Self::ReadGuard<'_> {
+        let thread = thread::current().id();
+        let mut state = self.lock_state();
+        while !state.read(thread) {
+            state = self.wait(state);
+        }
+        self.read_guard(thread)
+    }
+
+    fn write(&self) -> Self::WriteGuard<'_> {
+        let mut state = self.lock_state();
+        state.waiting_writers += 1;
+        while state.writer || !state.readers.is_empty() {
+            state = self.wait(state);
+        }
+        state.waiting_writers -= 1;
+        state.writer = true;
+        ReentrantWriteGuard { lock: self }
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        let thread = thread::current().id();
+        self.lock_state()
+            .read(thread)
+            .then(|| self.read_guard(thread))
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        let mut state = self.lock_state();
+        if state.writer || !state.readers.is_empty() {
+            return None;
+        }
+        state.writer = true;
+        Some(ReentrantWriteGuard { lock: self })
+    }
+
+    fn downgrade<'a>(guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+        let lock = guard.lock;
+        mem::forget(guard);
+        let thread = thread::current().id();
+        let mut state = lock.lock_state();
+        state.writer = false;
+        state.readers.insert(thread, 1);
+        // Other readers may go as well.
+        lock.condvar.notify_all();
+        lock.read_guard(thread)
+    }
+
+    /// Returns the exact state of the lock without acquiring it.
+    fn state(&self) -> LockState {
+        let state = self.lock_state();
+        if state.writer {
+            LockState::Write
+        } else if !state.readers.is_empty() {
+            LockState::Read
+        } else {
+            LockState::Unlocked
+        }
+    }
+}
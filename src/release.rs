@@ -0,0 +1,65 @@
+//! Release of guards in a fixed order.
+
+use std::mem::ManuallyDrop;
+
+/// Guards of cell locks together with the guard of the lock of whole collection.
+///
+/// Dropping it releases the cell locks first and the lock of whole collection after them, so a thread that
+/// locks whole collection never finds a cell of it still locked. The order is kept by [`Drop`] rather than
+/// by the order of fields, so reordering fields of a guard can't break it.
+pub(crate) struct OrderedGuards<C, G> {
+    cells: ManuallyDrop<C>,
+    global: ManuallyDrop<G>,
+}
+
+impl<C, G> OrderedGuards<C, G> {
+    pub(crate) fn new(cells: C, global: G) -> Self {
+        Self {
+            cells: ManuallyDrop::new(cells),
+            global: ManuallyDrop::new(global),
+        }
+    }
+
+    pub(crate) fn cells(&self) -> &C {
+        &self.cells
+    }
+
+    pub(crate) fn cells_mut(&mut self) -> &mut C {
+        &mut self.cells
+    }
+}
+
+impl<C, G> Drop for OrderedGuards<C, G> {
+    fn drop(&mut self) {
+        // SAFETY: both fields are dropped exactly once, here, and never used afterwards.
+        unsafe {
+            ManuallyDrop::drop(&mut self.cells);
+            ManuallyDrop::drop(&mut self.global);
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::cell::RefCell;
+
+    use super::OrderedGuards;
+
+    struct Tracked<'a>(&'static str, &'a RefCell<Vec<&'static str>>);
+
+    impl Drop for Tracked<'_> {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    #[test]
+    fn cells_before_global() {
+        let log = RefCell::new(Vec::new());
+        drop(OrderedGuards::new(
+            vec![Tracked("cell 0", &log), Tracked("cell 1", &log)],
+            Tracked("global", &log),
+        ));
+        assert_eq!(vec!["cell 0", "cell 1", "global"], *log.borrow());
+    }
+}
@@ -0,0 +1,195 @@
+//! This module describes sequence locks which let readers of `Copy` cells go without locking.
+
+use crate::{
+    primitive::{self, fence, AtomicUsize, Ordering, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    LockState, ShadowLock,
+};
+
+/// Readers-writer lock with a sequence counter, which is odd while the lock is held with exclusive write access.
+///
+/// Besides the usual locking, it allows [`LockerRoom::get_cell_seq`](crate::LockerRoom::get_cell_seq) to copy the value
+/// of a cell without locking it at all: the value is read optimistically and read again if it was written in between.
+/// So readers never block writers and don't contend with each other, which pays off for read-heavy cells of small
+/// `Copy` values.
+///
+/// The optimistic read races with the writer by design and relies on the value being plain old data: any bit pattern
+/// the read may observe while the value is being written must be harmless to produce. A torn value is never returned,
+/// but it is still produced before being thrown away, so `get_cell_seq` only copies [`Pod`] values and types with
+/// invalid bit patterns like `bool`, `char`, enums or references can't be read this way.
+///
+/// Ordinary [`read_cell`](crate::LockerRoom::read_cell) and [`write_cell`](crate::LockerRoom::write_cell) are
+/// unaffected and lock it like [`std::sync::RwLock`].
+/// ```
+/// # use lockerroom::{LockerRoom, SeqLocks};
+/// let locker_room = LockerRoom::builder(vec![(0, 0); 4])
+///     .shadow_locks::<SeqLocks>()
+///     .build();
+/// *locker_room.write_cell(1).unwrap() = (1, 1);
+/// assert_eq!(Some((1, 1)), locker_room.get_cell_seq(1));
+/// ```
+pub struct SeqLock {
+    seq: AtomicUsize,
+    lock: RwLock<()>,
+}
+
+/// Shadow locks of index-based collections made of [`SeqLock`].
+pub type SeqLocks = Vec<SeqLock>;
+
+/// Plain old data: `Copy` types for which every bit pattern is a valid value, so a value copied while it's being
+/// written is torn, but still valid.
+///
+/// It's implemented for integers, floats and arrays and tuples of them, which is what
+/// [`LockerRoom::get_cell_seq`](crate::LockerRoom::get_cell_seq) may read optimistically.
+///
+/// # Safety
+///
+/// Every bit pattern of the size of the type, except its padding, must be a valid value of it.
+pub unsafe trait Pod: Copy {}
+
+macro_rules! pod {
+    ($($t:ty),*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+macro_rules! pod_tuple {
+    ($($t:ident),*) => {
+        unsafe impl<$($t: Pod),*> Pod for ($($t,)*) {}
+    };
+}
+
+pod_tuple!(A);
+pod_tuple!(A, B);
+pod_tuple!(A, B, C);
+pod_tuple!(A, B, C, D);
+
+/// RAII guard of [`SeqLock`]'s exclusive write access, which makes the sequence counter even again once it is dropped.
+pub struct SeqLockWriteGuard<'a> {
+    seq: &'a AtomicUsize,
+    guard: Option<RwLockWriteGuard<'a, ()>>,
+}
+
+impl SeqLock {
+    /// Calls `read` until it runs while no writer holds the lock and returns its result.
+    ///
+    /// `read` may run concurrently with a writer, so its result is thrown away unless the sequence counter stayed
+    /// the same and even.
+    pub(crate) fn read_with<R>(&self, mut read: impl FnMut() -> R) -> R {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq % 2 == 1 {
+                primitive::spin_loop();
+                continue;
+            }
+            let value = read();
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                return value;
+            }
+        }
+    }
+
+    fn guard<'a>(&'a self, guard: RwLockWriteGuard<'a, ()>) -> SeqLockWriteGuard<'a> {
+        self.seq.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        SeqLockWriteGuard {
+            seq: &self.seq,
+            guard: Some(guard),
+        }
+    }
+}
+
+impl Default for SeqLock {
+    fn default() -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            lock: RwLock::new(()),
+        }
+    }
+}
+
+impl Drop for SeqLockWriteGuard<'_> {
+    fn drop(&mut self) {
+        if self.guard.is_some() {
+            self.seq.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+impl ShadowLock for SeqLock {
+    type ReadGuard<'a> = RwLockReadGuard<'a, ()>;
+    type WriteGuard<'a> = SeqLockWriteGuard<'a>;
+
+    fn read(&self) -> Self::ReadGuard<'_> {
+        ShadowLock::read(&self.lock)
+    }
+
+    fn write(&self) -> Self::WriteGuard<'_> {
+        self.guard(ShadowLock::write(&self.lock))
+    }
+
+    fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+        ShadowLock::try_read(&self.lock)
+    }
+
+    fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+        ShadowLock::try_write(&self.lock).map(|guard| self.guard(guard))
+    }
+
+    fn downgrade<'a>(mut guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+        guard.seq.fetch_add(1, Ordering::Release);
+        let write_guard = guard.guard.take().expect("guard is taken only here");
+        primitive::downgrade(write_guard)
+    }
+
+    fn state(&self) -> LockState {
+        if self.seq.load(Ordering::Relaxed) % 2 == 1 {
+            LockState::Write
+        } else {
+            ShadowLock::state(&self.lock)
+        }
+    }
+}
+
+#[cfg(loom)]
+mod loom_test {
+    use loom::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use crate::ShadowLock;
+
+    use super::SeqLock;
+
+    #[test]
+    fn read_with() {
+        loom::model(|| {
+            let lock = Arc::new(SeqLock::default());
+            let cell = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+            let writer = thread::spawn({
+                let (lock, cell) = (lock.clone(), cell.clone());
+                move || {
+                    let _guard = lock.write();
+                    cell[0].store(1, Ordering::Relaxed);
+                    cell[1].store(1, Ordering::Relaxed);
+                }
+            });
+            let (a, b) = lock.read_with(|| {
+                (
+                    cell[0].load(Ordering::Relaxed),
+                    cell[1].load(Ordering::Relaxed),
+                )
+            });
+            assert_eq!(a, b);
+            writer.join().unwrap();
+        });
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use crate::{AdaptiveLocks, BlockLocks, Collection, ShadowLocksCollection};
+
+use super::{LockObserver, LockerRoom};
+
+/// Builder of [`LockerRoom`] which allows to choose its shadow locks.
+///
+/// It's the single entry point to the locking strategies: a lock per cell with [`per_cell`](Self::per_cell),
+/// which is the default, a lock per block of cells with [`block`](Self::block), spinning locks with
+/// [`adaptive`](Self::adaptive), or any shadow locks with [`shadow_locks`](Self::shadow_locks). Strategies which only
+/// fit some index types, like `block` for `usize`, are only available for them.
+///
+/// Shadow locks also define the lock type and so the fairness policy of the `LockerRoom`. See [`ShadowLock`](crate::ShadowLock)
+/// for the policies of available locks.
+/// ```
+/// # use lockerroom::LockerRoom;
+/// # #[cfg(feature = "parking_lot")] {
+/// let locker_room = LockerRoom::builder(vec![0, 1, 2])
+///     .shadow_locks::<Vec<parking_lot::RwLock<()>>>()
+///     .build();
+/// *locker_room.write_cell(0).unwrap() += 1;
+/// assert_eq!(1, *locker_room.read_cell(0).unwrap());
+/// # }
+/// ```
+pub struct LockerRoomBuilder<T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    collection: T,
+    shadow_locks: ShadowLocks<T, S>,
+    observer: Option<Arc<dyn LockObserver<T::Idx>>>,
+}
+
+// Shadow locks are either created by the collection or created empty and filled with its indices by `build`.
+enum ShadowLocks<T, S> {
+    Create(fn(&T) -> S),
+    Fill(S),
+}
+
+impl<T> LockerRoomBuilder<T>
+where
+    T: Collection,
+{
+    pub(crate) fn new(collection: T) -> Self {
+        Self {
+            collection,
+            shadow_locks: ShadowLocks::Create(T::shadow_locks),
+            observer: None,
+        }
+    }
+}
+
+impl<T, S> LockerRoomBuilder<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    /// Chooses `L` as shadow locks of the `LockerRoom`.
+    ///
+    /// Shadow locks are created with [`Default`] and then filled by [`update_indices`](ShadowLocksCollection::update_indices).
+    pub fn shadow_locks<L>(self) -> LockerRoomBuilder<T, L>
+    where
+        L: ShadowLocksCollection<Idx = T::Idx> + Default,
+    {
+        LockerRoomBuilder {
+            collection: self.collection,
+            shadow_locks: ShadowLocks::Fill(L::default()),
+            observer: self.observer,
+        }
+    }
+
+    /// Chooses the default shadow locks of the collection, [`Collection::ShadowLocks`], which usually have a lock
+    /// per cell.
+    pub fn per_cell(self) -> LockerRoomBuilder<T> {
+        LockerRoomBuilder {
+            collection: self.collection,
+            shadow_locks: ShadowLocks::Create(T::shadow_locks),
+            observer: self.observer,
+        }
+    }
+
+    /// Chooses [`BlockLocks`], where every lock is shared by `block_size` consecutive cells, as shadow locks.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `block_size` is zero.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room = LockerRoom::builder(vec![0; 8]).block(4).build();
+    /// *locker_room.write_cell(4).unwrap() += 1;
+    /// ```
+    pub fn block(self, block_size: usize) -> LockerRoomBuilder<T, BlockLocks>
+    where
+        T: Collection<Idx = usize>,
+    {
+        LockerRoomBuilder {
+            collection: self.collection,
+            shadow_locks: ShadowLocks::Fill(BlockLocks::new(block_size)),
+            observer: self.observer,
+        }
+    }
+
+    /// Chooses [`AdaptiveLocks`] which spin `spins` times before they park the thread as shadow locks.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room = LockerRoom::builder(vec![0; 4]).adaptive(16).build();
+    /// *locker_room.write_cell(1).unwrap() += 1;
+    /// ```
+    pub fn adaptive(self, spins: u32) -> LockerRoomBuilder<T, AdaptiveLocks>
+    where
+        T: Collection<Idx = usize>,
+    {
+        LockerRoomBuilder {
+            collection: self.collection,
+            shadow_locks: ShadowLocks::Fill(AdaptiveLocks::with_spins(spins)),
+            observer: self.observer,
+        }
+    }
+
+    /// Sets the observer which will be notified about locking of cells. See [`LockObserver`].
+    pub fn observer(mut self, observer: Arc<dyn LockObserver<T::Idx>>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Creates the configured `LockerRoom`.
+    pub fn build(self) -> LockerRoom<T, S> {
+        let shadow_locks = match self.shadow_locks {
+            ShadowLocks::Create(create) => create(&self.collection),
+            ShadowLocks::Fill(mut shadow_locks) => {
+                shadow_locks.update_indices(self.collection.indices());
+                shadow_locks
+            }
+        };
+        LockerRoom::with_shadow_locks(self.collection, shadow_locks, self.observer)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{Collection, LockerRoom, ShadowLocksCollection};
+
+/// [`LockerRoom`] without the lock of whole collection.
+///
+/// Guards of `LockerRoomCells` hold only the lock of their cell, so they are smaller and cheaper to acquire and
+/// release. In exchange cells can't be inserted or removed, since there is no `lock_room`: the set of cells is fixed
+/// when the `LockerRoomCells` is created. See [`LockerRoom`](crate::LockerRoom#without-the-lock-of-whole-collection)
+/// for the methods it has.
+///
+/// It's created from a `LockerRoom`, which keeps its shadow locks, rather than from a collection, so that
+/// `LockerRoom::from(collection)` still infers that the `LockerRoom` has the lock of whole collection.
+/// ```
+/// # use std::thread;
+/// # use lockerroom::{LockerRoom, sync::LockerRoomCells};
+/// let locker_room: LockerRoomCells<_> = LockerRoom::from(vec![0, 1, 2]).into();
+/// thread::scope(|scope| {
+///     scope.spawn(|| *locker_room.write_cell(0).unwrap() += 1);
+///     scope.spawn(|| *locker_room.write_cell(0).unwrap() += 2);
+/// });
+/// assert_eq!(3, *locker_room.read_cell(0).unwrap());
+/// ```
+pub type LockerRoomCells<T, S = <T as Collection>::ShadowLocks> = LockerRoom<T, S, false>;
+
+/// Slice of cells with a lock for every element, which threads share by `&` reference and write element by element.
+///
+/// Its length is fixed when it's created, so it needs no lock of whole collection. The elements aren't wrapped into
+/// [`UnsafeCell`](std::cell::UnsafeCell) one by one: the whole slice is already behind one, and every element is only reached through its lock.
+/// ```
+/// # use std::thread;
+/// # use lockerroom::{LockerRoom, sync::SharedSlice};
+/// let slice: SharedSlice<_> = LockerRoom::from(vec![0; 4].into_boxed_slice()).into();
+/// thread::scope(|scope| {
+///     for i in 0..8 {
+///         let slice = &slice;
+///         scope.spawn(move || *slice.write_cell(i % 4).unwrap() += 1);
+///     }
+/// });
+/// assert_eq!(&[2, 2, 2, 2][..], &*slice.into_inner());
+/// ```
+pub type SharedSlice<T> = LockerRoomCells<Box<[T]>>;
+
+/// Keeps the shadow locks of the `LockerRoom` and drops the lock of whole collection.
+/// ```
+/// # use lockerroom::{LockerRoom, sync::LockerRoomCells};
+/// let locker_room: LockerRoom<_> = vec![0, 1].into();
+/// *locker_room.lock_room() = vec![2, 3, 4];
+/// let locker_room: LockerRoomCells<_> = locker_room.into();
+/// assert_eq!(4, *locker_room.read_cell(2).unwrap());
+/// ```
+impl<T, S> From<LockerRoom<T, S>> for LockerRoomCells<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    fn from(locker_room: LockerRoom<T, S>) -> Self {
+        let (collection, index_locks) = locker_room.into_parts();
+        Self::with_shadow_locks(collection, index_locks, None)
+    }
+}
+
+impl<T> Default for LockerRoomCells<T>
+where
+    T: Collection + Default,
+{
+    fn default() -> Self {
+        LockerRoom::from(T::default()).into()
+    }
+}
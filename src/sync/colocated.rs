@@ -0,0 +1,199 @@
+use std::{borrow::Borrow, sync::RwLock};
+
+use super::{
+    LockedCells, LockedMultiWriteGuard, LockedReadCellGuard, LockedRoomGuard, LockedWriteCellGuard,
+};
+
+/// Provides readers-writer lock for each cell of a vector, like [`LockerRoom`](crate::LockerRoom), but keeps every
+/// value next to its lock.
+///
+/// `LockerRoom` keeps the collection and its shadow locks apart, so locking a cell at a random index touches the lock
+/// in one place and the value in another, which is often two cache misses. Here the value lives inside its lock, so
+/// both are usually on the same cache line. Cells are locked like in [`LockedCells`], which it's built on, and
+/// `cargo bench --bench colocated` compares random access to both layouts.
+///
+/// The price is paid by whole collection: the values aren't contiguous anymore, since every one of them is padded by
+/// its lock. So the guard of [`lock_room`](Self::lock_room) can't give a slice or the vector of values, and bulk
+/// operations like sorting or copying the values go cell by cell. Scans of whole collection also read more memory.
+///
+/// Unlike the vectors and maps of this crate, it isn't a [`Collection`](crate::Collection) to put into `LockerRoom`,
+/// but a container of its own, like [`LockedCells`] it's built on. `LockerRoom` owns the collection and its shadow
+/// locks apart, and the shadow locks must own every lock they return. Locks living inside the collection
+/// could only be lent to them by pointers, which would dangle once `lock_room` reallocates the values, and
+/// [`update_indices`](crate::ShadowLocksCollection::update_indices) only sees the indices, so it can't refresh them.
+/// So only cell locking and the guard of whole collection are provided here, while the builder, batch guards,
+/// observers and stats of `LockerRoom` don't apply to it.
+/// ```
+/// # use std::thread;
+/// # use lockerroom::sync::Colocated;
+/// let locker_room: Colocated<_> = vec![0, 1].into();
+/// thread::scope(|scope| {
+///     scope.spawn(|| *locker_room.write_cell(0).unwrap() += 1);
+///     scope.spawn(|| *locker_room.write_cell(0).unwrap() += 2);
+/// });
+/// locker_room.lock_room().push(2);
+/// assert_eq!(3, *locker_room.read_cell(0).unwrap());
+/// assert_eq!(vec![3, 1, 2], locker_room.into_inner());
+/// ```
+pub struct Colocated<T> {
+    cells: LockedCells<T>,
+}
+
+impl<'a, T> Colocated<T> {
+    /// Creates `Colocated` cells, moving every value into its lock.
+    pub fn new(values: Vec<T>) -> Self {
+        Self {
+            cells: values
+                .into_iter()
+                .map(RwLock::new)
+                .collect::<Vec<_>>()
+                .into(),
+        }
+    }
+
+    /// Locks cell at the index with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell(&'a self, index: impl Borrow<usize>) -> Option<LockedReadCellGuard<'a, T>> {
+        self.cells.read_cell(index)
+    }
+
+    /// Locks cell at the index with exclusive write access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell(&'a self, index: impl Borrow<usize>) -> Option<LockedWriteCellGuard<'a, T>> {
+        self.cells.write_cell(index)
+    }
+
+    /// Locks cells at the indices with exclusive write access, see [`LockedCells::write_cells`].
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Option<LockedMultiWriteGuard<'a, T>> {
+        self.cells.write_cells(indices)
+    }
+
+    /// Exclusively locks whole collection with write access, blocking the current thread until it can be acquired.
+    ///
+    /// The guard inserts and removes values at the end and reaches them one by one, since they aren't contiguous.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn lock_room(&'a self) -> ColocatedRoomGuard<'a, T> {
+        ColocatedRoomGuard {
+            cells: self.cells.lock_room(),
+        }
+    }
+
+    /// Consumes this `Colocated`, returning the values moved out of their locks.
+    pub fn into_inner(self) -> Vec<T> {
+        self.cells
+            .into_inner()
+            .into_iter()
+            .map(|cell| cell.into_inner().unwrap_or_else(|err| err.into_inner()))
+            .collect()
+    }
+}
+
+impl<T> From<Vec<T>> for Colocated<T> {
+    fn from(values: Vec<T>) -> Self {
+        Self::new(values)
+    }
+}
+
+/// RAII structure used to release the exclusive write access of the lock of whole [`Colocated`] when dropped.
+///
+/// This structure is created by the [`lock_room`](Colocated::lock_room) methods on [`Colocated`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct ColocatedRoomGuard<'a, T> {
+    cells: LockedRoomGuard<'a, T>,
+}
+
+// No cell is locked while whole collection is, so the values are reached through `get_mut` of their locks.
+impl<T> ColocatedRoomGuard<'_, T> {
+    /// Returns the number of cells.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns `true` if there are no cells.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns a mutable reference to the value at the index or `None` if there is no cell with such index.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let cell = self.cells.get_mut(index)?;
+        Some(cell.get_mut().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// An iterator visiting the values in order of indices, with mutable references to them.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells
+            .iter_mut()
+            .map(|cell| cell.get_mut().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// Appends a cell with the value.
+    pub fn push(&mut self, value: T) {
+        self.cells.push(RwLock::new(value));
+    }
+
+    /// Removes the last cell and returns its value, or `None` if there are no cells.
+    pub fn pop(&mut self) -> Option<T> {
+        let cell = self.cells.pop()?;
+        Some(cell.into_inner().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// Removes the cells from `len` on, keeping the first `len` of them.
+    pub fn truncate(&mut self, len: usize) {
+        self.cells.truncate(len);
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use super::Colocated;
+
+    #[test]
+    fn colocated() {
+        let locker_room: Colocated<_> = vec![0, 1, 2].into();
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            let cell = locker_room.write_cell(1).unwrap();
+            scope.spawn(|| {
+                assert_eq!(
+                    2,
+                    *locker_room.read_cell(0).unwrap() + *locker_room.read_cell(2).unwrap()
+                );
+                sender.send(*locker_room.read_cell(1).unwrap()).unwrap();
+            });
+            // Only the locked cell is waited for.
+            assert!(receiver.recv_timeout(Duration::from_millis(20)).is_err());
+            drop(cell);
+            assert_eq!(1, receiver.recv().unwrap());
+        });
+        let mut cells = locker_room.write_cells([2, 0]).unwrap();
+        *cells.get_mut(2).unwrap() += 10;
+        drop(cells);
+
+        let mut room = locker_room.lock_room();
+        room.push(3);
+        room.iter_mut().for_each(|value| *value *= 2);
+        assert_eq!(Some(6), room.pop());
+        *room.get_mut(0).unwrap() = 5;
+        assert!(room.get_mut(3).is_none());
+        room.truncate(2);
+        assert_eq!(2, room.len());
+        drop(room);
+        assert!(locker_room.read_cell(2).is_none());
+        assert_eq!(vec![5, 2], locker_room.into_inner());
+    }
+}
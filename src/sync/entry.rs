@@ -0,0 +1,100 @@
+use crate::{Collection, MapCollection, ShadowLocksCollection};
+
+use super::{LockerRoom, WriteCellGuard};
+
+/// A view into a single cell of a map-backed [`LockerRoom`], which may either be occupied or vacant.
+///
+/// This enum is constructed from the [`entry`](LockerRoom::entry) method on [`LockerRoom`].
+pub enum Entry<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: MapCollection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// The cell exists and is locked with exclusive write access.
+    Occupied(WriteCellGuard<'a, T, S>),
+    /// There is no cell with the key. Nothing is locked.
+    Vacant(VacantEntry<'a, T, S>),
+}
+
+impl<'a, T, S> Entry<'a, T, S>
+where
+    T: MapCollection,
+    T::Idx: Clone,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Ensures a cell exists by inserting the default if vacant, and returns the write guard of the cell.
+    ///
+    /// See [`VacantEntry::insert`] for the locking of a vacant entry.
+    pub fn or_insert(self, default: T::Value) -> WriteCellGuard<'a, T, S> {
+        self.or_insert_with(|| default)
+    }
+
+    /// Ensures a cell exists by inserting the result of `default` if vacant, and returns the write guard of the cell.
+    ///
+    /// See [`VacantEntry::insert`] for the locking of a vacant entry.
+    pub fn or_insert_with(self, default: impl FnOnce() -> T::Value) -> WriteCellGuard<'a, T, S> {
+        match self {
+            Entry::Occupied(guard) => guard,
+            Entry::Vacant(entry) => entry.insert_with(default),
+        }
+    }
+}
+
+/// A view into a vacant cell of a map-backed [`LockerRoom`]. It is a part of the [`Entry`] enum.
+pub struct VacantEntry<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: MapCollection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    locker_room: &'a LockerRoom<T, S>,
+    key: T::Idx,
+}
+
+impl<'a, T, S> VacantEntry<'a, T, S>
+where
+    T: MapCollection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(locker_room: &'a LockerRoom<T, S>, key: T::Idx) -> Self {
+        Self { locker_room, key }
+    }
+
+    /// Gets a reference to the key of the entry.
+    pub fn key(&self) -> &T::Idx {
+        &self.key
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> T::Idx {
+        self.key
+    }
+}
+
+impl<'a, T, S> VacantEntry<'a, T, S>
+where
+    T: MapCollection,
+    T::Idx: Clone,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Inserts the value into the new cell and returns the write guard of the cell.
+    ///
+    /// Unlike locking an existing cell, inserting needs to exclusively lock whole collection, like
+    /// [`lock_room`](LockerRoom::lock_room) does, so the current thread blocks until all cell guards are dropped.
+    /// Then the lock of whole collection is atomically turned into the lock of the new cell.
+    ///
+    /// Other thread may insert the key after the entry was made. In that case the value is dropped and
+    /// the write guard of the existing cell is returned.
+    pub fn insert(self, value: T::Value) -> WriteCellGuard<'a, T, S> {
+        self.insert_with(|| value)
+    }
+
+    fn insert_with(self, value: impl FnOnce() -> T::Value) -> WriteCellGuard<'a, T, S> {
+        let mut room_guard = self.locker_room.lock_room();
+        if room_guard.index(&self.key).is_none() {
+            room_guard.insert_cell(self.key.clone(), value());
+        }
+        room_guard
+            .into_write_cell(&self.key)
+            .expect("cell exists while whole collection is locked")
+    }
+}
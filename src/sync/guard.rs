@@ -1,48 +1,193 @@
 //! Guards for different locking types.
 
 use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::VecDeque,
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
+    mem::ManuallyDrop,
     ops::{Deref, DerefMut},
-    sync::{RwLockReadGuard, RwLockWriteGuard},
+    ptr, thread,
 };
 
-use crate::{Collection, ShadowLocksCollection};
+use super::CellLogger;
+use crate::{
+    deadlock::{self, CellNames, Held},
+    order,
+    primitive::RwLock,
+    release::OrderedGuards,
+    CanonicalOrder, Collection, DrainCollection, ShadowLock, ShadowLocksCollection,
+};
+
+pub(crate) type LockReadGuard<'a, S> =
+    <<S as ShadowLocksCollection>::Lock as ShadowLock>::ReadGuard<'a>;
+pub(crate) type LockWriteGuard<'a, S> =
+    <<S as ShadowLocksCollection>::Lock as ShadowLock>::WriteGuard<'a>;
+// Guards of cell locks are tracked with feature `deadlock-detect` in debug builds.
+pub(crate) type CellReadGuard<'a, S> = Held<LockReadGuard<'a, S>>;
+pub(crate) type CellWriteGuard<'a, S> = Held<LockWriteGuard<'a, S>>;
+// Locked cells of multi-cell guards with their guards, sorted by index. A guard is missing for a cell whose lock is
+// shared with the previous cell and held by its guard.
+type ReadCells<'a, T, S> = Vec<(
+    <T as Collection>::Idx,
+    &'a <T as Collection>::Output,
+    Option<CellReadGuard<'a, S>>,
+)>;
+type WriteCells<'a, T, S> = Vec<(
+    <T as Collection>::Idx,
+    &'a mut <T as Collection>::Output,
+    Option<CellWriteGuard<'a, S>>,
+)>;
 
 /// RAII structure used to release the shared read access of a cell lock when dropped.
 ///
 /// This structure is created by the [`read_cell`](crate::LockerRoom::read_cell) methods on [`LockerRoom`](crate::LockerRoom).
-pub struct ReadCellGuard<'a, T>
+///
+/// It's formatted, hashed and compared like the value of the cell, as is [`WriteCellGuard`]:
+/// ```
+/// # use std::collections::HashSet;
+/// # use lockerroom::LockerRoom;
+/// let locker_room: LockerRoom<_> = vec![1, 2].into();
+/// let (a, b) = (locker_room.read_cell(0).unwrap(), locker_room.read_cell(1).unwrap());
+/// assert_eq!("1 < 2", format!("{a} < {b}"));
+/// assert!(a < b);
+/// let guards = HashSet::from([a, b]);
+/// assert!(guards.contains(&locker_room.read_cell(1).unwrap()));
+/// ```
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct ReadCellGuard<'a, T, S = <T as Collection>::ShadowLocks>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     value: &'a T::Output,
-    // For dropping and, after that, unlocking.
+    cell_rwlock: &'a S::Lock,
+    global_rwlock: &'a S::Lock,
+    // Releases the cell lock and, after it, the lock of whole collection.
     #[allow(dead_code)]
-    cell_rwlock_read_guard: RwLockReadGuard<'a, ()>,
-    // For dropping and, after that, unlocking. But it stands after cell guard because of order of dropping.
-    #[allow(dead_code)]
-    global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+    guards: OrderedGuards<CellReadGuard<'a, S>, LockReadGuard<'a, S>>,
 }
 
-impl<'a, T> ReadCellGuard<'a, T>
+impl<'a, T, S> ReadCellGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     pub(crate) fn new(
         value: &'a T::Output,
-        global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
-        cell_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+        (global_rwlock, global_rwlock_read_guard): (&'a S::Lock, LockReadGuard<'a, S>),
+        (cell_rwlock, cell_rwlock_read_guard): (&'a S::Lock, CellReadGuard<'a, S>),
     ) -> Self {
         Self {
             value,
-            global_rwlock_read_guard,
-            cell_rwlock_read_guard,
+            cell_rwlock,
+            global_rwlock,
+            guards: OrderedGuards::new(cell_rwlock_read_guard, global_rwlock_read_guard),
+        }
+    }
+
+    /// Makes an independent guard of the same cell, locking the cell and whole collection with shared read access once more.
+    ///
+    /// The locks are acquired without blocking: the current thread already holds them, so waiting behind a queued
+    /// writer, which in turn waits for `orig` to be dropped, would deadlock. This function returns `None` if
+    /// a writer is queued on one of the locks.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::clone_guard(&guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::ReadCellGuard};
+    /// let locker_room: LockerRoom<_> = vec![0, 1].into();
+    /// let guard = locker_room.read_cell(1).unwrap();
+    /// let cloned = ReadCellGuard::clone_guard(&guard).unwrap();
+    /// drop(guard);
+    /// assert_eq!(1, *cloned);
+    /// ```
+    pub fn clone_guard(orig: &Self) -> Option<Self> {
+        let global_rwlock_read_guard = orig.global_rwlock.try_read()?;
+        // Named like the cell of the original guard.
+        let cell_rwlock_read_guard =
+            deadlock::hold_again(orig.cell_rwlock, false, orig.cell_rwlock.try_read()?);
+        Some(Self {
+            value: orig.value,
+            cell_rwlock: orig.cell_rwlock,
+            global_rwlock: orig.global_rwlock,
+            guards: OrderedGuards::new(cell_rwlock_read_guard, global_rwlock_read_guard),
+        })
+    }
+
+    /// Makes a guard for a component of the cell value, e.g. `str` of a `String`. The cell stays locked with
+    /// shared read access until the new guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::ReadCellGuard};
+    /// let locker_room: LockerRoom<_> = vec![String::from("hello")].into();
+    /// let guard = ReadCellGuard::map(locker_room.read_cell(0).unwrap(), String::as_str);
+    /// assert_eq!("hello", &*guard);
+    /// ```
+    pub fn map<U>(
+        orig: Self,
+        f: impl FnOnce(&T::Output) -> &U,
+    ) -> MappedReadCellGuard<'a, U, S::Lock>
+    where
+        U: ?Sized,
+    {
+        MappedReadCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+
+    /// Attempts to make a guard for a component of the cell value. The original guard is given back if `f` returns `None`.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::try_map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    pub fn try_map<U>(
+        orig: Self,
+        f: impl FnOnce(&T::Output) -> Option<&U>,
+    ) -> Result<MappedReadCellGuard<'a, U, S::Lock>, Self>
+    where
+        U: ?Sized,
+    {
+        match f(orig.value) {
+            Some(value) => Ok(MappedReadCellGuard {
+                value,
+                guards: orig.guards,
+            }),
+            None => Err(orig),
         }
     }
 }
 
-impl<'a, T> Deref for ReadCellGuard<'a, T>
+impl<'a, T, S, V> ReadCellGuard<'a, T, S>
+where
+    T: Collection<Output = Option<V>>,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Makes a guard for the value inside the cell's `Option`, or releases the cell and returns `None` if the cell
+    /// holds `None`.
+    ///
+    /// This is an associated function that needs to be used as `ReadCellGuard::as_deref(guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::ReadCellGuard};
+    /// let locker_room: LockerRoom<Vec<Option<i32>>> = vec![Some(1), None].into();
+    /// let guard = ReadCellGuard::as_deref(locker_room.read_cell(0).unwrap()).unwrap();
+    /// assert_eq!(1, *guard);
+    /// assert!(ReadCellGuard::as_deref(locker_room.read_cell(1).unwrap()).is_none());
+    /// ```
+    pub fn as_deref(orig: Self) -> Option<MappedReadCellGuard<'a, V, S::Lock>> {
+        Self::try_map(orig, Option::as_ref).ok()
+    }
+}
+
+impl<'a, T, S> Deref for ReadCellGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     type Target = T::Output;
 
@@ -51,42 +196,543 @@ where
     }
 }
 
+impl<'a, T, S> fmt::Display for ReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<'a, T, S> Hash for ReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<'a, T, S> PartialEq for ReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<'a, T, S> Eq for ReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: Eq,
+{
+}
+
+impl<'a, T, S> PartialOrd for ReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
 /// RAII structure used to release the exclusive write access of a cell lock when dropped.
 ///
 /// This structure is created by the [`write_cell`](crate::LockerRoom::write_cell) methods on [`LockerRoom`](crate::LockerRoom).
-pub struct WriteCellGuard<'a, T>
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct WriteCellGuard<'a, T, S = <T as Collection>::ShadowLocks>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     value: &'a mut T::Output,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<CellWriteGuard<'a, S>, LockReadGuard<'a, S>>,
+}
+
+impl<'a, T, S> WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(
+        value: &'a mut T::Output,
+        global_rwlock_read_guard: LockReadGuard<'a, S>,
+        cell_rwlock_write_guard: CellWriteGuard<'a, S>,
+    ) -> Self {
+        Self {
+            value,
+            guards: OrderedGuards::new(cell_rwlock_write_guard, global_rwlock_read_guard),
+        }
+    }
+
+    /// Makes a guard for a component of the cell value, e.g. `str` of a `String`. The cell stays locked with
+    /// exclusive write access until the new guard is dropped.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::WriteCellGuard};
+    /// let locker_room: LockerRoom<_> = vec![String::from("hello")].into();
+    /// let mut guard = WriteCellGuard::map(locker_room.write_cell(0).unwrap(), String::as_mut_str);
+    /// guard.make_ascii_uppercase();
+    /// drop(guard);
+    /// assert_eq!("HELLO", *locker_room.read_cell(0).unwrap());
+    /// ```
+    pub fn map<U>(
+        orig: Self,
+        f: impl FnOnce(&mut T::Output) -> &mut U,
+    ) -> MappedWriteCellGuard<'a, U, S::Lock>
+    where
+        U: ?Sized,
+    {
+        MappedWriteCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+
+    /// Attempts to make a guard for a component of the cell value. The original guard is given back if `f` returns `None`.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::try_map(guard, ...)`,
+    /// so it doesn't conflict with a method on the cell value.
+    pub fn try_map<U>(
+        orig: Self,
+        f: impl FnOnce(&mut T::Output) -> Option<&mut U>,
+    ) -> Result<MappedWriteCellGuard<'a, U, S::Lock>, Self>
+    where
+        U: ?Sized,
+    {
+        // The reference is reborrowed through a raw pointer, so `orig` can be given back if `f` returns `None`.
+        let value: *mut T::Output = orig.value;
+        match f(unsafe { &mut *value }) {
+            Some(value) => Ok(MappedWriteCellGuard {
+                value,
+                guards: orig.guards,
+            }),
+            None => Err(orig),
+        }
+    }
+
+    /// Forgets the guard, but keeps the cell locked with exclusive write access, so it can be locked in one call
+    /// and released in another. The returned token releases the cell once it is passed to
+    /// [`LockerRoom::unlock_cell`](crate::LockerRoom::unlock_cell).
+    ///
+    /// If the token is dropped instead, the cell is never released: it can't be locked anymore and whole
+    /// collection can't be locked either. This is safe, but it's a deadlock waiting to happen. The token borrows
+    /// the `LockerRoom`, so erasing its lifetime, e.g. to pass it through FFI, is undefined behavior if the
+    /// `LockerRoom` is moved or dropped before the cell is released.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::leak(guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::WriteCellGuard};
+    /// let locker_room: LockerRoom<_> = vec![0].into();
+    /// let token = WriteCellGuard::leak(locker_room.write_cell(0).unwrap());
+    /// assert!(locker_room.try_lock_room().is_none());
+    /// locker_room.unlock_cell(token);
+    /// assert!(locker_room.try_lock_room().is_some());
+    /// ```
+    pub fn leak(orig: Self) -> CellWriteToken<'a, T, S> {
+        CellWriteToken {
+            guard: ManuallyDrop::new(orig),
+        }
+    }
+}
+
+/// Token of exclusive write access to a cell, which keeps the cell locked until it is passed to
+/// [`LockerRoom::unlock_cell`](crate::LockerRoom::unlock_cell). Dropping it leaves the cell locked forever.
+///
+/// This structure is created by [`WriteCellGuard::leak`].
+#[must_use = "the cell stays locked forever if the token isn't passed to `unlock_cell`"]
+pub struct CellWriteToken<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    guard: ManuallyDrop<WriteCellGuard<'a, T, S>>,
+}
+
+impl<'a, T, S> CellWriteToken<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn unlock(self) {
+        drop(ManuallyDrop::into_inner(self.guard));
+    }
+}
+
+impl<'a, T, S, V> WriteCellGuard<'a, T, S>
+where
+    T: Collection<Output = Option<V>>,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Makes a guard for the value inside the cell's `Option`, or releases the cell and returns `None` if the cell
+    /// holds `None`.
+    ///
+    /// This is an associated function that needs to be used as `WriteCellGuard::as_deref_mut(guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::WriteCellGuard};
+    /// let locker_room: LockerRoom<Vec<Option<i32>>> = vec![Some(1), None].into();
+    /// *WriteCellGuard::as_deref_mut(locker_room.write_cell(0).unwrap()).unwrap() += 1;
+    /// assert!(WriteCellGuard::as_deref_mut(locker_room.write_cell(1).unwrap()).is_none());
+    /// assert_eq!(vec![Some(2), None], locker_room.into_inner());
+    /// ```
+    pub fn as_deref_mut(orig: Self) -> Option<MappedWriteCellGuard<'a, V, S::Lock>> {
+        Self::try_map(orig, Option::as_mut).ok()
+    }
+}
+
+impl<'a, T, S> Deref for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    type Target = T::Output;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T, S> DerefMut for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+impl<'a, T, S> fmt::Display for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<'a, T, S> Hash for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<'a, T, S> PartialEq for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<'a, T, S> Eq for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: Eq,
+{
+}
+
+impl<'a, T, S> PartialOrd for WriteCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    T::Output: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a cell lock when dropped, which records the value
+/// of the cell with a [`CellLogger`] first.
+///
+/// The value isn't recorded if the guard is dropped during a panic, since the write may be unfinished. The index
+/// is kept the way it was passed, e.g. as a reference, for the logger.
+///
+/// This structure is created by the [`write_cell_logged`](crate::LockerRoom::write_cell_logged) method on
+/// [`LockerRoom`](crate::LockerRoom).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct LoggedWriteCellGuard<
+    'a,
+    'l,
+    T,
+    S = <T as Collection>::ShadowLocks,
+    I = <T as Collection>::Idx,
+> where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    I: Borrow<T::Idx>,
+{
+    index: I,
+    // Dropped after the value is logged.
+    guard: WriteCellGuard<'a, T, S>,
+    logger: &'l dyn CellLogger<T::Idx, T::Output>,
+}
+
+impl<'a, 'l, T, S, I> LoggedWriteCellGuard<'a, 'l, T, S, I>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    I: Borrow<T::Idx>,
+{
+    pub(crate) fn new(
+        index: I,
+        guard: WriteCellGuard<'a, T, S>,
+        logger: &'l dyn CellLogger<T::Idx, T::Output>,
+    ) -> Self {
+        Self {
+            index,
+            guard,
+            logger,
+        }
+    }
+}
+
+impl<'a, T, S, I> Deref for LoggedWriteCellGuard<'a, '_, T, S, I>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    I: Borrow<T::Idx>,
+{
+    type Target = T::Output;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<'a, T, S, I> DerefMut for LoggedWriteCellGuard<'a, '_, T, S, I>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    I: Borrow<T::Idx>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<'a, T, S, I> Drop for LoggedWriteCellGuard<'a, '_, T, S, I>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    I: Borrow<T::Idx>,
+{
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.logger.log(self.index.borrow(), &self.guard);
+        }
+    }
+}
+
+/// RAII structure used to release the shared read access of a cell lock when dropped, which gives access
+/// to a component of the cell value.
+///
+/// This structure is created by [`ReadCellGuard::map`] and [`ReadCellGuard::try_map`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MappedReadCellGuard<'a, U, L = RwLock<()>>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    value: &'a U,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<Held<L::ReadGuard<'a>>, L::ReadGuard<'a>>,
+}
+
+impl<'a, U, L> MappedReadCellGuard<'a, U, L>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    /// Makes a guard for a component of the value, like [`ReadCellGuard::map`] does.
+    pub fn map<V>(orig: Self, f: impl FnOnce(&U) -> &V) -> MappedReadCellGuard<'a, V, L>
+    where
+        V: ?Sized,
+    {
+        MappedReadCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+}
+
+impl<'a, U, L> Deref for MappedReadCellGuard<'a, U, L>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a cell lock when dropped, which gives access
+/// to a component of the cell value.
+///
+/// This structure is created by [`WriteCellGuard::map`] and [`WriteCellGuard::try_map`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MappedWriteCellGuard<'a, U, L = RwLock<()>>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    value: &'a mut U,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<Held<L::WriteGuard<'a>>, L::ReadGuard<'a>>,
+}
+
+impl<'a, U, L> MappedWriteCellGuard<'a, U, L>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    /// Makes a guard for a component of the value, like [`WriteCellGuard::map`] does.
+    pub fn map<V>(orig: Self, f: impl FnOnce(&mut U) -> &mut V) -> MappedWriteCellGuard<'a, V, L>
+    where
+        V: ?Sized,
+    {
+        MappedWriteCellGuard {
+            value: f(orig.value),
+            guards: orig.guards,
+        }
+    }
+}
+
+impl<'a, U, L> Deref for MappedWriteCellGuard<'a, U, L>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<'a, U, L> DerefMut for MappedWriteCellGuard<'a, U, L>
+where
+    U: ?Sized,
+    L: ShadowLock + 'a,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+/// RAII structure used to release the shared read access of a cell lock when dropped. Unlike [`ReadCellGuard`]
+/// it holds no lock of whole collection.
+///
+/// This structure is created by the [`read_cell`](crate::sync::LockerRoomCells::read_cell) methods on
+/// [`LockerRoomCells`](crate::sync::LockerRoomCells).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct BareReadCellGuard<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    value: &'a T::Output,
     // For dropping and, after that, unlocking.
     #[allow(dead_code)]
-    cell_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
-    // For dropping and, after that, unlocking. But it stands after cell guard because of order of dropping.
+    cell_rwlock_read_guard: CellReadGuard<'a, S>,
+}
+
+impl<'a, T, S> BareReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(value: &'a T::Output, cell_rwlock_read_guard: CellReadGuard<'a, S>) -> Self {
+        Self {
+            value,
+            cell_rwlock_read_guard,
+        }
+    }
+}
+
+impl<'a, T, S> Deref for BareReadCellGuard<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    type Target = T::Output;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a cell lock when dropped. Unlike [`WriteCellGuard`]
+/// it holds no lock of whole collection.
+///
+/// This structure is created by the [`write_cell`](crate::sync::LockerRoomCells::write_cell) methods on
+/// [`LockerRoomCells`](crate::sync::LockerRoomCells).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct BareWriteCellGuard<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    value: &'a mut T::Output,
+    // For dropping and, after that, unlocking.
     #[allow(dead_code)]
-    global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
+    cell_rwlock_write_guard: CellWriteGuard<'a, S>,
 }
 
-impl<'a, T> WriteCellGuard<'a, T>
+impl<'a, T, S> BareWriteCellGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     pub(crate) fn new(
         value: &'a mut T::Output,
-        global_rwlock_read_guard: RwLockReadGuard<'a, ()>,
-        cell_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
+        cell_rwlock_write_guard: CellWriteGuard<'a, S>,
     ) -> Self {
         Self {
             value,
-            global_rwlock_read_guard,
             cell_rwlock_write_guard,
         }
     }
 }
 
-impl<'a, T> Deref for WriteCellGuard<'a, T>
+impl<'a, T, S> Deref for BareWriteCellGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     type Target = T::Output;
 
@@ -95,9 +741,10 @@ where
     }
 }
 
-impl<'a, T> DerefMut for WriteCellGuard<'a, T>
+impl<'a, T, S> DerefMut for BareWriteCellGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.value
@@ -106,37 +753,411 @@ where
 
 /// RAII structure used to release the exclusive write access of a whole collection lock when dropped.
 ///
+/// Shadow locks follow the indices of the collection once the guard is dropped, or right away with
+/// [`sync_locks`](Self::sync_locks). Until then cells added through the guard have no locks. No cell can be
+/// locked while the guard is alive anyway, even by the thread which holds it: [`read_cell`](crate::LockerRoom::read_cell)
+/// from that thread waits for the guard forever. Use [`read_cell`](Self::read_cell) and
+/// [`write_cell_mut`](Self::write_cell_mut) of the guard instead.
+///
 /// This structure is created by the [`lock_room`](crate::LockerRoom::lock_room) methods on [`LockerRoom`](crate::LockerRoom).
-pub struct RoomGuard<'a, T>
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct RoomGuard<'a, T, S = <T as Collection>::ShadowLocks>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     collection: &'a mut T,
-    index_locks: &'a mut T::ShadowLocks,
+    index_locks: &'a mut S,
     #[allow(dead_code)]
-    global_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
+    global_rwlock_write_guard: LockWriteGuard<'a, S>,
+    // Names the cells locked by `into_cells` and `into_write_cell` in deadlock reports.
+    cell_names: CellNames<T::Idx>,
 }
 
-impl<'a, T> RoomGuard<'a, T>
+impl<'a, T, S> RoomGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     pub(crate) fn new(
         collection: &'a mut T,
-        index_locks: &'a mut T::ShadowLocks,
-        global_rwlock_write_guard: RwLockWriteGuard<'a, ()>,
+        index_locks: &'a mut S,
+        global_rwlock_write_guard: LockWriteGuard<'a, S>,
+        cell_names: CellNames<T::Idx>,
     ) -> Self {
         Self {
             collection,
             index_locks,
             global_rwlock_write_guard,
+            cell_names,
         }
     }
+
+    /// An iterator visiting all cells with their indices in the order of [`Collection::indices`].
+    ///
+    /// Unlike iterating the collection itself, it only needs the collection to implement [`Collection`].
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = BTreeMap::from([("a", 1), ("b", 2)]).into();
+    /// let room = locker_room.lock_room();
+    /// assert_eq!(vec![("a", &1), ("b", &2)], room.cells().collect::<Vec<_>>());
+    /// ```
+    pub fn cells(&self) -> impl Iterator<Item = (T::Idx, &T::Output)> + use<'_, 'a, T, S> {
+        cells(self.collection)
+    }
+
+    /// An iterator visiting all cells with their indices in the order of [`Collection::indices`], with mutable
+    /// references to the values.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let mut room = locker_room.lock_room();
+    /// room.cells_mut().for_each(|(index, value)| *value += index);
+    /// assert_eq!(vec![1, 3], *room);
+    /// ```
+    pub fn cells_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (T::Idx, &mut T::Output)> + use<'_, 'a, T, S> {
+        cells_mut(self.collection)
+    }
+
+    /// Returns a reference to the cell at the index through the held lock of whole collection, locking nothing.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let room = locker_room.lock_room();
+    /// // `locker_room.read_cell(1)` would wait for `room` forever.
+    /// assert_eq!(Some(&2), room.read_cell(1));
+    /// ```
+    ///
+    /// It suits scanning cells to decide which of them to change, all under one lock of whole collection:
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![3, 1, 2].into();
+    /// let mut room = locker_room.lock_room();
+    /// let min = (0..room.len()).min_by_key(|&i| room.read_cell(i)).unwrap();
+    /// *room.write_cell_mut(min).unwrap() = 0;
+    /// assert_eq!(vec![3, 0, 2], *room);
+    /// ```
+    pub fn read_cell(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        self.collection.index(index)
+    }
+
+    /// Peeks at the cell at the index like [`read_cell`](Self::read_cell) does, for those who look for this name.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let room = locker_room.lock_room();
+    /// assert_eq!(Some(&2), room.peek(1));
+    /// assert_eq!(None, room.peek(2));
+    /// ```
+    pub fn peek(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        self.read_cell(index)
+    }
+
+    /// Returns a mutable reference to the cell at the index through the held lock of whole collection, locking
+    /// nothing.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let mut room = locker_room.lock_room();
+    /// *room.write_cell_mut(1).unwrap() += 1;
+    /// assert_eq!(vec![1, 3], *room);
+    /// ```
+    pub fn write_cell_mut(&mut self, index: impl Borrow<T::Idx>) -> Option<&mut T::Output> {
+        self.collection.index_mut(index)
+    }
+
+    /// Shrinks the capacity of the collection and its shadow locks as much as possible, see
+    /// [`Collection::shrink_to_fit`] and [`ShadowLocksCollection::shrink_to_fit`].
+    ///
+    /// Collections keep their capacity when cells are removed, so a long-lived map which once was large keeps
+    /// the memory of both its cells and their locks. This gives the memory back.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = (0..1000).map(|i| (i, i)).collect::<HashMap<_, _>>().into();
+    /// let mut room = locker_room.lock_room();
+    /// room.retain(|key, _| *key < 10);
+    /// room.shrink_to_fit();
+    /// assert!(room.capacity() < 1000);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.reborrow().shrink_to_fit();
+    }
+
+    /// Updates the shadow locks to the indices of the collection right away, like dropping the guard does.
+    ///
+    /// Dropping the guard updates them once more, so this only helps when the locks have to follow the collection
+    /// while it stays locked.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![0].into();
+    /// let mut room = locker_room.lock_room();
+    /// room.push(1);
+    /// room.sync_locks();
+    /// let guard = lockerroom::sync::RoomGuard::into_cells(room, [1]).unwrap();
+    /// assert_eq!(Some(&1), guard.get(1));
+    /// ```
+    pub fn sync_locks(&mut self) {
+        self.reborrow().sync_locks();
+    }
+
+    /// Moves all cells out of the collection with their indices, leaving it empty, and drops the shadow locks of
+    /// the cells right away.
+    ///
+    /// Cells which aren't iterated over are dropped with the iterator.
+    ///
+    /// This is an associated function that needs to be used as `RoomGuard::drain(&mut guard)`,
+    /// so it doesn't conflict with a method on the collection, e.g. [`Vec::drain`].
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::{LockerRoom, sync::RoomGuard};
+    /// let locker_room: LockerRoom<_> = HashMap::from([("a", 1), ("b", 2)]).into();
+    /// let mut room = locker_room.lock_room();
+    /// let mut cells: Vec<_> = RoomGuard::drain(&mut room).collect();
+    /// cells.sort();
+    /// assert_eq!(vec![("a", 1), ("b", 2)], cells);
+    /// assert!(room.is_empty());
+    /// ```
+    pub fn drain(orig: &mut Self) -> impl Iterator<Item = (T::Idx, T::Value)> + use<'_, 'a, T, S>
+    where
+        T: DrainCollection,
+    {
+        orig.index_locks.update_indices(iter::empty());
+        orig.collection.drain_cells()
+    }
+
+    /// Makes a shorter-lived [`RoomGuardMut`] of the same locked collection without locking anything once more,
+    /// so helper functions can take whole locked collection by value and the guard stays usable afterwards.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::RoomGuardMut};
+    /// fn push_two(mut room: RoomGuardMut<'_, Vec<i32>>) {
+    ///     room.extend([0, 0]);
+    /// }
+    ///
+    /// let locker_room: LockerRoom<_> = vec![1].into();
+    /// let mut room = locker_room.lock_room();
+    /// push_two(room.reborrow());
+    /// push_two(room.reborrow());
+    /// assert_eq!(5, room.len());
+    /// ```
+    pub fn reborrow(&mut self) -> RoomGuardMut<'_, T, S> {
+        RoomGuardMut {
+            collection: self.collection,
+            index_locks: self.index_locks,
+        }
+    }
+
+    /// Takes the fields out of the guard, updating the shadow locks like dropping it does.
+    fn into_locked(self) -> (&'a mut T, &'a S, LockWriteGuard<'a, S>) {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped after its fields are moved out.
+        let (collection, index_locks, global_rwlock_write_guard) = unsafe {
+            (
+                ptr::read(&this.collection),
+                ptr::read(&this.index_locks),
+                ptr::read(&this.global_rwlock_write_guard),
+            )
+        };
+        index_locks.update_indices(collection.indices());
+        (collection, index_locks, global_rwlock_write_guard)
+    }
+
+    /// Atomically turns the guard into exclusive write access to the cells at the indices, so no other thread can
+    /// lock them or whole collection in between. Other cells become available to other threads.
+    ///
+    /// It suits "resize, then initialize the new cells" workflows. Repeated indices are locked once, and so is a lock
+    /// shared by cells, e.g. by a block of [`BlockLocks`](crate::BlockLocks).
+    ///
+    /// This is an associated function that needs to be used as `RoomGuard::into_cells(guard, ...)`,
+    /// so it doesn't conflict with a method on the collection.
+    ///
+    /// This function will return `None`, releasing all the locks, if there is no cell with one of the indices.
+    /// ```
+    /// # use lockerroom::{LockerRoom, sync::RoomGuard};
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let mut room = locker_room.lock_room();
+    /// room.resize(4, 0);
+    /// let mut cells = RoomGuard::into_cells(room, [2, 3]).unwrap();
+    /// // Cells 0 and 1 are available again, but the new ones are not yet.
+    /// assert_eq!(Some(1), locker_room.get_cell(0));
+    /// cells.iter_mut().for_each(|(index, value)| *value = *index);
+    /// # drop(cells);
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn into_cells(
+        orig: Self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> Option<MultiWriteGuard<'a, T, S>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let cell_names = orig.cell_names;
+        let (collection, index_locks, global_rwlock_write_guard) = orig.into_locked();
+        let collection: *mut T = collection;
+        let mut cells = Vec::with_capacity(indices.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in indices {
+            let cell_rwlock = index_locks.index(&index)?;
+            // Nobody else holds cell locks while whole collection is locked, so locking the cell blocks only if this
+            // thread holds its lock already. Cells sharing a lock, like blocks of `BlockLocks`, are adjacent in the
+            // order, so it's enough to check the previous one.
+            let cell_rwlock_write_guard =
+                (!previous.is_some_and(|previous| ptr::eq(previous, cell_rwlock))).then(|| {
+                    deadlock::hold(
+                        cell_rwlock,
+                        true,
+                        || cell_names.name(&index),
+                        cell_rwlock.write(),
+                    )
+                });
+            previous = Some(cell_rwlock);
+            // SAFETY: the indices are distinct, so every cell is borrowed mutably only once.
+            let value = unsafe { &mut *collection }.index_mut(&index)?;
+            cells.push((index, value, cell_rwlock_write_guard));
+        }
+        let global_rwlock_read_guard = S::Lock::downgrade(global_rwlock_write_guard);
+        Some(MultiWriteGuard::new(cells, global_rwlock_read_guard))
+    }
+
+    /// Atomically turns the guard into exclusive write access to the cell at the index,
+    /// so no other thread can lock the cell or whole collection in between.
+    pub(crate) fn into_write_cell(self, index: &T::Idx) -> Option<WriteCellGuard<'a, T, S>> {
+        let cell_names = self.cell_names;
+        let (collection, index_locks, global_rwlock_write_guard) = self.into_locked();
+        // Nobody holds cell locks while whole collection is locked, so locking the cell never blocks.
+        let cell_rwlock = index_locks.index(index)?;
+        let cell_rwlock_write_guard = deadlock::hold(
+            cell_rwlock,
+            true,
+            || cell_names.name(index),
+            cell_rwlock.write(),
+        );
+        let global_rwlock_read_guard = S::Lock::downgrade(global_rwlock_write_guard);
+        let value = collection.index_mut(index)?;
+        Some(WriteCellGuard::new(
+            value,
+            global_rwlock_read_guard,
+            cell_rwlock_write_guard,
+        ))
+    }
 }
 
-impl<'a, T> Deref for RoomGuard<'a, T>
+/// Ends of a [`VecDeque`] are changed together with its shadow locks.
+///
+/// Indices of a `VecDeque` are positions from the front, so [`push_front`](Self::push_front) and
+/// [`pop_front`](Self::pop_front) shift every cell to another index: a cell at index 0 before `push_front` is at
+/// index 1 after it. The shadow locks are shifted the same way, so every lock stays with its cell, and
+/// [`insert`](Self::insert), [`remove`](Self::remove) and the rotations do the same in the middle. Methods of the
+/// deque itself, reached through [`DerefMut`], change only the cells, and then the shadow locks just follow the new
+/// length at the back once the guard is dropped or one of these methods is called.
+/// ```
+/// # use std::collections::VecDeque;
+/// # use lockerroom::LockerRoom;
+/// let locker_room: LockerRoom<_> = VecDeque::from([1, 2]).into();
+/// let mut room = locker_room.lock_room();
+/// room.push_front(0);
+/// assert_eq!(Some(2), room.pop_back());
+/// drop(room);
+/// assert_eq!(0, *locker_room.read_cell(0).unwrap());
+/// assert_eq!(1, *locker_room.read_cell(1).unwrap());
+/// ```
+impl<'a, V, L> RoomGuard<'a, VecDeque<V>, VecDeque<L>>
+where
+    L: ShadowLock + 'a,
+{
+    /// Prepends a cell to the deque together with its lock.
+    pub fn push_front(&mut self, value: V) {
+        self.align_locks();
+        self.collection.push_front(value);
+        self.index_locks.push_front(L::default());
+    }
+
+    /// Appends a cell to the deque together with its lock.
+    pub fn push_back(&mut self, value: V) {
+        self.align_locks();
+        self.collection.push_back(value);
+        self.index_locks.push_back(L::default());
+    }
+
+    /// Removes the first cell of the deque together with its lock and returns its value, or `None` if the deque is empty.
+    pub fn pop_front(&mut self) -> Option<V> {
+        self.align_locks();
+        let value = self.collection.pop_front()?;
+        self.index_locks.pop_front();
+        Some(value)
+    }
+
+    /// Removes the last cell of the deque together with its lock and returns its value, or `None` if the deque is empty.
+    pub fn pop_back(&mut self) -> Option<V> {
+        self.align_locks();
+        let value = self.collection.pop_back()?;
+        self.index_locks.pop_back();
+        Some(value)
+    }
+
+    /// Inserts a cell at the index together with its lock, shifting the cells after it to the back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length of the deque, like [`VecDeque::insert`].
+    pub fn insert(&mut self, index: usize, value: V) {
+        self.align_locks();
+        self.collection.insert(index, value);
+        self.index_locks.insert(index, L::default());
+    }
+
+    /// Removes the cell at the index together with its lock and returns its value, or `None` if there is no cell
+    /// with such index. The cells after it are shifted to the front.
+    pub fn remove(&mut self, index: usize) -> Option<V> {
+        self.align_locks();
+        let value = self.collection.remove(index)?;
+        self.index_locks.remove(index);
+        Some(value)
+    }
+
+    /// Rotates the cells together with their locks `n` places to the front, like [`VecDeque::rotate_left`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the deque.
+    pub fn rotate_left(&mut self, n: usize) {
+        self.align_locks();
+        self.collection.rotate_left(n);
+        self.index_locks.rotate_left(n);
+    }
+
+    /// Rotates the cells together with their locks `n` places to the back, like [`VecDeque::rotate_right`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the deque.
+    pub fn rotate_right(&mut self, n: usize) {
+        self.align_locks();
+        self.collection.rotate_right(n);
+        self.index_locks.rotate_right(n);
+    }
+
+    // Cells changed through `DerefMut` may have left the shadow locks behind, and then they only follow the length.
+    fn align_locks(&mut self) {
+        if self.index_locks.len() != self.collection.len() {
+            self.sync_locks();
+        }
+    }
+}
+
+impl<'a, T, S> Deref for RoomGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     type Target = T;
 
@@ -145,20 +1166,370 @@ where
     }
 }
 
-impl<'a, T> DerefMut for RoomGuard<'a, T>
+impl<'a, T, S> DerefMut for RoomGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.collection
     }
 }
 
-impl<'a, T> Drop for RoomGuard<'a, T>
+impl<'a, T, S> Drop for RoomGuard<'a, T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
     fn drop(&mut self) {
+        self.sync_locks();
+    }
+}
+
+/// Mutable access to whole collection borrowed from a [`RoomGuard`], which keeps the collection locked.
+///
+/// It has the methods of `RoomGuard` that don't give the lock away, and it can be reborrowed in turn.
+/// Shadow locks are updated once the `RoomGuard` is dropped, like after changing the collection through it directly.
+///
+/// This structure is created by the [`reborrow`](RoomGuard::reborrow) method on [`RoomGuard`].
+pub struct RoomGuardMut<'b, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'b,
+{
+    collection: &'b mut T,
+    index_locks: &'b mut S,
+}
+
+impl<'b, T, S> RoomGuardMut<'b, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'b,
+{
+    /// An iterator visiting all cells with their indices, see [`RoomGuard::cells`].
+    pub fn cells(&self) -> impl Iterator<Item = (T::Idx, &T::Output)> + use<'_, 'b, T, S> {
+        cells(self.collection)
+    }
+
+    /// An iterator visiting all cells with their indices and mutable references to the values, see
+    /// [`RoomGuard::cells_mut`].
+    pub fn cells_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (T::Idx, &mut T::Output)> + use<'_, 'b, T, S> {
+        cells_mut(self.collection)
+    }
+
+    /// Returns a reference to the cell at the index, locking nothing, see [`RoomGuard::read_cell`].
+    pub fn read_cell(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        self.collection.index(index)
+    }
+
+    /// Returns a mutable reference to the cell at the index, locking nothing, see [`RoomGuard::write_cell_mut`].
+    pub fn write_cell_mut(&mut self, index: impl Borrow<T::Idx>) -> Option<&mut T::Output> {
+        self.collection.index_mut(index)
+    }
+
+    /// Shrinks the capacity of the collection and its shadow locks, see [`RoomGuard::shrink_to_fit`].
+    pub fn shrink_to_fit(&mut self) {
+        self.collection.shrink_to_fit();
+        self.sync_locks();
+        self.index_locks.shrink_to_fit();
+    }
+
+    /// Updates the shadow locks to the indices of the collection right away, see [`RoomGuard::sync_locks`].
+    pub fn sync_locks(&mut self) {
         self.index_locks.update_indices(self.collection.indices());
     }
+
+    /// Makes an even shorter-lived `RoomGuardMut` of the same locked collection, see [`RoomGuard::reborrow`].
+    pub fn reborrow(&mut self) -> RoomGuardMut<'_, T, S> {
+        RoomGuardMut {
+            collection: self.collection,
+            index_locks: self.index_locks,
+        }
+    }
+}
+
+impl<'b, T, S> Deref for RoomGuardMut<'b, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'b,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.collection
+    }
+}
+
+impl<'b, T, S> DerefMut for RoomGuardMut<'b, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'b,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.collection
+    }
+}
+
+fn cells<T: Collection>(collection: &T) -> impl Iterator<Item = (T::Idx, &T::Output)> {
+    collection
+        .indices()
+        .filter_map(move |index| collection.index(&index).map(|value| (index, value)))
+}
+
+fn cells_mut<T: Collection>(collection: &mut T) -> impl Iterator<Item = (T::Idx, &mut T::Output)> {
+    let indices: Vec<_> = collection.indices().collect();
+    let collection: *mut T = collection;
+    indices.into_iter().filter_map(move |index| {
+        // SAFETY: the indices are distinct, so every cell is borrowed mutably at most once, just like
+        // by write guards of different cells.
+        let value = unsafe { &mut *collection }.index_mut(&index)?;
+        Some((index, value))
+    })
+}
+
+/// RAII structure used to release the shared read access of several cell locks when dropped.
+///
+/// Unlike [`ReadCellGuard`] it remembers the index of every locked cell.
+///
+/// This structure is created by the [`read_cells`](crate::LockerRoom::read_cells) methods on [`LockerRoom`](crate::LockerRoom).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MultiReadGuard<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    // Released before the lock of whole collection.
+    guards: OrderedGuards<ReadCells<'a, T, S>, LockReadGuard<'a, S>>,
+}
+
+impl<'a, T, S> MultiReadGuard<'a, T, S>
+where
+    T: Collection,
+    T::Idx: CanonicalOrder,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(
+        cells: ReadCells<'a, T, S>,
+        global_rwlock_read_guard: LockReadGuard<'a, S>,
+    ) -> Self {
+        Self {
+            guards: OrderedGuards::new(cells, global_rwlock_read_guard),
+        }
+    }
+
+    /// Returns a reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        let position = order::position(self.guards.cells(), index.borrow(), |(idx, _, _)| idx)?;
+        Some(self.guards.cells()[position].1)
+    }
+
+    /// An iterator visiting all locked cells with their indices in [`CanonicalOrder`](crate::CanonicalOrder) of indices.
+    pub fn iter(&self) -> impl Iterator<Item = (&T::Idx, &T::Output)> + use<'_, 'a, T, S> {
+        self.guards
+            .cells()
+            .iter()
+            .map(|(idx, value, _)| (idx, &**value))
+    }
+
+    /// An iterator visiting the indices of all locked cells in [`CanonicalOrder`](crate::CanonicalOrder).
+    pub fn indices(&self) -> impl Iterator<Item = &T::Idx> + use<'_, 'a, T, S> {
+        self.guards.cells().iter().map(|(idx, _, _)| idx)
+    }
+
+    /// Returns the number of locked cells.
+    pub fn len(&self) -> usize {
+        self.guards.cells().len()
+    }
+
+    /// Returns `true` if the guard locks no cells.
+    pub fn is_empty(&self) -> bool {
+        self.guards.cells().is_empty()
+    }
+}
+
+/// RAII structure used to release the exclusive write access of several cell locks when dropped.
+///
+/// Unlike [`WriteCellGuard`] it remembers the index of every locked cell.
+///
+/// This structure is created by the [`write_cells`](crate::LockerRoom::write_cells) methods on [`LockerRoom`](crate::LockerRoom).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct MultiWriteGuard<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    // Released before the lock of whole collection.
+    guards: OrderedGuards<WriteCells<'a, T, S>, LockReadGuard<'a, S>>,
+}
+
+impl<'a, T, S> MultiWriteGuard<'a, T, S>
+where
+    T: Collection,
+    T::Idx: CanonicalOrder,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(
+        cells: WriteCells<'a, T, S>,
+        global_rwlock_read_guard: LockReadGuard<'a, S>,
+    ) -> Self {
+        Self {
+            guards: OrderedGuards::new(cells, global_rwlock_read_guard),
+        }
+    }
+
+    fn position(&self, index: &T::Idx) -> Option<usize> {
+        order::position(self.guards.cells(), index, |(idx, _, _)| idx)
+    }
+
+    /// Returns a reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get(&self, index: impl Borrow<T::Idx>) -> Option<&T::Output> {
+        let position = self.position(index.borrow())?;
+        Some(&*self.guards.cells()[position].1)
+    }
+
+    /// Returns a mutable reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get_mut(&mut self, index: impl Borrow<T::Idx>) -> Option<&mut T::Output> {
+        let position = self.position(index.borrow())?;
+        Some(&mut *self.guards.cells_mut()[position].1)
+    }
+
+    /// An iterator visiting all locked cells with their indices in [`CanonicalOrder`](crate::CanonicalOrder) of indices.
+    pub fn iter(&self) -> impl Iterator<Item = (&T::Idx, &T::Output)> + use<'_, 'a, T, S> {
+        self.guards
+            .cells()
+            .iter()
+            .map(|(idx, value, _)| (idx, &**value))
+    }
+
+    /// An iterator visiting all locked cells with their indices in [`CanonicalOrder`](crate::CanonicalOrder) of indices, with mutable references to the values.
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&T::Idx, &mut T::Output)> + use<'_, 'a, T, S> {
+        self.guards
+            .cells_mut()
+            .iter_mut()
+            .map(|(idx, value, _)| (&*idx, &mut **value))
+    }
+
+    /// An iterator visiting the indices of all locked cells in [`CanonicalOrder`](crate::CanonicalOrder).
+    pub fn indices(&self) -> impl Iterator<Item = &T::Idx> + use<'_, 'a, T, S> {
+        self.guards.cells().iter().map(|(idx, _, _)| idx)
+    }
+
+    /// Returns the number of locked cells.
+    pub fn len(&self) -> usize {
+        self.guards.cells().len()
+    }
+
+    /// Returns `true` if the guard locks no cells.
+    pub fn is_empty(&self) -> bool {
+        self.guards.cells().is_empty()
+    }
+}
+
+/// RAII structure used to release the shared read access of a contiguous range of cell locks when dropped.
+///
+/// This structure is created by the [`read_range`](crate::LockerRoom::read_range) methods on [`LockerRoom`](crate::LockerRoom).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct RangeReadGuard<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    values: &'a [T::Output],
+    // Releases the cell locks and, after them, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<Vec<CellReadGuard<'a, S>>, LockReadGuard<'a, S>>,
+}
+
+impl<'a, T, S> RangeReadGuard<'a, T, S>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    pub(crate) fn new(
+        values: &'a [T::Output],
+        global_rwlock_read_guard: LockReadGuard<'a, S>,
+        cell_rwlock_read_guards: Vec<CellReadGuard<'a, S>>,
+    ) -> Self {
+        Self {
+            values,
+            guards: OrderedGuards::new(cell_rwlock_read_guards, global_rwlock_read_guard),
+        }
+    }
+}
+
+impl<'a, T, S> Deref for RangeReadGuard<'a, T, S>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    type Target = [T::Output];
+
+    fn deref(&self) -> &Self::Target {
+        self.values
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a contiguous range of cell locks when dropped.
+///
+/// This structure is created by the [`write_range`](crate::LockerRoom::write_range) methods on [`LockerRoom`](crate::LockerRoom).
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct RangeWriteGuard<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    values: &'a mut [T::Output],
+    // Releases the cell locks and, after them, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<Vec<CellWriteGuard<'a, S>>, LockReadGuard<'a, S>>,
+}
+
+impl<'a, T, S> RangeWriteGuard<'a, T, S>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    pub(crate) fn new(
+        values: &'a mut [T::Output],
+        global_rwlock_read_guard: LockReadGuard<'a, S>,
+        cell_rwlock_write_guards: Vec<CellWriteGuard<'a, S>>,
+    ) -> Self {
+        Self {
+            values,
+            guards: OrderedGuards::new(cell_rwlock_write_guards, global_rwlock_read_guard),
+        }
+    }
+}
+
+impl<'a, T, S> Deref for RangeWriteGuard<'a, T, S>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    type Target = [T::Output];
+
+    fn deref(&self) -> &Self::Target {
+        self.values
+    }
+}
+
+impl<'a, T, S> DerefMut for RangeWriteGuard<'a, T, S>
+where
+    T: Collection<Idx = usize>,
+    T::Output: Sized,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.values
+    }
 }
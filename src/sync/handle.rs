@@ -0,0 +1,46 @@
+use std::borrow::Borrow;
+
+use crate::{Collection, ShadowLocksCollection};
+
+use super::{LockerRoom, ReadCellGuard, WriteCellGuard};
+
+/// A handle of a single cell of a [`LockerRoom`], which locks the cell on demand.
+///
+/// Nothing is locked until [`read`](Self::read) or [`write`](Self::write) is called.
+///
+/// This structure is created by the [`cell`](LockerRoom::cell) method on [`LockerRoom`].
+pub struct CellHandle<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    locker_room: &'a LockerRoom<T, S>,
+    index: T::Idx,
+}
+
+impl<'a, T, S> CellHandle<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(locker_room: &'a LockerRoom<T, S>, index: T::Idx) -> Self {
+        Self { locker_room, index }
+    }
+
+    /// Gets a reference to the index of the cell.
+    pub fn index(&self) -> &T::Idx {
+        &self.index
+    }
+
+    /// Locks the cell with shared read access like [`LockerRoom::read_cell`].
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read(&self) -> Option<ReadCellGuard<'a, T, S>> {
+        self.locker_room.read_cell(self.index.borrow())
+    }
+
+    /// Locks the cell with exclusive write access like [`LockerRoom::write_cell`].
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write(&self) -> Option<WriteCellGuard<'a, T, S>> {
+        self.locker_room.write_cell(self.index.borrow())
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    borrow::Borrow,
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+    deadlock::{self, Held},
+    order,
+    release::OrderedGuards,
+};
+
+// Locked cells of `LockedMultiWriteGuard` with their guards, sorted by index.
+type WriteCells<'a, T> = Vec<(usize, &'a mut T, Held<RwLockWriteGuard<'a, T>>)>;
+
+/// Provides the lock of whole collection, like [`LockerRoom`](crate::LockerRoom), for cells which already have their
+/// own locks.
+///
+/// The lock of every cell is the lock around its value, so nothing is moved out of the cells and no shadow locks are
+/// kept beside them. Cells are locked by [`read_cell`](Self::read_cell) and [`write_cell`](Self::write_cell) with
+/// shared read access to whole collection, several cells are locked in ascending order of indices by
+/// [`write_cells`](Self::write_cells), and [`lock_room`](Self::lock_room) gives exclusive access to the vector of
+/// locks, so cells can be inserted or removed.
+///
+/// Poisoning of the locks is ignored, like `LockerRoom` does.
+/// ```
+/// # use std::{sync::RwLock, thread};
+/// # use lockerroom::sync::LockedCells;
+/// let cells = vec![RwLock::new(0), RwLock::new(1)];
+/// let locker_room = LockedCells::from_locked_cells(cells);
+/// thread::scope(|scope| {
+///     scope.spawn(|| *locker_room.write_cell(0).unwrap() += 1);
+///     scope.spawn(|| *locker_room.write_cell(0).unwrap() += 2);
+/// });
+/// locker_room.lock_room().push(RwLock::new(2));
+/// assert_eq!(3, *locker_room.read_cell(0).unwrap());
+/// assert_eq!(2, *locker_room.read_cell(2).unwrap());
+/// ```
+pub struct LockedCells<T> {
+    cells: UnsafeCell<Vec<RwLock<T>>>,
+    global_lock: RwLock<()>,
+}
+
+// The vector is only changed under the exclusive lock of whole collection, and values are reached through their
+// locks, so it's shared like `RwLock<T>`. `Send` is derived automatically with the right bounds.
+unsafe impl<T> Sync for LockedCells<T> where T: Send + Sync {}
+
+impl<'a, T> LockedCells<T> {
+    /// Creates `LockedCells` from cells which are already locked one by one.
+    pub fn from_locked_cells(cells: Vec<RwLock<T>>) -> Self {
+        Self {
+            cells: UnsafeCell::new(cells),
+            global_lock: RwLock::new(()),
+        }
+    }
+
+    fn read_room(&self) -> RwLockReadGuard<'_, ()> {
+        self.global_lock
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn lock_read(index: usize, lock: &'a RwLock<T>) -> (&'a T, Held<RwLockReadGuard<'a, T>>) {
+        let mut value = ptr::null();
+        let guard = deadlock::acquire(
+            lock,
+            false,
+            || index.to_string().into(),
+            || {
+                let guard = lock.read().unwrap_or_else(|err| err.into_inner());
+                value = &*guard as *const T;
+                guard
+            },
+        );
+        // The value lives in the lock rather than in the guard, so it's borrowed for as long as the lock.
+        (unsafe { &*value }, guard)
+    }
+
+    // Returns the value as a pointer, since it's reached mutably through the shared reference to its lock.
+    fn lock_write(index: usize, lock: &'a RwLock<T>) -> (*mut T, Held<RwLockWriteGuard<'a, T>>) {
+        let mut value = ptr::null_mut();
+        let guard = deadlock::acquire(
+            lock,
+            true,
+            || index.to_string().into(),
+            || {
+                let mut guard = lock.write().unwrap_or_else(|err| err.into_inner());
+                value = &mut *guard as *mut T;
+                guard
+            },
+        );
+        (value, guard)
+    }
+
+    /// Locks cell at the index with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell(&'a self, index: impl Borrow<usize>) -> Option<LockedReadCellGuard<'a, T>> {
+        let global_lock_guard = self.read_room();
+        let index = *index.borrow();
+        let cells = unsafe { &*self.cells.get() };
+        let (value, guard) = Self::lock_read(index, cells.get(index)?);
+        Some(LockedReadCellGuard {
+            value,
+            guards: OrderedGuards::new(guard, global_lock_guard),
+        })
+    }
+
+    /// Locks cell at the index with exclusive write access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell(&'a self, index: impl Borrow<usize>) -> Option<LockedWriteCellGuard<'a, T>> {
+        let global_lock_guard = self.read_room();
+        let index = *index.borrow();
+        let cells = unsafe { &*self.cells.get() };
+        let (value, guard) = Self::lock_write(index, cells.get(index)?);
+        Some(LockedWriteCellGuard {
+            value: unsafe { &mut *value },
+            guards: OrderedGuards::new(guard, global_lock_guard),
+        })
+    }
+
+    /// Locks cells at the indices with exclusive write access, in ascending order of indices like
+    /// [`LockerRoom::write_cells`](crate::LockerRoom::write_cells), so calls locking the same cells in different
+    /// orders don't deadlock. Repeated indices are locked once.
+    ///
+    /// This function will return `None` if there is no cell with one of the indices. The cells locked so far are
+    /// released then.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Option<LockedMultiWriteGuard<'a, T>> {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.read_room();
+        let cells = unsafe { &*self.cells.get() };
+        // Guards of the cells locked so far are dropped with `locked` on early return.
+        let mut locked = Vec::with_capacity(indices.len());
+        for index in indices {
+            let (value, guard) = Self::lock_write(index, cells.get(index)?);
+            locked.push((index, unsafe { &mut *value }, guard));
+        }
+        Some(LockedMultiWriteGuard {
+            guards: OrderedGuards::new(locked, global_lock_guard),
+        })
+    }
+
+    /// Exclusively locks whole collection with write access, blocking the current thread until it can be acquired.
+    ///
+    /// The guard gives the vector of locks, so cells can be inserted or removed. No cell is locked meanwhile.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn lock_room(&'a self) -> LockedRoomGuard<'a, T> {
+        let global_lock_guard = self
+            .global_lock
+            .write()
+            .unwrap_or_else(|err| err.into_inner());
+        LockedRoomGuard {
+            cells: unsafe { &mut *self.cells.get() },
+            global_lock_guard,
+        }
+    }
+
+    /// Consumes this `LockedCells`, returning the cells with their locks.
+    pub fn into_inner(self) -> Vec<RwLock<T>> {
+        self.cells.into_inner()
+    }
+}
+
+impl<T> From<Vec<RwLock<T>>> for LockedCells<T> {
+    fn from(cells: Vec<RwLock<T>>) -> Self {
+        Self::from_locked_cells(cells)
+    }
+}
+
+/// RAII structure used to release the shared read access of a cell of [`LockedCells`] when dropped.
+///
+/// This structure is created by the [`read_cell`](LockedCells::read_cell) methods on [`LockedCells`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct LockedReadCellGuard<'a, T> {
+    value: &'a T,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<Held<RwLockReadGuard<'a, T>>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<T> Deref for LockedReadCellGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a cell of [`LockedCells`] when dropped.
+///
+/// This structure is created by the [`write_cell`](LockedCells::write_cell) methods on [`LockedCells`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct LockedWriteCellGuard<'a, T> {
+    value: &'a mut T,
+    // Releases the cell lock and, after it, the lock of whole collection.
+    #[allow(dead_code)]
+    guards: OrderedGuards<Held<RwLockWriteGuard<'a, T>>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<T> Deref for LockedWriteCellGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> DerefMut for LockedWriteCellGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.value
+    }
+}
+
+/// RAII structure used to release the exclusive write access of several cells of [`LockedCells`] when dropped.
+///
+/// This structure is created by the [`write_cells`](LockedCells::write_cells) methods on [`LockedCells`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct LockedMultiWriteGuard<'a, T> {
+    // Released before the lock of whole collection.
+    guards: OrderedGuards<WriteCells<'a, T>, RwLockReadGuard<'a, ()>>,
+}
+
+impl<T> LockedMultiWriteGuard<'_, T> {
+    fn position(&self, index: usize) -> Option<usize> {
+        order::position(self.guards.cells(), &index, |(idx, _, _)| idx)
+    }
+
+    /// Returns a reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let position = self.position(index)?;
+        Some(&*self.guards.cells()[position].1)
+    }
+
+    /// Returns a mutable reference to the locked cell at the index or `None` if this cell isn't locked by the guard.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let position = self.position(index)?;
+        Some(&mut *self.guards.cells_mut()[position].1)
+    }
+
+    /// Returns the number of locked cells.
+    pub fn len(&self) -> usize {
+        self.guards.cells().len()
+    }
+
+    /// Returns `true` if the guard locks no cells.
+    pub fn is_empty(&self) -> bool {
+        self.guards.cells().is_empty()
+    }
+}
+
+/// RAII structure used to release the exclusive write access of the lock of whole [`LockedCells`] when dropped.
+///
+/// This structure is created by the [`lock_room`](LockedCells::lock_room) methods on [`LockedCells`].
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct LockedRoomGuard<'a, T> {
+    cells: &'a mut Vec<RwLock<T>>,
+    #[allow(dead_code)]
+    global_lock_guard: RwLockWriteGuard<'a, ()>,
+}
+
+impl<T> Deref for LockedRoomGuard<'_, T> {
+    type Target = Vec<RwLock<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        self.cells
+    }
+}
+
+impl<T> DerefMut for LockedRoomGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.cells
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::{
+        sync::{mpsc, RwLock},
+        thread,
+        time::Duration,
+    };
+
+    use super::LockedCells;
+
+    #[test]
+    fn locked_cells() {
+        let locker_room: LockedCells<_> = vec![RwLock::new(0), RwLock::new(1)].into();
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            let cell = locker_room.read_cell(1).unwrap();
+            scope.spawn(|| {
+                let mut cells = locker_room.write_cells([1, 0, 1]).unwrap();
+                assert_eq!(2, cells.len());
+                *cells.get_mut(0).unwrap() += 10;
+                *cells.get_mut(1).unwrap() += 10;
+                assert!(cells.get(2).is_none());
+                sender.send(()).unwrap();
+            });
+            thread::sleep(Duration::from_millis(20));
+            // The writer waits for the read guard.
+            assert!(receiver.try_recv().is_err());
+            assert_eq!(1, *cell);
+            drop(cell);
+            receiver.recv().unwrap();
+        });
+        assert!(locker_room.read_cell(2).is_none());
+        assert!(locker_room.write_cells([0, 2]).is_none());
+        *locker_room.write_cell(0).unwrap() += 1;
+
+        thread::scope(|scope| {
+            let cell = locker_room.read_cell(0).unwrap();
+            let room_writer = scope.spawn(|| locker_room.lock_room().push(RwLock::new(2)));
+            thread::sleep(Duration::from_millis(20));
+            // The room writer waits for the cell to be released.
+            assert!(!room_writer.is_finished());
+            assert_eq!(11, *cell);
+            drop(cell);
+            room_writer.join().unwrap();
+        });
+        let cells: Vec<_> = locker_room
+            .into_inner()
+            .into_iter()
+            .map(|cell| cell.into_inner().unwrap())
+            .collect();
+        assert_eq!(vec![11, 11, 2], cells);
+    }
+}
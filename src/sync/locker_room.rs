@@ -1,8 +1,39 @@
-use std::{borrow::Borrow, cell::UnsafeCell, marker::PhantomData, sync::RwLock};
+use std::{
+    borrow::{Borrow, Cow},
+    cell::UnsafeCell,
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    mem,
+    ops::Range,
+    ptr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::{Collection, ShadowLocksCollection};
+#[cfg(feature = "tracing")]
+use crate::trace::TraceIndex;
+#[cfg(any(feature = "tracing", feature = "deadlock-detect", doc))]
+use crate::trace::{self, FmtIndex};
+use crate::{
+    deadlock::{self, CellNames},
+    order, BlockLocks, BorrowedIndex, BorrowedShadowLocks, CanonicalOrder, Collection,
+    CollectionError, CompareAndSwapError, FromCells, LockState, MapCollection, Pod, PtrCollection,
+    SeqLock, ShadowLock, ShadowLocksCollection,
+};
 
-use super::{ReadCellGuard, RoomGuard, WriteCellGuard};
+use super::{
+    guard::{CellReadGuard, CellWriteGuard},
+    notify::Notifier,
+    BareReadCellGuard, BareWriteCellGuard, CellHandle, CellLogger, CellWriteToken, Entry,
+    LockObserver, LockerRoomBuilder, LockerRoomView, LoggedWriteCellGuard, MultiReadGuard,
+    MultiWriteGuard, RangeReadGuard, RangeWriteGuard, ReadCellGuard, RoomGuard, RoomReadCtx,
+    ScopedLockerRoom, VacantEntry, WriteCellGuard,
+};
+#[cfg(any(feature = "stats", doc))]
+use super::{stats::Counters, LockerRoomStats};
 
 /// Provides readers-writer lock for each indexed cell or exclusive write access to whole collection.
 ///
@@ -19,41 +50,428 @@ use super::{ReadCellGuard, RoomGuard, WriteCellGuard};
 /// });
 /// assert_eq!(3, *locker_room.read_cell(0).unwrap());
 /// ```
-pub struct LockerRoom<T>
+///
+/// # Guards and resizing
+///
+/// Every cell guard also holds the lock of whole collection with shared access, while
+/// [`lock_room`](LockerRoom::lock_room) needs it exclusively. So the collection can't be resized, e.g. truncated
+/// under `lock_room`, while any cell guard is alive: `lock_room` waits until all of them are dropped, and references
+/// given by the guards never dangle.
+///
+/// By default cells are locked with [`Collection::ShadowLocks`]. Other shadow locks `S` can be chosen with [`LockerRoom::builder`].
+///
+/// # Without the lock of whole collection
+///
+/// If cells are never inserted or removed, the lock of whole collection can be left out with `ROOM` set to `false`,
+/// e.g. by converting a `LockerRoom` into [`LockerRoomCells`](crate::sync::LockerRoomCells), which is
+/// `LockerRoom<T, S, false>`. Such `LockerRoom` has no field for the lock at all, and its guards
+/// [`BareReadCellGuard`](crate::sync::BareReadCellGuard) and [`BareWriteCellGuard`](crate::sync::BareWriteCellGuard)
+/// hold only the lock of their cell, so they are smaller and cheaper to acquire and release. `cargo bench --bench
+/// no_room` compares locking of cells with and without it.
+///
+/// It only has `read_cell`, `write_cell`, `indices`, `trace_indices`, `into_inner` and `into_parts`: everything else
+/// here either changes the set of cells, like [`lock_room`](Self::lock_room), or relies on the lock of whole
+/// collection to see it unchanged, so it exists only if `ROOM` is `true`.
+/// ```compile_fail
+/// # use lockerroom::{LockerRoom, sync::LockerRoomCells};
+/// let locker_room: LockerRoomCells<_> = LockerRoom::from(vec![0, 1]).into();
+/// locker_room.lock_room().push(2);
+/// ```
+pub struct LockerRoom<T, S = <T as Collection>::ShadowLocks, const ROOM: bool = true>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+    Room<ROOM>: RoomLock<S::Lock>,
 {
     collection: UnsafeCell<T>,
-    global_lock: RwLock<()>,
-    index_locks: UnsafeCell<T::ShadowLocks>,
+    global_lock: <Room<ROOM> as RoomLock<S::Lock>>::Lock,
+    index_locks: UnsafeCell<S>,
+    observer: Option<Arc<dyn LockObserver<T::Idx>>>,
+    notifier: Notifier,
+    #[cfg(any(feature = "tracing", feature = "deadlock-detect", doc))]
+    fmt_index: Option<FmtIndex<T::Idx>>,
+    #[cfg(any(feature = "stats", doc))]
+    counters: Counters,
     phantom: PhantomData<T::Idx>,
 }
 
-unsafe impl<T: Collection> Sync for LockerRoom<T> {}
+/// The `ROOM` parameter of [`LockerRoom`] as a type, which picks the lock of whole collection with [`RoomLock`].
+pub struct Room<const ROOM: bool>;
+
+/// Picks the lock of whole collection of a [`LockerRoom`] whose shadow locks are locks `L`: `L` itself if `ROOM`
+/// is `true` and zero-sized `()` otherwise.
+///
+/// It's implemented for both values of `ROOM` and every lock, so it only needs to be named in bounds of code generic
+/// over `ROOM`.
+pub trait RoomLock<L> {
+    /// Type of the lock of whole collection.
+    type Lock: Default;
+}
+
+impl<L: Default> RoomLock<L> for Room<true> {
+    type Lock = L;
+}
+
+impl<L> RoomLock<L> for Room<false> {
+    type Lock = ();
+}
+
+// Threads share the collection and the shadow locks, get mutable access to them through `lock_room`
+// and to the cell values through `write_cell`. `Send` is derived automatically with the right bounds.
+unsafe impl<T, S, const ROOM: bool> Sync for LockerRoom<T, S, ROOM>
+where
+    T: Collection + Send + Sync,
+    T::Output: Send + Sync,
+    S: ShadowLocksCollection<Idx = T::Idx> + Send + Sync,
+    Room<ROOM>: RoomLock<S::Lock>,
+{
+}
+
+impl<T> LockerRoom<T>
+where
+    T: Collection,
+{
+    /// Creates a [`LockerRoomBuilder`] that allows to configure the `LockerRoom` before creating it.
+    pub fn builder(collection: T) -> LockerRoomBuilder<T> {
+        LockerRoomBuilder::new(collection)
+    }
+
+    /// Creates a `LockerRoom` which notifies the observer about locking of cells. See [`LockObserver`].
+    pub fn with_observer(collection: T, observer: Arc<dyn LockObserver<T::Idx>>) -> Self {
+        Self::builder(collection).observer(observer).build()
+    }
+}
+
+impl<T> LockerRoom<T, BlockLocks>
+where
+    T: Collection<Idx = usize>,
+{
+    /// Creates a `LockerRoom` where every lock is shared by `block_size` consecutive cells. See [`BlockLocks`]
+    /// for what it costs.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `block_size` is zero.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room = LockerRoom::with_block_size(vec![0; 8], 4);
+    /// let guard = locker_room.write_cell(0).unwrap();
+    /// // Cell 3 shares the lock with cell 0, but cell 4 doesn't.
+    /// *locker_room.write_cell(4).unwrap() += 1;
+    /// # drop(guard);
+    /// ```
+    pub fn with_block_size(collection: T, block_size: usize) -> Self {
+        LockerRoom::builder(collection).block(block_size).build()
+    }
+}
+
+impl<'a, T, S, const ROOM: bool> LockerRoom<T, S, ROOM>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+    Room<ROOM>: RoomLock<S::Lock>,
+{
+    pub(crate) fn with_shadow_locks(
+        collection: T,
+        index_locks: S,
+        observer: Option<Arc<dyn LockObserver<T::Idx>>>,
+    ) -> Self {
+        debug_assert_eq!(
+            collection.indices().count(),
+            index_locks.len(),
+            "shadow locks must have a lock for every index of the collection"
+        );
+        Self {
+            collection: UnsafeCell::new(collection),
+            global_lock: Default::default(),
+            index_locks: UnsafeCell::new(index_locks),
+            observer,
+            notifier: Default::default(),
+            #[cfg(any(feature = "tracing", feature = "deadlock-detect", doc))]
+            fmt_index: None,
+            #[cfg(any(feature = "stats", doc))]
+            counters: Default::default(),
+            phantom: Default::default(),
+        }
+    }
+
+    /// Makes spans and deadlock reports of the `LockerRoom` record indices of cells.
+    ///
+    /// With feature `tracing` every wait for a cell lock is covered by `lock_cell` span at `TRACE` level which records
+    /// the kind of access, `read` or `write`. Its field `index` is `_` unless this function is called. The wait for
+    /// [`lock_room`](Self::lock_room) is covered by `lock_room` span. Releasing of locks isn't traced.
+    ///
+    /// Spans cost almost nothing if no subscriber is interested in them, and indices are formatted only if one is.
+    ///
+    /// With feature `deadlock-detect` in debug builds the thread that would close a cycle of waits for cell locks panics.
+    /// The panic message names every cell in the cycle by its index, or by `_` unless this function is called.
+    #[cfg(any(feature = "tracing", feature = "deadlock-detect", doc))]
+    #[doc(cfg(any(feature = "tracing", feature = "deadlock-detect")))]
+    pub fn trace_indices(mut self) -> Self
+    where
+        T::Idx: std::fmt::Debug,
+    {
+        self.fmt_index = Some(trace::fmt_index);
+        self
+    }
+
+    // Names the cells in deadlock reports.
+    #[cfg(all(feature = "deadlock-detect", debug_assertions))]
+    fn cell_names(&self) -> CellNames<T::Idx> {
+        CellNames::new(self.fmt_index)
+    }
+
+    #[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+    fn cell_names(&self) -> CellNames<T::Idx> {
+        CellNames::new()
+    }
+
+    fn cell_name(&self, index: &T::Idx) -> Cow<'static, str> {
+        self.cell_names().name(index)
+    }
+
+    fn lock_read(&self, index: &T::Idx, lock: &'a S::Lock) -> CellReadGuard<'a, S> {
+        #[cfg(feature = "stats")]
+        self.counters.read();
+        deadlock::acquire(
+            lock,
+            false,
+            || self.cell_name(index),
+            || {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::trace_span!(
+                    "lock_cell",
+                    kind = "read",
+                    index = ?TraceIndex(index, self.fmt_index)
+                )
+                .entered();
+                let Some(observer) = &self.observer else {
+                    return lock.read();
+                };
+                let guard = lock.try_read().unwrap_or_else(|| {
+                    observer.on_contended(index);
+                    lock.read()
+                });
+                observer.on_read_acquired(index);
+                guard
+            },
+        )
+    }
+
+    fn lock_write(&self, index: &T::Idx, lock: &'a S::Lock) -> CellWriteGuard<'a, S> {
+        #[cfg(feature = "stats")]
+        self.counters.write();
+        deadlock::acquire(
+            lock,
+            true,
+            || self.cell_name(index),
+            || {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::trace_span!(
+                    "lock_cell",
+                    kind = "write",
+                    index = ?TraceIndex(index, self.fmt_index)
+                )
+                .entered();
+                let Some(observer) = &self.observer else {
+                    return lock.write();
+                };
+                let guard = lock.try_write().unwrap_or_else(|| {
+                    observer.on_contended(index);
+                    lock.write()
+                });
+                observer.on_write_acquired(index);
+                guard
+            },
+        )
+    }
+
+    /// Consumes this `LockerRoom`, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.collection.into_inner()
+    }
+
+    /// Consumes this `LockerRoom`, returning the underlying data and its shadow locks, which can be given back
+    /// to [`from_parts`](Self::from_parts) instead of creating new ones.
+    ///
+    /// No guard can outlive the `LockerRoom`, so all the returned locks are unlocked.
+    pub fn into_parts(self) -> (T, S) {
+        (self.collection.into_inner(), self.index_locks.into_inner())
+    }
+}
 
-impl<'a, T> LockerRoom<T>
+impl<'a, T, S> LockerRoom<T, S>
 where
     T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
 {
+    /// Creates a `LockerRoom` from the collection and its shadow locks, e.g. returned by
+    /// [`into_parts`](Self::into_parts), without creating the locks with [`Collection::shadow_locks`].
+    ///
+    /// The shadow locks must be unlocked and have a lock for every index of the collection.
+    /// In debug builds this function panics if the number of locks differs from the number of indices;
+    /// [`validate`](Self::validate) checks the rest.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// let (mut v, shadow_locks) = locker_room.into_parts();
+    /// v.reverse();
+    /// let locker_room = LockerRoom::from_parts(v, shadow_locks);
+    /// assert_eq!(3, *locker_room.read_cell(0).unwrap());
+    /// ```
+    pub fn from_parts(collection: T, shadow_locks: S) -> Self {
+        Self::with_shadow_locks(collection, shadow_locks, None)
+    }
+
+    // Like `lock_read`, but gives up after `timeout`. Waiting for a limited time can't deadlock,
+    // so the lock is only recorded as held.
+    fn lock_read_for(
+        &self,
+        index: &T::Idx,
+        lock: &'a S::Lock,
+        timeout: Duration,
+    ) -> Option<CellReadGuard<'a, S>> {
+        let guard = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!(
+                "lock_cell",
+                kind = "read",
+                index = ?TraceIndex(index, self.fmt_index)
+            )
+            .entered();
+            match &self.observer {
+                None => lock.try_read_for(timeout),
+                Some(observer) => lock
+                    .try_read()
+                    .or_else(|| {
+                        observer.on_contended(index);
+                        lock.try_read_for(timeout)
+                    })
+                    .inspect(|_| observer.on_read_acquired(index)),
+            }
+        };
+        let Some(guard) = guard else {
+            self.timed_out(index);
+            return None;
+        };
+        #[cfg(feature = "stats")]
+        self.counters.read();
+        Some(deadlock::hold(lock, false, || self.cell_name(index), guard))
+    }
+
+    // Like `lock_write`, but gives up after `timeout`.
+    fn lock_write_for(
+        &self,
+        index: &T::Idx,
+        lock: &'a S::Lock,
+        timeout: Duration,
+    ) -> Option<CellWriteGuard<'a, S>> {
+        let guard = {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!(
+                "lock_cell",
+                kind = "write",
+                index = ?TraceIndex(index, self.fmt_index)
+            )
+            .entered();
+            match &self.observer {
+                None => lock.try_write_for(timeout),
+                Some(observer) => lock
+                    .try_write()
+                    .or_else(|| {
+                        observer.on_contended(index);
+                        lock.try_write_for(timeout)
+                    })
+                    .inspect(|_| observer.on_write_acquired(index)),
+            }
+        };
+        let Some(guard) = guard else {
+            self.timed_out(index);
+            return None;
+        };
+        #[cfg(feature = "stats")]
+        self.counters.write();
+        Some(deadlock::hold(lock, true, || self.cell_name(index), guard))
+    }
+
+    fn timed_out(&self, index: &T::Idx) {
+        #[cfg(feature = "stats")]
+        self.counters.timeout();
+        if let Some(observer) = &self.observer {
+            observer.on_timed_out(index);
+        }
+    }
+
     /// Locks cell at the index with shared read access, blocking the current thread until it can be acquired.
     ///
     /// This function will return `None` if there is no cell with such index.
     ///
     /// Returns an RAII guard which will release this thread's shared access once it is dropped.
-    pub fn read_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<ReadCellGuard<'a, T>> {
-        let global_lock_guard = self
-            .global_lock
-            .read()
-            .unwrap_or_else(|err| err.into_inner());
-        let index_locks = unsafe { &*self.index_locks.get() };
-        let index_lock_guard = index_locks
-            .index(index.borrow())?
-            .read()
-            .unwrap_or_else(|err| err.into_inner());
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<ReadCellGuard<'a, T, S>> {
+        let global_lock_guard = self.global_lock.read();
+        let index = index.borrow();
+        let collection = unsafe { &*self.collection.get() };
+        // Hash maps hash the index once for both the shadow locks and the collection.
+        let hash = collection.hash_index(index);
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index_hashed(index, hash)?;
+        let index_lock_guard = self.lock_read(index, index_lock);
+        collection.index_hashed(index, hash).map(|v| {
+            ReadCellGuard::new(
+                v,
+                (&self.global_lock, global_lock_guard),
+                (index_lock, index_lock_guard),
+            )
+        })
+    }
+
+    /// Locks cell at the key with shared read access like [`read_cell`](Self::read_cell), but looks the cell up
+    /// by a borrowed form of the key, e.g. by `&str` for `String` keys without allocating.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = HashMap::from([(String::from("a"), 1)]).into();
+    /// assert_eq!(1, *locker_room.read_cell_borrowed("a").unwrap());
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell_borrowed<Q>(&'a self, key: &Q) -> Option<ReadCellGuard<'a, T, S>>
+    where
+        T: BorrowedIndex<Q>,
+        S: BorrowedShadowLocks<Q>,
+        Q: ?Sized,
+    {
+        let global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let (index, index_lock) = index_locks.index_borrowed(key)?;
+        let index_lock_guard = self.lock_read(index, index_lock);
         let collection = unsafe { &*self.collection.get() };
+        collection.index_borrowed(key).map(|v| {
+            ReadCellGuard::new(
+                v,
+                (&self.global_lock, global_lock_guard),
+                (index_lock, index_lock_guard),
+            )
+        })
+    }
+
+    /// Locks cell at the key with exclusive write access like [`write_cell`](Self::write_cell), but looks the cell
+    /// up by a borrowed form of the key, e.g. by `&str` for `String` keys without allocating.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell_borrowed<Q>(&'a self, key: &Q) -> Option<WriteCellGuard<'a, T, S>>
+    where
+        T: BorrowedIndex<Q>,
+        S: BorrowedShadowLocks<Q>,
+        Q: ?Sized,
+    {
+        let global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let (index, index_lock) = index_locks.index_borrowed(key)?;
+        let index_lock_guard = self.lock_write(index, index_lock);
+        let collection = unsafe { &mut *self.collection.get() };
         collection
-            .index(index)
-            .map(|v| ReadCellGuard::new(v, global_lock_guard, index_lock_guard))
+            .index_borrowed_mut(key)
+            .map(|v| WriteCellGuard::new(v, global_lock_guard, index_lock_guard))
     }
 
     /// Locks cell at the index with exclusive write access, blocking the current thread until it can be acquired.
@@ -61,91 +479,4141 @@ where
     /// This function will return `None` if there is no cell with such index.
     ///
     /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
-    pub fn write_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<WriteCellGuard<'a, T>> {
-        let global_lock_guard = self
-            .global_lock
-            .read()
-            .unwrap_or_else(|err| err.into_inner());
-        let index_locks = unsafe { &*self.index_locks.get() };
-        let index_lock_guard = index_locks
-            .index(index.borrow())?
-            .write()
-            .unwrap_or_else(|err| err.into_inner());
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<WriteCellGuard<'a, T, S>> {
+        let global_lock_guard = self.global_lock.read();
+        let index = index.borrow();
+        // Hash maps hash the index once for both the shadow locks and the collection.
+        let hash = unsafe { &*self.collection.get() }.hash_index(index);
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock_guard = self.lock_write(index, index_locks.index_hashed(index, hash)?);
         let collection = unsafe { &mut *self.collection.get() };
         collection
-            .index_mut(index)
+            .index_mut_hashed(index, hash)
             .map(|v| WriteCellGuard::new(v, global_lock_guard, index_lock_guard))
     }
 
-    /// Exclusively locks whole collection with right access.
+    /// Locks cell at the index with shared read access, blocking the current thread no longer than `timeout`.
     ///
-    /// No cell locks can be acquired by other threads when locked whole collection.
+    /// The lock of whole collection and the lock of the cell share the timeout: the cell is waited for only as long
+    /// as is left after the collection. This function will return `None` if there is no cell with such index or if
+    /// the locks couldn't be acquired in time, and no lock is held then. See [`ShadowLock::try_read_for`] on how the
+    /// waiting is done, e.g. [`parking_lot::RwLock`](https://docs.rs/parking_lot/latest/parking_lot/type.RwLock.html)
+    /// parks the thread until the lock is released or the timeout expires.
+    /// ```
+    /// # use std::time::Duration;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let guard = locker_room.write_cell(0).unwrap();
+    /// assert!(locker_room.try_read_cell_for(0, Duration::from_millis(10)).is_none());
+    /// drop(guard);
+    /// assert_eq!(1, *locker_room.try_read_cell_for(0, Duration::from_millis(10)).unwrap());
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn try_read_cell_for(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        timeout: Duration,
+    ) -> Option<ReadCellGuard<'a, T, S>> {
+        let deadline = Instant::now() + timeout;
+        let index = index.borrow();
+        let Some(global_lock_guard) = self.global_lock.try_read_for(timeout) else {
+            self.timed_out(index);
+            return None;
+        };
+        let collection = unsafe { &*self.collection.get() };
+        let hash = collection.hash_index(index);
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index_hashed(index, hash)?;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let index_lock_guard = self.lock_read_for(index, index_lock, remaining)?;
+        collection.index_hashed(index, hash).map(|v| {
+            ReadCellGuard::new(
+                v,
+                (&self.global_lock, global_lock_guard),
+                (index_lock, index_lock_guard),
+            )
+        })
+    }
+
+    /// Locks cell at the index with exclusive write access, blocking the current thread no longer than `timeout`.
     ///
-    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
-    pub fn lock_room(&'a self) -> RoomGuard<'a, T> {
-        let global_lock_guard = self
-            .global_lock
-            .write()
-            .unwrap_or_else(|err| err.into_inner());
-        let index_locks = unsafe { &mut *self.index_locks.get() };
+    /// The timeout is shared by the lock of whole collection and the lock of the cell like in
+    /// [`try_read_cell_for`](Self::try_read_cell_for). This function will return `None` if there is no cell with such
+    /// index or if the locks couldn't be acquired in time, and no lock is held then.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn try_write_cell_for(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        timeout: Duration,
+    ) -> Option<WriteCellGuard<'a, T, S>> {
+        let deadline = Instant::now() + timeout;
+        let index = index.borrow();
+        let Some(global_lock_guard) = self.global_lock.try_read_for(timeout) else {
+            self.timed_out(index);
+            return None;
+        };
+        let hash = unsafe { &*self.collection.get() }.hash_index(index);
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index_hashed(index, hash)?;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let index_lock_guard = self.lock_write_for(index, index_lock, remaining)?;
         let collection = unsafe { &mut *self.collection.get() };
-        RoomGuard::new(collection, index_locks, global_lock_guard)
+        collection
+            .index_mut_hashed(index, hash)
+            .map(|v| WriteCellGuard::new(v, global_lock_guard, index_lock_guard))
     }
 
-    /// Consumes this `LockerRoom`, returning the underlying data.
-    pub fn into_inner(self) -> T {
-        self.collection.into_inner()
+    /// Locks cell at the index with shared read access like [`read_cell`](Self::read_cell), for indices known to be valid.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no cell with such index. The message includes the index.
+    /// ```should_panic
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// assert_eq!(2, *locker_room.read_cell_expect(1));
+    /// // Panics with "no cell at index 2".
+    /// let _ = locker_room.read_cell_expect(2);
+    /// ```
+    #[track_caller]
+    pub fn read_cell_expect(&'a self, index: impl Borrow<T::Idx>) -> ReadCellGuard<'a, T, S>
+    where
+        T::Idx: Debug,
+    {
+        let index = index.borrow();
+        match self.read_cell(index) {
+            Some(guard) => guard,
+            None => panic!("no cell at index {index:?}"),
+        }
     }
-}
 
-impl<T> From<T> for LockerRoom<T>
-where
-    T: Collection,
-{
-    fn from(value: T) -> Self {
-        let index_locks = value.shadow_locks();
-        Self {
-            collection: UnsafeCell::new(value),
-            global_lock: Default::default(),
-            index_locks: UnsafeCell::new(index_locks),
-            phantom: Default::default(),
+    /// Locks cell at the index with exclusive write access like [`write_cell`](Self::write_cell), for indices known
+    /// to be valid.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no cell with such index. The message includes the index.
+    #[track_caller]
+    pub fn write_cell_expect(&'a self, index: impl Borrow<T::Idx>) -> WriteCellGuard<'a, T, S>
+    where
+        T::Idx: Debug,
+    {
+        let index = index.borrow();
+        match self.write_cell(index) {
+            Some(guard) => guard,
+            None => panic!("no cell at index {index:?}"),
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::{ops::DerefMut, sync::Arc, thread};
+    /// Locks cell at the index with exclusive write access like [`write_cell`](Self::write_cell) and returns a clone
+    /// of its value along with the guard.
+    ///
+    /// The value is cloned under the write lock, so it's exactly the value the guard starts with. This function
+    /// will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![vec![1, 2], vec![3]].into();
+    /// let (previous, mut cell) = locker_room.write_cell_with_previous(0).unwrap();
+    /// cell.retain(|v| v % 2 == 0);
+    /// cell.push(4);
+    /// let removed: Vec<_> = previous.iter().filter(|v| !cell.contains(v)).collect();
+    /// let added: Vec<_> = cell.iter().filter(|v| !previous.contains(v)).collect();
+    /// assert_eq!((vec![&1], vec![&4]), (removed, added));
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell_with_previous(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+    ) -> Option<(T::Output, WriteCellGuard<'a, T, S>)>
+    where
+        T::Output: Clone,
+    {
+        let guard = self.write_cell(index)?;
+        Some((T::Output::clone(&guard), guard))
+    }
 
-    use super::LockerRoom;
+    /// Locks cell at the index with exclusive write access like [`write_cell`](Self::write_cell). Once the returned
+    /// guard is dropped, the new value of the cell is recorded with the logger before the cell is released.
+    /// See [`CellLogger`].
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell_logged<'l, I: Borrow<T::Idx>>(
+        &'a self,
+        index: I,
+        logger: &'l dyn CellLogger<T::Idx, T::Output>,
+    ) -> Option<LoggedWriteCellGuard<'a, 'l, T, S, I>> {
+        let guard = self.write_cell(index.borrow())?;
+        Some(LoggedWriteCellGuard::new(index, guard, logger))
+    }
 
-    #[test]
-    fn t() {
-        let len = 9999;
-        let v: Vec<_> = (0..len).collect();
-        let locker_room: Arc<LockerRoom<_>> = Arc::new(v.into());
-        thread::scope(|scope| {
-            for _ in 0..len {
-                scope.spawn(|| {
-                    for i in 0..len {
-                        *locker_room.write_cell(i).unwrap() += i;
-                    }
-                });
+    /// Returns a handle of the cell at the index, which locks the cell on demand with
+    /// [`read`](CellHandle::read) and [`write`](CellHandle::write).
+    ///
+    /// Nothing is locked and the index isn't checked until the handle is used.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![0, 1, 2, 3].into();
+    /// let cell = locker_room.cell(3);
+    /// *cell.write().unwrap() += 1;
+    /// assert_eq!(4, *cell.read().unwrap());
+    /// assert!(locker_room.cell(4).read().is_none());
+    /// ```
+    pub fn cell(&'a self, index: T::Idx) -> CellHandle<'a, T, S> {
+        CellHandle::new(self, index)
+    }
+
+    /// Releases the cell kept locked by the token of [`WriteCellGuard::leak`].
+    ///
+    /// The token must come from this `LockerRoom`. A token of another one releases its own cell anyway.
+    pub fn unlock_cell(&'a self, token: CellWriteToken<'a, T, S>) {
+        token.unlock();
+    }
+
+    /// Locks cell at the index with exclusive write access once `cond` holds for its value, blocking the current
+    /// thread until then.
+    ///
+    /// The condition is checked while the cell is locked. If it isn't met, the cell is released and the thread parks
+    /// until [`notify_cell`](Self::notify_cell) is called, then it checks again. So whoever changes the value in a way
+    /// the waiter may care about has to call `notify_cell` after releasing the cell.
+    ///
+    /// This function will return `None` if there is no cell with such index, including the case when the cell is
+    /// removed while the thread waits.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    /// ```
+    /// # use std::thread;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![None, None].into();
+    /// thread::scope(|scope| {
+    ///     scope.spawn(|| {
+    ///         *locker_room.write_cell(1).unwrap() = Some("ready");
+    ///         locker_room.notify_cell(1);
+    ///     });
+    ///     let mut guard = locker_room.wait_write_cell(1, Option::is_some).unwrap();
+    ///     assert_eq!(Some("ready"), guard.take());
+    /// });
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn wait_write_cell(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        cond: impl Fn(&T::Output) -> bool,
+    ) -> Option<WriteCellGuard<'a, T, S>>
+    where
+        T::Idx: Hash,
+    {
+        let index = index.borrow();
+        loop {
+            // Registered before the check, so a notification sent after the check isn't missed.
+            let registration = self.notifier.register(index);
+            let guard = self.write_cell(index)?;
+            if cond(&guard) {
+                return Some(guard);
             }
-            scope.spawn(|| {
-                for i in 0..len {
-                    let mut guard = locker_room.lock_room();
-                    let v = guard.deref_mut();
-                    v.resize(i + len + 1, i);
-                }
-            });
-        });
-        let v = Arc::into_inner(locker_room).unwrap().into_inner();
-        for i in 0..len {
-            assert_eq!(i * (len + 1), v[i]);
+            drop(guard);
+            registration.wait();
         }
-        for i in 0..len {
-            assert_eq!(i, v[i + len]);
+    }
+
+    /// Wakes the threads waiting in [`wait_write_cell`](Self::wait_write_cell) for the cell at the index to check
+    /// their conditions again.
+    ///
+    /// Waiters of other cells stay asleep, unless their indices have the same hash as this one. Those wake up, find
+    /// their conditions unchanged and go back to waiting.
+    pub fn notify_cell(&'a self, index: impl Borrow<T::Idx>)
+    where
+        T::Idx: Hash,
+    {
+        self.notifier.notify(index.borrow());
+    }
+
+    /// Locks cells at the indices with shared read access, blocking the current thread until all of them can be acquired.
+    ///
+    /// Cells are locked in [`CanonicalOrder`] of indices, which is ascending for [`Ord`] ones, and repeated indices
+    /// are locked once, so concurrent calls of `read_cells` and [`write_cells`](Self::write_cells) can't deadlock each other.
+    /// A lock shared by several of the cells, e.g. a block of [`BlockLocks`](crate::BlockLocks), is locked once too.
+    ///
+    /// This function will return `None` if there is no cell with one of the indices.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access to all the cells once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> Option<MultiReadGuard<'a, T, S>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let collection = unsafe { &*self.collection.get() };
+        let mut cells = Vec::with_capacity(indices.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in indices {
+            let index_lock = index_locks.index(&index)?;
+            // Locking it again could deadlock behind a queued writer. Cells sharing a lock are adjacent in the order.
+            let index_lock_guard = (!previous
+                .is_some_and(|previous| ptr::eq(previous, index_lock)))
+            .then(|| self.lock_read(&index, index_lock));
+            previous = Some(index_lock);
+            let value = collection.index(&index)?;
+            cells.push((index, value, index_lock_guard));
+        }
+        Some(MultiReadGuard::new(cells, global_lock_guard))
+    }
+
+    /// Locks cells at the indices with exclusive write access, blocking the current thread until all of them can be acquired.
+    ///
+    /// Cells are locked in [`CanonicalOrder`] of indices, which is ascending for [`Ord`] ones, and repeated indices
+    /// are locked once, so concurrent calls of [`read_cells`](Self::read_cells) and `write_cells` can't deadlock each other.
+    /// A lock shared by several of the cells, e.g. a block of [`BlockLocks`](crate::BlockLocks), is locked once too.
+    ///
+    /// This function will return `None` if there is no cell with one of the indices. If it panics, e.g. in [`Ord`] of
+    /// the index while looking the next cell up, the cells locked so far are released during unwinding.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access to all the cells once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> Option<MultiWriteGuard<'a, T, S>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        // Guards of the cells locked so far are dropped with `cells` if the indexing panics.
+        let mut cells = Vec::with_capacity(indices.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in indices {
+            let index_lock = index_locks.index(&index)?;
+            // Locking it again would deadlock. Cells sharing a lock are adjacent in the order.
+            let index_lock_guard = (!previous
+                .is_some_and(|previous| ptr::eq(previous, index_lock)))
+            .then(|| self.lock_write(&index, index_lock));
+            previous = Some(index_lock);
+            // Indices are unique, so every cell is borrowed mutably only once.
+            let value = unsafe { &mut *self.collection.get() }.index_mut(&index)?;
+            cells.push((index, value, index_lock_guard));
+        }
+        Some(MultiWriteGuard::new(cells, global_lock_guard))
+    }
+
+    /// Locks every cell with shared read access, blocking the current thread until all of them can be acquired.
+    ///
+    /// Cells are locked in [`CanonicalOrder`] of indices like [`read_cells`](Self::read_cells) does, so it can't
+    /// deadlock with [`lock_all_write`](Self::lock_all_write) or `write_cells` of other threads. The indices are taken
+    /// while whole collection is locked with shared read access, so no cell is inserted or removed in between.
+    ///
+    /// Unlike [`read_room_scoped`](Self::read_room_scoped), which holds only the lock of whole collection, the guard
+    /// holds the lock of every cell: writers of single cells wait for it, while readers of cells and of whole
+    /// collection don't. In exchange it keeps an index, a reference and a lock guard per cell, so its memory grows
+    /// with the collection.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// let cells = locker_room.lock_all_read();
+    /// assert!(locker_room.try_write_cells([1]).is_none());
+    /// assert_eq!(2, *locker_room.read_cell(1).unwrap());
+    /// assert_eq!(6, cells.iter().map(|(_, value)| value).sum::<i32>());
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn lock_all_read(&'a self) -> MultiReadGuard<'a, T, S>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let collection = unsafe { &*self.collection.get() };
+        let mut indices: Vec<_> = collection.indices().collect();
+        order::sort_indices(&mut indices);
+        let mut cells = Vec::with_capacity(indices.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in indices {
+            // Indices without a cell or a lock break the contract of the collection, so they're skipped.
+            let Some(index_lock) = index_locks.index(&index) else {
+                continue;
+            };
+            // A lock shared with the previous cell is held already, as in `read_cells`.
+            let index_lock_guard = (!previous
+                .is_some_and(|previous| ptr::eq(previous, index_lock)))
+            .then(|| self.lock_read(&index, index_lock));
+            previous = Some(index_lock);
+            if let Some(value) = collection.index(&index) {
+                cells.push((index, value, index_lock_guard));
+            }
+        }
+        MultiReadGuard::new(cells, global_lock_guard)
+    }
+
+    /// Locks every cell with exclusive write access, blocking the current thread until all of them can be acquired.
+    ///
+    /// Cells are locked in the same order as [`lock_all_read`](Self::lock_all_read) locks them. Unlike
+    /// [`lock_room`](Self::lock_room), which also gives exclusive access to every cell, it doesn't lock whole
+    /// collection exclusively, so readers of whole collection aren't held back, but cells can't be inserted or removed.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn lock_all_write(&'a self) -> MultiWriteGuard<'a, T, S>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let mut indices: Vec<_> = unsafe { &*self.collection.get() }.indices().collect();
+        order::sort_indices(&mut indices);
+        let mut cells = Vec::with_capacity(indices.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in indices {
+            let Some(index_lock) = index_locks.index(&index) else {
+                continue;
+            };
+            // A lock shared with the previous cell is held already, as in `RoomGuard::into_cells`.
+            let index_lock_guard = (!previous
+                .is_some_and(|previous| ptr::eq(previous, index_lock)))
+            .then(|| self.lock_write(&index, index_lock));
+            previous = Some(index_lock);
+            // Indices are unique, so every cell is borrowed mutably only once.
+            if let Some(value) = unsafe { &mut *self.collection.get() }.index_mut(&index) {
+                cells.push((index, value, index_lock_guard));
+            }
+        }
+        MultiWriteGuard::new(cells, global_lock_guard)
+    }
+
+    /// Attempts to lock cells at the indices with exclusive write access without blocking.
+    ///
+    /// Cells are tried in the same order as [`write_cells`](Self::write_cells) locks them. This function will return
+    /// `None` if there is no cell with one of the indices or if any of the cells or whole collection is locked by
+    /// someone else. The cells locked so far are released then, so the caller can back off and retry instead of
+    /// waiting while holding a part of the cells.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// let cell = locker_room.read_cell(2).unwrap();
+    /// assert!(locker_room.try_write_cells([0, 2]).is_none());
+    /// drop(cell);
+    /// assert!(locker_room.try_write_cells([0, 2]).is_some());
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn try_write_cells(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+    ) -> Option<MultiWriteGuard<'a, T, S>>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut indices: Vec<_> = indices.into_iter().collect();
+        order::sort_indices(&mut indices);
+        let global_lock_guard = self.global_lock.try_read()?;
+        let index_locks = unsafe { &*self.index_locks.get() };
+        // Guards of the cells locked so far are dropped with `cells` on every early return.
+        let mut cells = Vec::with_capacity(indices.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in indices {
+            let index_lock = index_locks.index(&index)?;
+            // A lock shared with the previous cell is held already, as in `write_cells`.
+            let index_lock_guard = if previous.is_some_and(|previous| ptr::eq(previous, index_lock))
+            {
+                None
+            } else {
+                let index_lock_guard = index_lock.try_write()?;
+                #[cfg(feature = "stats")]
+                self.counters.write();
+                Some(deadlock::hold(
+                    index_lock,
+                    true,
+                    || self.cell_name(&index),
+                    index_lock_guard,
+                ))
+            };
+            previous = Some(index_lock);
+            // Indices are unique, so every cell is borrowed mutably only once.
+            let value = unsafe { &mut *self.collection.get() }.index_mut(&index)?;
+            cells.push((index, value, index_lock_guard));
+        }
+        Some(MultiWriteGuard::new(cells, global_lock_guard))
+    }
+
+    /// Locks cells at the indices with shared read access like [`read_cells`](Self::read_cells), calls `f` with
+    /// references to their values and releases the cells.
+    ///
+    /// Values are passed in [`CanonicalOrder`] of indices without repeats. The locks can't outlive `f`,
+    /// so this is harder to misuse than holding the guard of `read_cells`.
+    ///
+    /// This function will return `None` without calling `f` if there is no cell with one of the indices.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// assert_eq!(Some(4), locker_room.with_cells([2, 0], |values| values.iter().copied().sum()));
+    /// assert_eq!(None, locker_room.with_cells([0, 3], |values| values.len()));
+    /// ```
+    pub fn with_cells<R>(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+        f: impl FnOnce(&[&T::Output]) -> R,
+    ) -> Option<R>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let guard = self.read_cells(indices)?;
+        let values: Vec<_> = guard.iter().map(|(_, value)| value).collect();
+        Some(f(&values))
+    }
+
+    /// Locks cells at the indices with exclusive write access like [`write_cells`](Self::write_cells), calls `f`
+    /// with mutable references to their values and releases the cells.
+    ///
+    /// Values are passed in [`CanonicalOrder`] of indices without repeats. The locks can't outlive `f`,
+    /// so this is harder to misuse than holding the guard of `write_cells`.
+    ///
+    /// This function will return `None` without calling `f` if there is no cell with one of the indices.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// locker_room.with_cells_mut([2, 0], |values| values.iter_mut().for_each(|value| **value *= 10));
+    /// assert_eq!(vec![10, 2, 30], locker_room.into_inner());
+    /// ```
+    pub fn with_cells_mut<R>(
+        &'a self,
+        indices: impl IntoIterator<Item = T::Idx>,
+        f: impl FnOnce(&mut [&mut T::Output]) -> R,
+    ) -> Option<R>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let mut guard = self.write_cells(indices)?;
+        let mut values: Vec<_> = guard.iter_mut().map(|(_, value)| value).collect();
+        Some(f(&mut values))
+    }
+
+    /// Swaps values of the cells at two indices, locking both of them with exclusive write access like [`write_cells`](Self::write_cells).
+    ///
+    /// This function will return `false` if there is no cell with one of the indices. Swapping a cell with itself does nothing.
+    pub fn swap_cells(&'a self, a: T::Idx, b: T::Idx) -> bool
+    where
+        T::Idx: CanonicalOrder,
+        T::Output: Sized,
+    {
+        let Some(mut guard) = self.write_cells([a, b]) else {
+            return false;
+        };
+        let mut values = guard.iter_mut().map(|(_, value)| value);
+        if let (Some(a), Some(b)) = (values.next(), values.next()) {
+            mem::swap(a, b);
+        }
+        true
+    }
+
+    /// Copies the value of the cell at the index out. The cell is locked with shared read access only while it's copied.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// assert_eq!(Some(2), locker_room.get_cell(1));
+    /// assert_eq!(None, locker_room.get_cell(2));
+    /// ```
+    pub fn get_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<T::Output>
+    where
+        T::Output: Copy,
+    {
+        self.read_cell(index).map(|guard| *guard)
+    }
+
+    /// Clones the values of the cells at the indices into a new collection, locking every cell with shared read
+    /// access only while its value is cloned.
+    ///
+    /// Maps keep the indices as keys, while vectors get the values in the order of the indices, see [`FromCells`].
+    /// Missing cells are skipped. At most one cell is locked at a time, so it can't deadlock with other threads, but
+    /// the values are not a consistent snapshot: a cell may be written after the previous one was cloned.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3, 4].into();
+    /// assert_eq!(vec![4, 2], locker_room.project([3, 1, 9]));
+    ///
+    /// let locker_room: LockerRoom<_> = HashMap::from([("a", 1), ("b", 2), ("c", 3)]).into();
+    /// assert_eq!(HashMap::from([("a", 1), ("c", 3)]), locker_room.project(["a", "c"]));
+    /// ```
+    pub fn project(&'a self, indices: impl IntoIterator<Item = T::Idx>) -> T
+    where
+        T: FromCells,
+        T::Value: Clone,
+    {
+        T::from_cells(indices.into_iter().filter_map(|index| {
+            let value = T::Value::clone(&*self.read_cell(&index)?);
+            Some((index, value))
+        }))
+    }
+
+    /// Copies the value of the cell at the index out like [`get_cell`](Self::get_cell), but without locking the cell:
+    /// the value is read again while a writer changes it. The value may be torn while it's read, so only [`Pod`] values
+    /// are copied this way, see [`SeqLock`], and only from collections which can point to a cell without referencing
+    /// it, see [`PtrCollection`].
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::{LockerRoom, SeqLocks};
+    /// let locker_room = LockerRoom::builder(vec![1, 2])
+    ///     .shadow_locks::<SeqLocks>()
+    ///     .build();
+    /// assert!(locker_room.set_cell(1, 3));
+    /// assert_eq!(Some(3), locker_room.get_cell_seq(1));
+    /// assert_eq!(None, locker_room.get_cell_seq(2));
+    /// ```
+    pub fn get_cell_seq(&'a self, index: impl Borrow<T::Idx>) -> Option<T::Output>
+    where
+        T: PtrCollection,
+        T::Output: Pod,
+        S: ShadowLocksCollection<Lock = SeqLock>,
+    {
+        let _global_lock_guard = self.global_lock.read();
+        let index = index.borrow();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index(index)?;
+        // The global lock is held, so the collection is only mutated through its cells. A writer may hold a mutable
+        // reference to this cell, so the pointer is taken without a shared reference to it.
+        let value = unsafe { T::index_ptr(self.collection.get(), index) }?;
+        // SAFETY: the copy is a deliberately racy volatile read which a writer may tear. The value is `Pod`, so even
+        // a torn copy is a valid value, and `read_with` throws it away unless the sequence counter shows that no
+        // writer held the lock meanwhile.
+        Some(index_lock.read_with(|| unsafe { ptr::read_volatile(value) }))
+    }
+
+    /// Overwrites the value of the cell at the index. The cell is locked with exclusive write access only while it's written.
+    ///
+    /// This function will return `false` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// assert!(locker_room.set_cell(1, 3));
+    /// assert!(!locker_room.set_cell(2, 3));
+    /// assert_eq!(Some(3), locker_room.get_cell(1));
+    /// ```
+    pub fn set_cell(&'a self, index: impl Borrow<T::Idx>, value: T::Output) -> bool
+    where
+        T::Output: Sized,
+    {
+        self.write_cell(index)
+            .map(|mut guard| *guard = value)
+            .is_some()
+    }
+
+    /// Replaces the value of the cell at the index, locking it with exclusive write access, and returns the old value.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// assert_eq!(Some(2), locker_room.replace_cell(1, 3));
+    /// assert_eq!(None, locker_room.replace_cell(2, 3));
+    /// assert_eq!(3, *locker_room.read_cell(1).unwrap());
+    /// ```
+    pub fn replace_cell(&'a self, index: impl Borrow<T::Idx>, value: T::Output) -> Option<T::Output>
+    where
+        T::Output: Sized,
+    {
+        self.write_cell(index)
+            .map(|mut guard| mem::replace(&mut *guard, value))
+    }
+
+    /// Takes the value of the cell at the index, locking it with exclusive write access, and leaves `Default::default()` in its place.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![String::from("a")].into();
+    /// assert_eq!(Some(String::from("a")), locker_room.take_cell(0));
+    /// assert_eq!(None, locker_room.take_cell(1));
+    /// assert!(locker_room.read_cell(0).unwrap().is_empty());
+    /// ```
+    pub fn take_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<T::Output>
+    where
+        T::Output: Default,
+    {
+        self.write_cell(index)
+            .map(|mut guard| mem::take(&mut *guard))
+    }
+
+    /// Locks the cell at the index with exclusive write access and replaces its value with `new` if it's equal to `expected`.
+    ///
+    /// Otherwise the value isn't changed and `new` is given back in the error: [`CompareAndSwapError::Mismatch`] if the value
+    /// isn't equal to `expected` and [`CompareAndSwapError::NoCell`] if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::{CompareAndSwapError, LockerRoom};
+    /// let locker_room: LockerRoom<_> = vec![0].into();
+    /// // Optimistic increment: read the value without holding the lock while computing, then retry if it has changed.
+    /// loop {
+    ///     let current = *locker_room.read_cell(0).unwrap();
+    ///     match locker_room.compare_and_swap_cell(0, &current, current + 1) {
+    ///         Ok(()) => break,
+    ///         Err(CompareAndSwapError::Mismatch(_)) => continue,
+    ///         Err(CompareAndSwapError::NoCell(_)) => unreachable!(),
+    ///     }
+    /// }
+    /// assert_eq!(1, *locker_room.read_cell(0).unwrap());
+    /// assert_eq!(Err(CompareAndSwapError::Mismatch(3)), locker_room.compare_and_swap_cell(0, &0, 3));
+    /// assert_eq!(Err(CompareAndSwapError::NoCell(3)), locker_room.compare_and_swap_cell(1, &0, 3));
+    /// ```
+    pub fn compare_and_swap_cell(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        expected: &T::Output,
+        new: T::Output,
+    ) -> Result<(), CompareAndSwapError<T::Output>>
+    where
+        T::Output: PartialEq + Sized,
+    {
+        let Some(mut guard) = self.write_cell(index) else {
+            return Err(CompareAndSwapError::NoCell(new));
+        };
+        if *guard != *expected {
+            return Err(CompareAndSwapError::Mismatch(new));
         }
+        *guard = new;
+        Ok(())
+    }
+
+    /// Locks the cell at the index with exclusive write access and applies `f` to its value.
+    ///
+    /// The lock is released as soon as `f` returns. This function will return `false` if there is no cell with such index.
+    pub fn update_cell(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        f: impl FnOnce(&mut T::Output),
+    ) -> bool {
+        self.modify_cell(index, f).is_some()
+    }
+
+    /// Locks the cell at the index with exclusive write access and applies `f` to its value, returning the result of `f`.
+    ///
+    /// The lock is released as soon as `f` returns. This function will return `None` if there is no cell with such index.
+    pub fn modify_cell<R>(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        f: impl FnOnce(&mut T::Output) -> R,
+    ) -> Option<R> {
+        self.write_cell(index).map(|mut guard| f(&mut guard))
+    }
+
+    /// Locks the cell at the index with exclusive write access and applies `f` to its value, where `f` reports whether it
+    /// changed the value. Returns that report, e.g. to mark the cell as dirty or invalidate caches.
+    ///
+    /// The report isn't checked, the crate trusts `f`. The lock is released as soon as `f` returns.
+    /// This function will return `None` if there is no cell with such index.
+    pub fn write_cell_if(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        f: impl FnOnce(&mut T::Output) -> bool,
+    ) -> Option<bool> {
+        self.modify_cell(index, f)
+    }
+
+    /// Applies `f` to the value of every cell for which `pred` returns `true`, returning the number of such cells.
+    ///
+    /// Whole collection isn't locked: `pred` is called while a cell is locked with shared read access and, if it
+    /// matches, the cell is locked again with exclusive write access for `f`, one cell at a time. So other cells stay
+    /// available, but the cell may be written by someone else in between; use [`write_where_checked`](Self::write_where_checked)
+    /// if `f` must only see values matching `pred`.
+    ///
+    /// Cells are visited by a snapshot of [`indices`](Self::indices), so cells inserted meanwhile are not visited,
+    /// and removed cells are skipped.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3, 4].into();
+    /// assert_eq!(2, locker_room.write_where(|_, v| v % 2 == 0, |v| *v = 0));
+    /// assert_eq!(vec![1, 0, 3, 0], locker_room.into_inner());
+    /// ```
+    pub fn write_where(
+        &'a self,
+        pred: impl Fn(&T::Idx, &T::Output) -> bool,
+        f: impl FnMut(&mut T::Output),
+    ) -> usize {
+        self.write_where_with(pred, f, false)
+    }
+
+    /// Like [`write_where`](Self::write_where), but calls `pred` once more under the write lock and skips the cell
+    /// if it doesn't match anymore.
+    pub fn write_where_checked(
+        &'a self,
+        pred: impl Fn(&T::Idx, &T::Output) -> bool,
+        f: impl FnMut(&mut T::Output),
+    ) -> usize {
+        self.write_where_with(pred, f, true)
+    }
+
+    fn write_where_with(
+        &'a self,
+        pred: impl Fn(&T::Idx, &T::Output) -> bool,
+        mut f: impl FnMut(&mut T::Output),
+        recheck: bool,
+    ) -> usize {
+        let mut written = 0;
+        for index in self.indices() {
+            let matches = self
+                .read_cell(&index)
+                .is_some_and(|guard| pred(&index, &guard));
+            if !matches {
+                continue;
+            }
+            let Some(mut guard) = self.write_cell(&index) else {
+                continue;
+            };
+            if recheck && !pred(&index, &guard) {
+                continue;
+            }
+            f(&mut guard);
+            written += 1;
+        }
+        written
+    }
+
+    /// Exclusively locks whole collection with right access.
+    ///
+    /// No cell locks can be acquired by other threads when locked whole collection. And vice versa, this function
+    /// blocks while any cell guard is alive, so the collection is never changed under a cell reference.
+    /// This applies to the current thread as well: locking a cell while holding the guard deadlocks.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    pub fn lock_room(&'a self) -> RoomGuard<'a, T, S> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!("lock_room").entered();
+        let global_lock_guard = self.global_lock.write();
+        #[cfg(feature = "tracing")]
+        drop(span);
+        #[cfg(feature = "stats")]
+        self.counters.room();
+        let index_locks = unsafe { &mut *self.index_locks.get() };
+        let collection = unsafe { &mut *self.collection.get() };
+        RoomGuard::new(
+            collection,
+            index_locks,
+            global_lock_guard,
+            self.cell_names(),
+        )
+    }
+
+    /// Attempts to exclusively lock whole collection with write access without blocking.
+    ///
+    /// This function will return `None` if any cell or whole collection is locked by someone else.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn try_lock_room(&'a self) -> Option<RoomGuard<'a, T, S>> {
+        let global_lock_guard = self.global_lock.try_write()?;
+        #[cfg(feature = "stats")]
+        self.counters.room();
+        let index_locks = unsafe { &mut *self.index_locks.get() };
+        let collection = unsafe { &mut *self.collection.get() };
+        Some(RoomGuard::new(
+            collection,
+            index_locks,
+            global_lock_guard,
+            self.cell_names(),
+        ))
+    }
+
+    /// Exclusively locks whole collection with write access, blocking the current thread no longer than `timeout`.
+    ///
+    /// This function will return `None` if the lock couldn't be acquired in time, e.g. because cells are held by
+    /// other threads. See [`ShadowLock::try_write_for`] on how the waiting is done.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn lock_room_timeout(&'a self, timeout: Duration) -> Option<RoomGuard<'a, T, S>> {
+        let global_lock_guard = self.global_lock.try_write_for(timeout)?;
+        #[cfg(feature = "stats")]
+        self.counters.room();
+        let index_locks = unsafe { &mut *self.index_locks.get() };
+        let collection = unsafe { &mut *self.collection.get() };
+        Some(RoomGuard::new(
+            collection,
+            index_locks,
+            global_lock_guard,
+            self.cell_names(),
+        ))
+    }
+
+    /// Exclusively locks whole collection for a maintenance window, blocking the current thread until no cell is locked.
+    ///
+    /// This is the same as [`lock_room`](Self::lock_room): no cell can be locked until the returned guard is dropped,
+    /// and other threads can check that with [`is_paused`](Self::is_paused).
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// let guard = locker_room.pause();
+    /// assert!(locker_room.is_paused());
+    /// drop(guard);
+    /// assert!(!locker_room.is_paused());
+    /// ```
+    pub fn pause(&'a self) -> RoomGuard<'a, T, S> {
+        self.lock_room()
+    }
+
+    /// Returns `true` if cells can't be locked right now because whole collection is locked.
+    ///
+    /// It's checked by an attempt to lock whole collection with shared read access, so the result may be outdated once
+    /// the function returns. Locks which prefer writers also refuse readers while [`lock_room`](Self::lock_room)
+    /// waits, so a pending pause is reported as well.
+    pub fn is_paused(&'a self) -> bool {
+        self.global_lock.try_read().is_none()
+    }
+
+    /// Returns the numbers of locks acquired since the `LockerRoom` was created.
+    ///
+    /// Every cell lock taken by [`read_cell`](Self::read_cell), [`write_cell`](Self::write_cell),
+    /// [`write_cells`](Self::write_cells) and the like counts, as does every [`lock_room`](Self::lock_room). Counters
+    /// are updated with relaxed atomics and read one by one, so under concurrent locking the numbers may be from
+    /// slightly different moments. The clone of a `LockerRoom` starts from zero.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2].into();
+    /// *locker_room.write_cell(0).unwrap() += locker_room.get_cell(1).unwrap();
+    /// assert_eq!(1, locker_room.stats().reads);
+    /// assert_eq!(1, locker_room.stats().writes);
+    /// ```
+    #[cfg(any(feature = "stats", doc))]
+    #[doc(cfg(feature = "stats"))]
+    pub fn stats(&'a self) -> LockerRoomStats {
+        self.counters.snapshot()
+    }
+
+    /// Exclusively locks whole collection and calls `f` with shared references to it and to the cell at the index.
+    ///
+    /// No cell can be locked by other threads while `f` runs, as with [`lock_room`](Self::lock_room), but `f` still
+    /// gets the cell as if it was locked with shared read access. The lock of whole collection isn't re-entrant:
+    /// `f` must not lock cells or whole collection of the same `LockerRoom`, because that would wait for the lock
+    /// held by the current thread forever. Everything `f` needs should be read through its arguments.
+    ///
+    /// This function will return `None` without calling `f` if there is no cell with such index.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// let share = locker_room.with_room_and_cell(1, |v, cell| *cell as f64 / v.iter().sum::<i32>() as f64);
+    /// assert_eq!(Some(2. / 6.), share);
+    /// assert_eq!(None, locker_room.with_room_and_cell(3, |_, cell| *cell));
+    /// ```
+    pub fn with_room_and_cell<R>(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+        f: impl FnOnce(&T, &T::Output) -> R,
+    ) -> Option<R> {
+        let guard = self.lock_room();
+        let cell = guard.index(index)?;
+        Some(f(&guard, cell))
+    }
+
+    /// Locks whole collection with shared read access and calls `f` with a [`RoomReadCtx`], whose
+    /// [`read_cell`](RoomReadCtx::read_cell) only locks the cell. Returns the result of `f`.
+    ///
+    /// This saves taking the lock of whole collection for every cell when `f` reads many of them. But
+    /// [`lock_room`](Self::lock_room) waits until `f` returns, and `f` must not lock cells or whole collection
+    /// through the `LockerRoom` itself: with a lock which prefers writers, a concurrent `lock_room` would make that wait forever.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// let sum: i32 = locker_room.read_room_scoped(|room| {
+    ///     (0..3).map(|i| *room.read_cell(i).unwrap()).sum()
+    /// });
+    /// assert_eq!(6, sum);
+    /// ```
+    pub fn read_room_scoped<R>(&'a self, f: impl FnOnce(&RoomReadCtx<'a, T, S>) -> R) -> R {
+        f(&RoomReadCtx::new(self, self.global_lock.read()))
+    }
+
+    // Locks the cell like `read_cell`, but the caller must hold the lock of whole collection.
+    pub(crate) fn read_cell_bare(&'a self, index: &T::Idx) -> Option<BareReadCellGuard<'a, T, S>> {
+        let collection = unsafe { &*self.collection.get() };
+        let hash = collection.hash_index(index);
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock = index_locks.index_hashed(index, hash)?;
+        let index_lock_guard = self.lock_read(index, index_lock);
+        collection
+            .index_hashed(index, hash)
+            .map(|v| BareReadCellGuard::new(v, index_lock_guard))
+    }
+
+    // Checks that there is a cell at the index by its lock, but the caller must hold the lock of whole collection.
+    pub(crate) fn contains_index_unlocked(&self, index: &T::Idx) -> bool {
+        let index_locks = unsafe { &*self.index_locks.get() };
+        index_locks.index(index).is_some()
+    }
+
+    /// Returns `true` if there is a cell at the index, taking the whole collection lock with shared read access only
+    /// while checking.
+    ///
+    /// The cell itself isn't locked or even looked up: the index is found among the shadow locks, which is a length
+    /// comparison for `Vec` and a single probe for maps. Like [`indices`](Self::indices), it's only a snapshot.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = HashMap::from([("a", 1)]).into();
+    /// let guard = locker_room.write_cell("a").unwrap();
+    /// assert!(locker_room.contains_index("a"));
+    /// assert!(!locker_room.contains_index("b"));
+    /// # drop(guard);
+    /// ```
+    pub fn contains_index(&'a self, index: impl Borrow<T::Idx>) -> bool {
+        let _global_lock_guard = self.global_lock.read();
+        self.contains_index_unlocked(index.borrow())
+    }
+
+    /// Calls `f` with a reference to whole collection and returns its result. The lock can't outlive `f`.
+    ///
+    /// Cells are written while whole collection is locked only with shared access, so it's exclusively locked while
+    /// `f` runs, like [`lock_room`](Self::lock_room) does. `f` must not lock cells or whole collection of the same
+    /// `LockerRoom`, because that would wait for the lock held by the current thread forever.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// assert_eq!(6, locker_room.with_ref(|v| v.iter().sum::<i32>()));
+    /// ```
+    pub fn with_ref<R>(&'a self, f: impl FnOnce(&T) -> R) -> R {
+        let _global_lock_guard = self.global_lock.write();
+        f(unsafe { &*self.collection.get() })
+    }
+
+    /// Calls `f` with a mutable reference to whole collection under [`lock_room`](Self::lock_room) and returns its
+    /// result. Shadow locks are updated after `f` returns, so it may insert and remove cells.
+    ///
+    /// `f` must not lock cells or whole collection of the same `LockerRoom`, like in [`with_ref`](Self::with_ref).
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// assert_eq!(4, locker_room.with_mut(|v| { v.push(4); v.len() }));
+    /// assert_eq!(Some(4), locker_room.get_cell(3));
+    /// ```
+    pub fn with_mut<R>(&'a self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.lock_room())
+    }
+
+    /// Returns an iterator over cells which locks every cell with shared read access only when the iterator advances to it.
+    ///
+    /// The iterator goes over a snapshot of [`indices`](Self::indices), so cells inserted by [`lock_room`](Self::lock_room)
+    /// later are not visited, and removed cells are skipped.
+    ///
+    /// In a `for` loop every guard is dropped before the next cell is locked, so at most one cell is locked at a time.
+    /// Keeping earlier guards, e.g. by collecting the iterator, holds the locks of all those cells. Moreover, if other thread
+    /// waits for [`lock_room`](Self::lock_room) meanwhile, locking the next cell may deadlock on a lock which prefers
+    /// writers. Use [`read_cells`](Self::read_cells) to lock several cells at once.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// let mut sum = 0;
+    /// for (index, guard) in locker_room.iter_read() {
+    ///     sum += index * *guard;
+    /// }
+    /// assert_eq!(8, sum);
+    /// ```
+    pub fn iter_read(
+        &'a self,
+    ) -> impl Iterator<Item = (T::Idx, ReadCellGuard<'a, T, S>)> + use<'a, T, S> {
+        self.indices().into_iter().filter_map(move |index| {
+            let guard = self.read_cell(&index)?;
+            Some((index, guard))
+        })
+    }
+
+    /// Returns the lock state of the cell at the index, or `None` if there is no cell with such index.
+    ///
+    /// The state is best-effort and may be outdated once the function returns, see [`ShadowLock::state`]. None of the
+    /// provided locks expose the number of readers, so only the kind of access is reported.
+    /// It is intended for debugging of contention hot spots.
+    pub fn cell_lock_state(&'a self, index: impl Borrow<T::Idx>) -> Option<LockState> {
+        let _global_lock_guard = self.global_lock.read();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        index_locks.index(index).map(ShadowLock::state)
+    }
+
+    /// Returns the indices of all cells, taking the whole collection lock with shared read access only while collecting them.
+    ///
+    /// This is a snapshot: once the function returns, [`lock_room`](Self::lock_room) can add or remove cells,
+    /// so locking a cell with a returned index may still give `None`.
+    pub fn indices(&'a self) -> Vec<T::Idx> {
+        let _global_lock_guard = self.global_lock.read();
+        let collection = unsafe { &*self.collection.get() };
+        collection.indices().collect()
+    }
+
+    /// Checks that the collection and its shadow locks keep their contract: [`Collection::indices`] yields every
+    /// index once, there is a cell and a lock at each of them, and there are as many locks as indices.
+    ///
+    /// It walks whole collection while it's exclusively locked, like [`lock_room`](Self::lock_room) does,
+    /// so it's meant for tests and debugging of custom collections.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// assert_eq!(Ok(()), locker_room.validate());
+    /// ```
+    pub fn validate(&'a self) -> Result<(), CollectionError>
+    where
+        T::Idx: CanonicalOrder,
+    {
+        let _global_lock_guard = self.global_lock.write();
+        let collection = unsafe { &*self.collection.get() };
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let mut indices: Vec<_> = collection.indices().collect();
+        for index in &indices {
+            if collection.index(index).is_none() {
+                return Err(CollectionError::NoCell);
+            }
+            if index_locks.index(index).is_none() {
+                return Err(CollectionError::NoLock);
+            }
+        }
+        let len = indices.len();
+        // Repeats are found in canonical order like in `write_cells`, so `Ord` isn't needed.
+        order::sort_indices(&mut indices);
+        if indices.len() != len {
+            return Err(CollectionError::DuplicateIndex);
+        }
+        if indices.len() != index_locks.len() {
+            return Err(CollectionError::LenMismatch {
+                indices: indices.len(),
+                locks: index_locks.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns all locks to their initial state, given unique access which rules out any guard.
+    ///
+    /// The shadow locks are updated to the indices of the collection in place, and poisoning left by panics
+    /// is cleared from them and from the lock of whole collection, see [`ShadowLock::clear_poison`].
+    /// Neither the collection nor the locks are reallocated.
+    /// ```
+    /// # use std::panic::{self, AssertUnwindSafe};
+    /// # use lockerroom::LockerRoom;
+    /// let mut locker_room: LockerRoom<_> = vec![0, 1].into();
+    /// let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     let _guard = locker_room.write_cell(0).unwrap();
+    ///     panic!();
+    /// }));
+    /// locker_room.reset();
+    /// assert_eq!(0, *locker_room.read_cell(0).unwrap());
+    /// ```
+    pub fn reset(&mut self) {
+        self.global_lock.clear_poison();
+        let collection = self.collection.get_mut();
+        let index_locks = self.index_locks.get_mut();
+        index_locks.update_indices(collection.indices());
+        for index in collection.indices() {
+            if let Some(lock) = index_locks.index(&index) {
+                lock.clear_poison();
+            }
+        }
+    }
+
+    /// Consumes the `Arc` of this `LockerRoom`, returning the underlying data if it's the only strong reference.
+    /// Otherwise the `Arc` is given back.
+    ///
+    /// This is an associated function that needs to be used as `LockerRoom::try_into_inner_arc(arc)`.
+    /// ```
+    /// # use std::{sync::Arc, thread};
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: Arc<LockerRoom<_>> = Arc::new(vec![0, 0].into());
+    /// let handles: Vec<_> = (0..2)
+    ///     .map(|i| {
+    ///         let locker_room = Arc::clone(&locker_room);
+    ///         thread::spawn(move || *locker_room.write_cell(i).unwrap() += 1)
+    ///     })
+    ///     .collect();
+    /// handles.into_iter().for_each(|handle| handle.join().unwrap());
+    ///
+    /// let cloned = Arc::clone(&locker_room);
+    /// let locker_room = LockerRoom::try_into_inner_arc(locker_room).unwrap_err();
+    /// drop(cloned);
+    /// assert_eq!(Some(vec![1, 1]), LockerRoom::try_into_inner_arc(locker_room).ok());
+    /// ```
+    pub fn try_into_inner_arc(arc: Arc<Self>) -> Result<T, Arc<Self>> {
+        Arc::try_unwrap(arc).map(Self::into_inner)
+    }
+}
+
+impl<'a, T, S> LockerRoom<T, S, false>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Locks cell at the index with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell(&'a self, index: impl Borrow<T::Idx>) -> Option<BareReadCellGuard<'a, T, S>> {
+        let index = index.borrow();
+        // Without `lock_room` the shadow locks and the set of cells never change.
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock_guard = self.lock_read(index, index_locks.index(index)?);
+        let collection = unsafe { &*self.collection.get() };
+        collection
+            .index(index)
+            .map(|v| BareReadCellGuard::new(v, index_lock_guard))
+    }
+
+    /// Locks cell at the index with exclusive write access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's exclusive write access once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell(
+        &'a self,
+        index: impl Borrow<T::Idx>,
+    ) -> Option<BareWriteCellGuard<'a, T, S>> {
+        let index = index.borrow();
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let index_lock_guard = self.lock_write(index, index_locks.index(index)?);
+        let collection = unsafe { &mut *self.collection.get() };
+        collection
+            .index_mut(index)
+            .map(|v| BareWriteCellGuard::new(v, index_lock_guard))
+    }
+
+    /// Returns the indices of all cells.
+    pub fn indices(&'a self) -> Vec<T::Idx> {
+        let collection = unsafe { &*self.collection.get() };
+        collection.indices().collect()
+    }
+}
+
+impl<'a, T, S> LockerRoom<T, S>
+where
+    T: MapCollection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Gets the entry of the cell with the key for in-place modification or insertion.
+    ///
+    /// If the cell exists, it is locked with exclusive write access like [`write_cell`](Self::write_cell) does.
+    /// Otherwise nothing is locked until the value is inserted with [`VacantEntry::insert`], which escalates
+    /// to the exclusive lock of whole collection.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = HashMap::from([("a", 1)]).into();
+    /// *locker_room.entry("a").or_insert(0) += 10;
+    /// *locker_room.entry("b").or_insert(0) += 10;
+    /// assert_eq!(11, *locker_room.read_cell("a").unwrap());
+    /// assert_eq!(10, *locker_room.read_cell("b").unwrap());
+    /// ```
+    pub fn entry(&'a self, key: T::Idx) -> Entry<'a, T, S> {
+        match self.write_cell(&key) {
+            Some(guard) => Entry::Occupied(guard),
+            None => Entry::Vacant(VacantEntry::new(self, key)),
+        }
+    }
+
+    /// Locks the cell with the key with exclusive write access, inserting the result of `f` first if there is no such cell.
+    ///
+    /// It's a shorthand for `self.entry(key).or_insert_with(f)`, see [`entry`](Self::entry) on how the cell is locked.
+    /// The presence of the cell is checked again once whole collection is locked, so `f` is only called if no other
+    /// thread inserted the key in between.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<HashMap<&str, Vec<i32>>> = Default::default();
+    /// locker_room.get_or_insert_with("a", Vec::new).push(1);
+    /// locker_room.get_or_insert_with("a", Vec::new).push(2);
+    /// assert_eq!(vec![1, 2], *locker_room.read_cell("a").unwrap());
+    /// ```
+    pub fn get_or_insert_with(
+        &'a self,
+        key: T::Idx,
+        f: impl FnOnce() -> T::Value,
+    ) -> WriteCellGuard<'a, T, S>
+    where
+        T::Idx: Clone,
+    {
+        self.entry(key).or_insert_with(f)
+    }
+}
+
+impl<T, S> LockerRoom<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    /// Creates a scope of threads, like [`thread::scope`](std::thread::scope), in which threads spawned with
+    /// [`ScopedLockerRoom::spawn`] borrow this `LockerRoom`.
+    ///
+    /// All threads spawned in the scope are joined before this function returns. Since they only borrow the `LockerRoom`,
+    /// it doesn't need to be wrapped into an [`Arc`], which saves reference counting for short parallel sections.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![0, 0].into();
+    /// locker_room.scope(|scoped| {
+    ///     for i in 0..4 {
+    ///         scoped.spawn(move |locker_room| *locker_room.write_cell(i % 2).unwrap() += i);
+    ///     }
+    /// });
+    /// assert_eq!(vec![2, 4], locker_room.into_inner());
+    /// ```
+    pub fn scope<'env, R>(
+        &'env self,
+        f: impl for<'scope> FnOnce(&ScopedLockerRoom<'scope, 'env, T, S>) -> R,
+    ) -> R {
+        thread::scope(|scope| f(&ScopedLockerRoom::new(scope, self)))
+    }
+}
+
+impl<'a, T, V, S> LockerRoom<T, S>
+where
+    T: Collection<Idx = usize, Output = V> + AsRef<[V]> + AsMut<[V]>,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    /// Locks the cell locks of the range in ascending order, skipping a lock shared with the previous cell,
+    /// e.g. by a block of [`BlockLocks`].
+    fn lock_range<G>(
+        &self,
+        range: &Range<usize>,
+        mut lock: impl FnMut(&usize, &'a S::Lock) -> G,
+    ) -> Option<Vec<G>> {
+        let index_locks = unsafe { &*self.index_locks.get() };
+        let mut guards = Vec::with_capacity(range.len());
+        let mut previous: Option<&S::Lock> = None;
+        for index in range.clone() {
+            let index_lock = index_locks.index(index)?;
+            if previous.is_some_and(|previous| ptr::eq(previous, index_lock)) {
+                continue;
+            }
+            guards.push(lock(&index, index_lock));
+            previous = Some(index_lock);
+        }
+        Some(guards)
+    }
+
+    /// Locks a contiguous range of cells with shared read access, blocking the current thread until all of them
+    /// can be acquired.
+    ///
+    /// Cells are locked in ascending order of indices like [`read_cells`](Self::read_cells), so overlapping ranges
+    /// can't deadlock each other.
+    ///
+    /// This function will return `None` if the range is out of bounds.
+    ///
+    /// Returns an RAII guard which dereferences to the slice of the range and will release this thread's shared
+    /// access to all the cells once it is dropped.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3, 4].into();
+    /// assert_eq!([2, 3], *locker_room.read_range(1..3).unwrap());
+    /// assert!(locker_room.read_range(2..5).is_none());
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_range(&'a self, range: Range<usize>) -> Option<RangeReadGuard<'a, T, S>> {
+        let global_lock_guard = self.global_lock.read();
+        let index_lock_guards =
+            self.lock_range(&range, |index, lock| self.lock_read(index, lock))?;
+        let values = unsafe { &*self.collection.get() }.as_ref().get(range)?;
+        Some(RangeReadGuard::new(
+            values,
+            global_lock_guard,
+            index_lock_guards,
+        ))
+    }
+
+    /// Locks a contiguous range of cells with exclusive write access, blocking the current thread until all of them
+    /// can be acquired.
+    ///
+    /// Cells are locked in ascending order of indices like [`write_cells`](Self::write_cells), so overlapping ranges
+    /// can't deadlock each other.
+    ///
+    /// This function will return `None` if the range is out of bounds.
+    ///
+    /// Returns an RAII guard which dereferences to the mutable slice of the range and will release this thread's
+    /// exclusive write access to all the cells once it is dropped.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3, 4].into();
+    /// locker_room.write_range(1..3).unwrap().fill(0);
+    /// assert_eq!(vec![1, 0, 0, 4], locker_room.into_inner());
+    /// ```
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_range(&'a self, range: Range<usize>) -> Option<RangeWriteGuard<'a, T, S>> {
+        let global_lock_guard = self.global_lock.read();
+        let index_lock_guards =
+            self.lock_range(&range, |index, lock| self.lock_write(index, lock))?;
+        // The range is locked, so nobody else borrows its cells.
+        let values = unsafe { &mut *self.collection.get() }
+            .as_mut()
+            .get_mut(range)?;
+        Some(RangeWriteGuard::new(
+            values,
+            global_lock_guard,
+            index_lock_guards,
+        ))
+    }
+}
+
+impl<V, S> LockerRoom<Vec<V>, S>
+where
+    S: ShadowLocksCollection<Idx = usize>,
+{
+    /// Divides the cells into two views at an index, like [`slice::split_at_mut`].
+    ///
+    /// The first view has cells with indices `[0, mid)` and the second one `[mid, len)`. Since this function borrows
+    /// the `LockerRoom` mutably, nobody else can lock cells or whole collection until both views are dropped, so the
+    /// views are the only way to the cells and never overlap. It suits fork-join algorithms, which hand each half
+    /// to a thread of [`thread::scope`](std::thread::scope).
+    /// ```
+    /// # use std::thread;
+    /// # use lockerroom::LockerRoom;
+    /// let mut locker_room: LockerRoom<_> = vec![1, 2, 3, 4].into();
+    /// let (left, right) = locker_room.split_at_mut(1);
+    /// thread::scope(|scope| {
+    ///     scope.spawn(|| *left.write_cell(0).unwrap() *= 10);
+    ///     scope.spawn(|| *right.write_cell(2).unwrap() *= 10);
+    /// });
+    /// assert_eq!(vec![10, 2, 3, 40], locker_room.into_inner());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at_mut(
+        &mut self,
+        mid: usize,
+    ) -> (LockerRoomView<'_, Vec<V>, S>, LockerRoomView<'_, Vec<V>, S>) {
+        let len = self.collection.get_mut().len();
+        assert!(mid <= len, "mid > len");
+        let locker_room: &Self = self;
+        (
+            LockerRoomView::new(locker_room, 0..mid),
+            LockerRoomView::new(locker_room, mid..len),
+        )
+    }
+}
+
+impl<'a, V, S> LockerRoom<Vec<V>, S>
+where
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    /// Locks cell at the index with exclusive write access, growing the vector first if it's too short, so it works
+    /// as an auto-growing array. The new cells up to the index are filled with the results of `default`.
+    ///
+    /// An existing cell is locked like [`write_cell`](Self::write_cell). Growing needs to exclusively lock whole
+    /// collection, like [`lock_room`](Self::lock_room) does, so the current thread blocks until all cell guards
+    /// are dropped. Then the lock of whole collection is atomically turned into the lock of the cell.
+    ///
+    /// The lock of whole collection can't be upgraded, so it's released and locked again exclusively in between.
+    /// Other thread may grow the vector in that window, so the length is checked again and `default` is only called
+    /// for the cells still missing.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1].into();
+    /// *locker_room.write_cell_or_grow(3, || 0) = 4;
+    /// assert_eq!(vec![1, 0, 0, 4], locker_room.into_inner());
+    /// ```
+    pub fn write_cell_or_grow(
+        &'a self,
+        index: usize,
+        default: impl FnMut() -> V,
+    ) -> WriteCellGuard<'a, Vec<V>, S> {
+        if let Some(guard) = self.write_cell(index) {
+            return guard;
+        }
+        let mut room_guard = self.lock_room();
+        if room_guard.len() <= index {
+            room_guard.resize_with(index + 1, default);
+        }
+        room_guard
+            .into_write_cell(&index)
+            .expect("cell exists while whole collection is locked")
+    }
+}
+
+impl<V, S> LockerRoom<Vec<V>, S>
+where
+    S: ShadowLocksCollection<Idx = usize>,
+{
+    /// Resizes the vector to `new_len` cells like [`Vec::resize`], filling the new cells with clones of `value`.
+    ///
+    /// Whole collection is locked exclusively, like [`lock_room`](Self::lock_room) does, so the current thread blocks
+    /// until all cell guards are dropped. The shadow locks are resized before whole collection is released, so
+    /// the new cells can be locked as soon as this function returns.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1].into();
+    /// locker_room.resize(3, 0);
+    /// *locker_room.write_cell(2).unwrap() = 3;
+    /// assert_eq!(vec![1, 0, 3], locker_room.into_inner());
+    /// ```
+    pub fn resize(&self, new_len: usize, value: V)
+    where
+        V: Clone,
+    {
+        self.lock_room().resize(new_len, value);
+    }
+
+    /// Shortens the vector to `len` cells like [`Vec::truncate`]. It has no effect if the vector is already shorter.
+    ///
+    /// Whole collection is locked exclusively like in [`resize`](Self::resize), and the shadow locks of the removed
+    /// cells are dropped before it's released.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+    /// locker_room.truncate(1);
+    /// assert!(locker_room.read_cell(1).is_none());
+    /// assert_eq!(vec![1], locker_room.into_inner());
+    /// ```
+    pub fn truncate(&self, len: usize) {
+        self.lock_room().truncate(len);
+    }
+}
+
+impl<V> LockerRoom<Vec<V>> {
+    /// Creates a `LockerRoom` of `len` cells where the value of cell `i` is `f(i)`, like
+    /// [`array::from_fn`](std::array::from_fn).
+    ///
+    /// It collects the vector and converts it into a `LockerRoom`. Creating the values and their shadow locks in
+    /// a single pass turns out slower than these two tight passes, as `cargo bench --bench from_fn` shows.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room = LockerRoom::from_fn(4, |i| i * i);
+    /// assert_eq!(Some(9), locker_room.get_cell(3));
+    /// ```
+    pub fn from_fn(len: usize, f: impl FnMut(usize) -> V) -> Self {
+        (0..len).map(f).collect::<Vec<_>>().into()
+    }
+}
+
+impl<K, V> LockerRoom<HashMap<K, V>>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates a `LockerRoom` of a [`HashMap`] where the value at every key is `f(&key)`. A repeated key takes
+    /// the last value.
+    ///
+    /// Call it as `LockerRoom::<HashMap<_, _>>::from_keys`, since maps of other types have the same function.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room = LockerRoom::<HashMap<_, _>>::from_keys(["a", "bb"], |key| key.len());
+    /// assert_eq!(Some(2), locker_room.get_cell("bb"));
+    /// ```
+    pub fn from_keys(keys: impl IntoIterator<Item = K>, mut f: impl FnMut(&K) -> V) -> Self {
+        keys.into_iter()
+            .map(|key| {
+                let value = f(&key);
+                (key, value)
+            })
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
+}
+
+impl<K, V> LockerRoom<BTreeMap<K, V>>
+where
+    K: Ord + Clone,
+{
+    /// Creates a `LockerRoom` of a [`BTreeMap`] where the value at every key is `f(&key)`. A repeated key takes
+    /// the last value.
+    ///
+    /// Call it as `LockerRoom::<BTreeMap<_, _>>::from_keys`, since maps of other types have the same function.
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room = LockerRoom::<BTreeMap<_, _>>::from_keys(1..4, |key| key * 10);
+    /// assert_eq!(vec![1, 2, 3], locker_room.indices());
+    /// ```
+    pub fn from_keys(keys: impl IntoIterator<Item = K>, mut f: impl FnMut(&K) -> V) -> Self {
+        keys.into_iter()
+            .map(|key| {
+                let value = f(&key);
+                (key, value)
+            })
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    }
+}
+
+impl<T> From<T> for LockerRoom<T>
+where
+    T: Collection,
+{
+    fn from(value: T) -> Self {
+        let index_locks = value.shadow_locks();
+        Self::with_shadow_locks(value, index_locks, None)
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for LockerRoom<HashMap<K, V>>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates a `LockerRoom` from a [`HashMap`] of the key-value pairs, like [`HashMap::from`].
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<HashMap<_, _>> = [("a", 1), ("b", 2)].into();
+    /// assert_eq!(2, *locker_room.read_cell("b").unwrap());
+    /// ```
+    fn from(pairs: [(K, V); N]) -> Self {
+        HashMap::from(pairs).into()
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for LockerRoom<BTreeMap<K, V>>
+where
+    K: Ord + Clone,
+{
+    /// Creates a `LockerRoom` from a [`BTreeMap`] of the key-value pairs, like [`BTreeMap::from`].
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<BTreeMap<_, _>> = [("a", 1), ("b", 2)].into();
+    /// assert_eq!(vec!["a", "b"], locker_room.indices());
+    /// ```
+    fn from(pairs: [(K, V); N]) -> Self {
+        BTreeMap::from(pairs).into()
+    }
+}
+
+impl<T> Default for LockerRoom<T>
+where
+    T: Collection + Default,
+{
+    /// Creates a `LockerRoom` from the `Default` value of the collection.
+    fn default() -> Self {
+        T::default().into()
+    }
+}
+
+impl<T, S> Clone for LockerRoom<T, S>
+where
+    T: Collection + Clone,
+    S: ShadowLocksCollection<Idx = T::Idx> + Default,
+{
+    /// Creates an independent `LockerRoom` with a clone of the collection.
+    ///
+    /// Whole collection is exclusively locked while it's cloned, like [`lock_room`](Self::lock_room) does, so the clone
+    /// never sees a half-written cell. The clone gets its own shadow locks, created like in
+    /// [`LockerRoomBuilder::shadow_locks`], and all of them are unlocked regardless of the state of the source.
+    /// The [`LockObserver`], if any, is shared with the clone.
+    fn clone(&self) -> Self {
+        let global_lock_guard = self.global_lock.write();
+        let collection = unsafe { &*self.collection.get() }.clone();
+        drop(global_lock_guard);
+        let mut index_locks = S::default();
+        index_locks.update_indices(collection.indices());
+        let cloned = Self::with_shadow_locks(collection, index_locks, self.observer.clone());
+        #[cfg(any(feature = "tracing", feature = "deadlock-detect", doc))]
+        let cloned = Self {
+            fmt_index: self.fmt_index,
+            ..cloned
+        };
+        cloned
+    }
+}
+
+impl<T, S> PartialEq for LockerRoom<T, S>
+where
+    T: Collection + PartialEq,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    /// Compares the collections of both `LockerRoom`s.
+    ///
+    /// Cells are written while whole collection is locked only with shared access, so both collections are exclusively
+    /// locked while they're compared, like [`lock_room`](Self::lock_room) does. They're locked in the order of their
+    /// addresses, so `a == b` and `b == a` called concurrently can't deadlock each other.
+    ///
+    /// The result is a momentary snapshot, not a synchronization point: cells may change right after the comparison.
+    /// ```
+    /// # use lockerroom::LockerRoom;
+    /// let a: LockerRoom<_> = vec![0, 1].into();
+    /// let b: LockerRoom<_> = vec![0, 1].into();
+    /// assert!(a == b);
+    /// *b.write_cell(1).unwrap() += 1;
+    /// assert!(a != b);
+    /// ```
+    fn eq(&self, other: &Self) -> bool {
+        let (first, second) = if ptr::from_ref(self) < ptr::from_ref(other) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let _first_guard = first.global_lock.write();
+        let _second_guard = (!ptr::eq(first, second)).then(|| second.global_lock.write());
+        unsafe { *self.collection.get() == *other.collection.get() }
+    }
+}
+
+impl<T, S> Eq for LockerRoom<T, S>
+where
+    T: Collection + Eq,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+}
+
+impl<'a, A, T, S> Extend<A> for &'a LockerRoom<T, S>
+where
+    T: Collection + Extend<A>,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    /// Extends the collection with the contents of the iterator under [`lock_room`](LockerRoom::lock_room).
+    ///
+    /// Shadow locks are updated once, after the whole iterator is consumed.
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use lockerroom::LockerRoom;
+    /// let locker_room: LockerRoom<HashMap<_, _>> = Default::default();
+    /// (&locker_room).extend([("a", 1), ("b", 2)]);
+    /// assert_eq!(Some(2), locker_room.get_cell("b"));
+    /// ```
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        self.lock_room().extend(iter);
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+#[allow(clippy::needless_range_loop)]
+mod test {
+    use std::{
+        borrow::Borrow,
+        collections::{BTreeMap, HashMap, VecDeque},
+        mem,
+        ops::DerefMut,
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            mpsc, Arc, RwLock,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        cmp_by_hash,
+        sync::{
+            ArcReadCellGuard, BareWriteCellGuard, CellLogger, Entry, LockObserver, LockerRoomCells,
+            LockerRoomView, MappedReadCellGuard, ReadCellGuard, RoomGuard, RoomGuardMut,
+            SharedLockerRoom, SharedSlice, WriteCellGuard,
+        },
+        AdaptiveLocks, CanonicalOrder, Collection, CollectionError, CompareAndSwapError, Indexed,
+        IndexedLocks, Jagged, LockState, ReentrantReadLocks, SeqLock, SeqLocks, ShadowLock,
+        ShadowLocksCollection,
+    };
+
+    use super::{LockerRoom, Room, RoomLock};
+
+    #[test]
+    fn t() {
+        let len = 9999;
+        let v: Vec<_> = (0..len).collect();
+        let locker_room: Arc<LockerRoom<_>> = Arc::new(v.into());
+        thread::scope(|scope| {
+            for _ in 0..len {
+                scope.spawn(|| {
+                    for i in 0..len {
+                        *locker_room.write_cell(i).unwrap() += i;
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for i in 0..len {
+                    let mut guard = locker_room.lock_room();
+                    let v = guard.deref_mut();
+                    v.resize(i + len + 1, i);
+                }
+            });
+        });
+        let v = Arc::into_inner(locker_room).unwrap().into_inner();
+        for i in 0..len {
+            assert_eq!(i * (len + 1), v[i]);
+        }
+        for i in 0..len {
+            assert_eq!(i, v[i + len]);
+        }
+    }
+
+    #[test]
+    fn default() {
+        let locker_room: LockerRoom<Vec<usize>> = Default::default();
+        assert!(locker_room.read_cell(0).is_none());
+        locker_room.lock_room().push(1);
+        assert_eq!(1, *locker_room.read_cell(0).unwrap());
+
+        let locker_room: LockerRoom<HashMap<&str, usize>> = Default::default();
+        assert!(locker_room.read_cell("a").is_none());
+        locker_room.lock_room().insert("a", 1);
+        assert_eq!(1, *locker_room.read_cell("a").unwrap());
+
+        let locker_room: LockerRoom<BTreeMap<&str, usize>> = Default::default();
+        assert!(locker_room.read_cell("a").is_none());
+        locker_room.lock_room().insert("a", 1);
+        assert_eq!(1, *locker_room.read_cell("a").unwrap());
+    }
+
+    #[test]
+    fn clone() {
+        let locker_room: LockerRoom<_> = vec![0, 1].into();
+        let cloned = locker_room.clone();
+        *locker_room.write_cell(0).unwrap() += 10;
+        locker_room.lock_room().push(2);
+        let _guard = locker_room.write_cell(1).unwrap();
+        assert_eq!(Some(LockState::Unlocked), cloned.cell_lock_state(1));
+        *cloned.write_cell(1).unwrap() += 10;
+        assert_eq!(vec![0, 11], cloned.into_inner());
+    }
+
+    #[test]
+    fn indices() {
+        let locker_room: LockerRoom<_> = vec![0; 3].into();
+        assert_eq!(vec![0, 1, 2], locker_room.indices());
+        locker_room.lock_room().push(0);
+        assert_eq!(vec![0, 1, 2, 3], locker_room.indices());
+
+        let locker_room: LockerRoom<_> = BTreeMap::from([("a", 0), ("b", 1)]).into();
+        assert_eq!(vec!["a", "b"], locker_room.indices());
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LockerRoom<Vec<i32>>>();
+        assert_send_sync::<LockerRoom<HashMap<&str, Arc<i32>>>>();
+    }
+
+    #[test]
+    fn lock_room_timeout() {
+        fn check<S>(locker_room: LockerRoom<Vec<i32>, S>)
+        where
+            S: ShadowLocksCollection<Idx = usize> + Send + Sync,
+        {
+            let timeout = Duration::from_millis(20);
+            let guard = locker_room.write_cell(0).unwrap();
+            assert!(locker_room.try_lock_room().is_none());
+            let start = Instant::now();
+            assert!(locker_room.lock_room_timeout(timeout).is_none());
+            assert!(start.elapsed() >= timeout);
+            drop(guard);
+
+            let (sender, receiver) = mpsc::channel();
+            thread::scope(|scope| {
+                scope.spawn(|| {
+                    let _guard = locker_room.write_cell(1).unwrap();
+                    sender.send(()).unwrap();
+                    thread::sleep(timeout);
+                });
+                receiver.recv().unwrap();
+                assert!(locker_room.try_lock_room().is_none());
+                locker_room
+                    .lock_room_timeout(Duration::from_secs(10))
+                    .unwrap()
+                    .push(2);
+            });
+            assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+            locker_room.try_lock_room().unwrap().push(3);
+            assert_eq!(vec![0, 1, 2, 3], locker_room.into_inner());
+        }
+
+        check(LockerRoom::from(vec![0, 1]));
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<ReentrantReadLocks>()
+                .build(),
+        );
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<AdaptiveLocks>()
+                .build(),
+        );
+        #[cfg(feature = "parking_lot")]
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<Vec<parking_lot::RwLock<()>>>()
+                .build(),
+        );
+    }
+
+    #[test]
+    fn try_cell_for() {
+        fn check<S>(locker_room: LockerRoom<Vec<i32>, S>)
+        where
+            S: ShadowLocksCollection<Idx = usize>,
+        {
+            let timeout = Duration::from_millis(20);
+            let guard = locker_room.write_cell(0).unwrap();
+            let start = Instant::now();
+            assert!(locker_room.try_read_cell_for(0, timeout).is_none());
+            assert!(locker_room.try_write_cell_for(0, timeout).is_none());
+            assert!(start.elapsed() >= timeout * 2);
+            *locker_room.try_write_cell_for(1, timeout).unwrap() += 1;
+            drop(guard);
+            assert!(locker_room.try_read_cell_for(2, timeout).is_none());
+            assert_eq!(0, *locker_room.try_read_cell_for(0, timeout).unwrap());
+            assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+            let room = locker_room.lock_room();
+            assert!(locker_room.try_read_cell_for(1, timeout).is_none());
+            drop(room);
+            assert_eq!(2, *locker_room.try_read_cell_for(1, timeout).unwrap());
+        }
+
+        check(LockerRoom::from(vec![0, 1]));
+        #[cfg(feature = "parking_lot")]
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<Vec<parking_lot::RwLock<()>>>()
+                .build(),
+        );
+    }
+
+    #[cfg(feature = "parking_lot")]
+    #[test]
+    fn try_cell_for_budget() {
+        // parking_lot makes readers wait for a queued writer, so the room writer holds the lock of whole collection
+        // back until it gives up.
+        let locker_room = LockerRoom::builder(vec![0, 1])
+            .shadow_locks::<Vec<parking_lot::RwLock<()>>>()
+            .build();
+        let timeout = Duration::from_millis(200);
+        let room_timeout = Duration::from_millis(100);
+        thread::scope(|scope| {
+            let guard = locker_room.write_cell(0).unwrap();
+            scope.spawn(|| assert!(locker_room.lock_room_timeout(room_timeout).is_none()));
+            thread::sleep(Duration::from_millis(20));
+            let start = Instant::now();
+            // The cell is waited for only as long as is left after the collection.
+            assert!(locker_room.try_read_cell_for(0, timeout).is_none());
+            let elapsed = start.elapsed();
+            assert!(
+                elapsed >= timeout && elapsed < timeout + room_timeout - Duration::from_millis(30)
+            );
+            drop(guard);
+        });
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+        assert!(locker_room.try_lock_room().is_some());
+    }
+
+    #[test]
+    fn cell_lock_state() {
+        fn check<S>(locker_room: LockerRoom<Vec<i32>, S>)
+        where
+            S: ShadowLocksCollection<Idx = usize>,
+        {
+            assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+            let read_guard = locker_room.read_cell(0).unwrap();
+            let write_guard = locker_room.write_cell(1).unwrap();
+            assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(0));
+            assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(1));
+            assert_eq!(None, locker_room.cell_lock_state(2));
+            drop((read_guard, write_guard));
+            assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(1));
+        }
+
+        check(LockerRoom::from(vec![0, 1]));
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<ReentrantReadLocks>()
+                .build(),
+        );
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<AdaptiveLocks>()
+                .build(),
+        );
+        #[cfg(feature = "parking_lot")]
+        check(
+            LockerRoom::builder(vec![0, 1])
+                .shadow_locks::<Vec<parking_lot::RwLock<()>>>()
+                .build(),
+        );
+    }
+
+    #[test]
+    fn iter_read() {
+        let locker_room: LockerRoom<_> = BTreeMap::from([("a", 0), ("b", 1), ("c", 2)]).into();
+        let mut iter = locker_room.iter_read();
+        assert_eq!(
+            Some(("a", 0)),
+            iter.next().map(|(index, guard)| (index, *guard))
+        );
+        locker_room.lock_room().remove("b");
+        locker_room.lock_room().insert("d", 3);
+        assert_eq!(
+            Some(("c", 2)),
+            iter.next().map(|(index, guard)| (index, *guard))
+        );
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn map_guards() {
+        let locker_room: LockerRoom<_> = vec![String::from("hello"), String::from("world")].into();
+        let guard = ReadCellGuard::map(locker_room.read_cell(0).unwrap(), String::as_str);
+        let guard = MappedReadCellGuard::map(guard, |s| &s[1..]);
+        assert_eq!("ello", &*guard);
+        assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(0));
+        drop(guard);
+
+        let guard = locker_room.write_cell(1).unwrap();
+        let Err(guard) = WriteCellGuard::try_map(guard, |s| s.get_mut(10..)) else {
+            panic!("string is shorter than 10 bytes");
+        };
+        let Ok(mut guard) = WriteCellGuard::try_map(guard, |s| s.get_mut(1..)) else {
+            panic!("string is longer than 1 byte");
+        };
+        guard.make_ascii_uppercase();
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(1));
+        drop(guard);
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(1));
+        assert_eq!("wORLD", *locker_room.read_cell(1).unwrap());
+    }
+
+    #[test]
+    fn multi_cells() {
+        let locker_room: LockerRoom<_> = vec![0, 1, 2, 3].into();
+        {
+            let mut guard = locker_room.write_cells([3, 1, 3]).unwrap();
+            assert_eq!(2, guard.len());
+            assert!(guard.indices().eq(&[1, 3]));
+            for (i, v) in guard.iter_mut() {
+                *v += i;
+            }
+            *guard.get_mut(1).unwrap() += 1;
+            assert!(guard.get(0).is_none());
+        }
+        let guard = locker_room.read_cells([0, 1, 2, 3]).unwrap();
+        let cells: Vec<_> = guard.iter().map(|(i, v)| (*i, *v)).collect();
+        assert_eq!(vec![(0, 0), (1, 3), (2, 2), (3, 6)], cells);
+        drop(guard);
+        assert!(locker_room.write_cells([0, 4]).is_none());
+        assert!(locker_room.read_cells([4]).is_none());
+
+        let locker_room = Arc::new(locker_room);
+        thread::scope(|scope| {
+            for _ in 0..100 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let mut guard = locker_room.write_cells([0, 3]).unwrap();
+                        *guard.get_mut(0).unwrap() += 1;
+                        *guard.get_mut(3).unwrap() += 1;
+                    }
+                });
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let mut guard = locker_room.write_cells([3, 0]).unwrap();
+                        *guard.get_mut(3).unwrap() += 2;
+                        *guard.get_mut(0).unwrap() += 2;
+                    }
+                });
+            }
+        });
+        assert_eq!(30000, *locker_room.read_cell(0).unwrap());
+        assert_eq!(30006, *locker_room.read_cell(3).unwrap());
+    }
+
+    #[test]
+    fn swap_cells() {
+        let locker_room: LockerRoom<_> = vec![0, 1, 2].into();
+        assert!(locker_room.swap_cells(2, 0));
+        assert!(locker_room.swap_cells(1, 1));
+        assert!(!locker_room.swap_cells(1, 3));
+        assert!(!locker_room.swap_cells(3, 3));
+        assert_eq!(vec![2, 1, 0], locker_room.into_inner());
+    }
+
+    #[test]
+    fn write_cell_if() {
+        let locker_room: LockerRoom<_> = vec![1, 5].into();
+        let clamp = |v: &mut i32| {
+            let changed = *v > 3;
+            *v = (*v).min(3);
+            changed
+        };
+        assert_eq!(Some(false), locker_room.write_cell_if(0, clamp));
+        assert_eq!(Some(true), locker_room.write_cell_if(1, clamp));
+        assert_eq!(None, locker_room.write_cell_if(2, clamp));
+        assert_eq!(vec![1, 3], locker_room.into_inner());
+    }
+
+    #[test]
+    fn compare_and_swap_cell() {
+        let locker_room: LockerRoom<_> = vec![0].into();
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        let mut current = *locker_room.read_cell(0).unwrap();
+                        while let Err(err) =
+                            locker_room.compare_and_swap_cell(0, &current, current + 1)
+                        {
+                            assert!(matches!(err, CompareAndSwapError::Mismatch(_)));
+                            current = *locker_room.read_cell(0).unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(800, *locker_room.read_cell(0).unwrap());
+    }
+
+    #[test]
+    fn update_cell() {
+        let locker_room: LockerRoom<_> = vec![0, 1].into();
+        assert!(locker_room.update_cell(1, |v| *v += 1));
+        assert!(!locker_room.update_cell(2, |v| *v += 1));
+        assert_eq!(Some(4), locker_room.modify_cell(1, |v| *v * 2));
+        assert_eq!(None, locker_room.modify_cell(2, |v| *v * 2));
+        assert_eq!(vec![0, 2], locker_room.into_inner());
+    }
+
+    #[test]
+    fn get_set_cell() {
+        let locker_room: LockerRoom<_> = vec![(0, 0); 4].into();
+        thread::scope(|scope| {
+            for i in 1..=4 {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for j in 0..1000 {
+                        assert!(locker_room.set_cell(j % 4, (i * j, i * j)));
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for j in 0..4000 {
+                    let (a, b) = locker_room.get_cell(j % 4).unwrap();
+                    assert_eq!(a, b);
+                }
+            });
+        });
+        assert!(!locker_room.set_cell(4, (1, 1)));
+        assert_eq!(None, locker_room.get_cell(4));
+        // No guard outlives the calls.
+        assert!(locker_room.try_lock_room().is_some());
+    }
+
+    #[test]
+    fn pause() {
+        let locker_room: &LockerRoom<_> = &vec![1, 2].into();
+        assert!(!locker_room.is_paused());
+        let guard = locker_room.read_cell(0).unwrap();
+        assert!(!locker_room.is_paused());
+        drop(guard);
+
+        let (paused_tx, paused_rx) = mpsc::channel();
+        let (resume_tx, resume_rx) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                let _guard = locker_room.pause();
+                paused_tx.send(()).unwrap();
+                resume_rx.recv().unwrap();
+            });
+            paused_rx.recv().unwrap();
+            assert!(locker_room.is_paused());
+            assert_eq!(None, locker_room.try_lock_room().map(|_| ()));
+            resume_tx.send(()).unwrap();
+        });
+        assert!(!locker_room.is_paused());
+        assert_eq!(
+            Some(4),
+            locker_room.with_room_and_cell(1, |v, cell| v.len() * *cell)
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn array2() {
+        const SIZE: usize = 8;
+        // White cells of the checkerboard are ones, and every black cell gets the number of its white neighbors.
+        let grid = ndarray::Array2::from_shape_fn((SIZE, SIZE), |(i, j)| (i + j) % 2);
+        let locker_room: LockerRoom<_> = grid.into();
+        thread::scope(|scope| {
+            for i in 0..SIZE {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for j in (i % 2..SIZE).step_by(2) {
+                        let mut cell = locker_room.write_cell((i, j)).unwrap();
+                        let neighbors = [
+                            (i.wrapping_sub(1), j),
+                            (i + 1, j),
+                            (i, j.wrapping_sub(1)),
+                            (i, j + 1),
+                        ];
+                        *cell = neighbors
+                            .into_iter()
+                            .filter_map(|index| locker_room.get_cell(index))
+                            .sum();
+                    }
+                });
+            }
+        });
+        assert_eq!(None, locker_room.read_cell((0, SIZE)).map(|_| ()));
+        let grid = locker_room.into_inner();
+        assert_eq!(2, grid[(0, 0)]);
+        assert_eq!(3, grid[(0, 2)]);
+        assert_eq!(4, grid[(2, 2)]);
+        assert_eq!(1, grid[(2, 3)]);
+
+        let locker_room: LockerRoom<_> = ndarray::Array2::<usize>::zeros((2, 3)).into();
+        *locker_room.lock_room() = ndarray::Array2::ones((3, 2));
+        assert_eq!(Some(1), locker_room.get_cell((2, 1)));
+        assert_eq!(None, locker_room.get_cell((0, 2)));
+    }
+
+    #[test]
+    fn indexed() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct RowId(usize);
+        impl From<usize> for RowId {
+            fn from(id: usize) -> Self {
+                Self(id)
+            }
+        }
+        impl From<RowId> for usize {
+            fn from(id: RowId) -> Self {
+                id.0
+            }
+        }
+
+        let locker_room: LockerRoom<Indexed<_, RowId>> = Indexed::new(vec![0; 4]).into();
+        thread::scope(|scope| {
+            for id in 0..4 {
+                let locker_room = &locker_room;
+                scope.spawn(move || *locker_room.write_cell(RowId(id)).unwrap() = id * 10);
+            }
+        });
+        assert_eq!(None, locker_room.get_cell(RowId(4)));
+        locker_room.lock_room().truncate(2);
+        assert_eq!(2, unsafe { &*locker_room.index_locks.get() }.len());
+        assert_eq!(vec![RowId(0), RowId(1)], locker_room.indices());
+        assert!(locker_room.read_cell(RowId(2)).is_none());
+
+        let locker_room = LockerRoom::builder(Indexed::<_, RowId>::new(vec![1, 2]))
+            .shadow_locks::<IndexedLocks<AdaptiveLocks, RowId>>()
+            .build();
+        *locker_room.write_cell(RowId(0)).unwrap() += 1;
+        assert_eq!(vec![2, 2], locker_room.into_inner().into_inner());
+    }
+
+    #[test]
+    fn jagged() {
+        let rows = vec![vec![0; 3], vec![], vec![0; 1], vec![0; 4]];
+        let locker_room: LockerRoom<_> = Jagged(rows).into();
+        thread::scope(|scope| {
+            for column in 0..4 {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for row in 0..4 {
+                        if let Some(mut cell) = locker_room.write_cell((row, column)) {
+                            *cell = row * 10 + column;
+                        }
+                    }
+                });
+            }
+        });
+        let mut indices: Vec<_> = locker_room.indices();
+        indices.sort();
+        assert_eq!(
+            vec![
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (2, 0),
+                (3, 0),
+                (3, 1),
+                (3, 2),
+                (3, 3)
+            ],
+            indices
+        );
+        assert_eq!(None, locker_room.get_cell((0, 3)));
+        assert_eq!(None, locker_room.get_cell((1, 0)));
+        assert_eq!(None, locker_room.get_cell((4, 0)));
+
+        {
+            let mut room = locker_room.lock_room();
+            room.0[1].push(7);
+            room.0[3].truncate(2);
+        }
+        assert_eq!(Some(7), locker_room.get_cell((1, 0)));
+        assert_eq!(Some(31), locker_room.get_cell((3, 1)));
+        assert_eq!(None, locker_room.get_cell((3, 2)));
+        assert_eq!(
+            vec![vec![0, 1, 2], vec![7], vec![20], vec![30, 31]],
+            locker_room.into_inner().0
+        );
+    }
+
+    #[cfg(feature = "slab")]
+    #[test]
+    fn slab() {
+        let mut slab = slab::Slab::new();
+        let a = slab.insert("a");
+        let b = slab.insert("b");
+        let locker_room: LockerRoom<_> = slab.into();
+
+        let mut guard = locker_room.lock_room();
+        guard.remove(a);
+        let c = guard.insert("c");
+        let d = guard.insert("d");
+        drop(guard);
+
+        let mut indices = locker_room.indices();
+        indices.sort();
+        assert_eq!(vec![a, b, d], indices);
+        assert_eq!(a, c);
+        assert_eq!(Some("c"), locker_room.get_cell(c));
+        assert!(locker_room.set_cell(d, "e"));
+        let mut guard = locker_room.lock_room();
+        guard.remove(b);
+        drop(guard);
+        assert_eq!(None, locker_room.get_cell(b));
+        assert!(locker_room.write_cells([c, d]).is_some());
+        assert_eq!("e", locker_room.into_inner()[d]);
+    }
+
+    #[test]
+    fn cells() {
+        let locker_room: LockerRoomCells<_> = LockerRoom::from(vec![0, 1]).into();
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        *locker_room.write_cell(0).unwrap() += 1;
+                        let _guard = locker_room.read_cell(1).unwrap();
+                    }
+                });
+            }
+        });
+        assert!(locker_room.read_cell(2).is_none());
+        assert_eq!(vec![0, 1], locker_room.indices());
+        assert_eq!(vec![400, 1], locker_room.into_inner());
+        assert!(
+            mem::size_of::<BareWriteCellGuard<Vec<i32>>>()
+                < mem::size_of::<WriteCellGuard<Vec<i32>>>()
+        );
+    }
+
+    #[test]
+    fn no_room() {
+        assert_eq!(
+            0,
+            mem::size_of::<<Room<false> as RoomLock<RwLock<()>>>::Lock>()
+        );
+        assert!(
+            mem::size_of::<LockerRoom<Vec<i32>, Vec<RwLock<()>>, false>>()
+                < mem::size_of::<LockerRoom<Vec<i32>>>()
+        );
+
+        let locker_room: LockerRoom<_, _, false> = LockerRoom::from(vec![0, 1]).into();
+        let guard = locker_room.write_cell(1).unwrap();
+        // Other cells are locked as usual while one is held.
+        assert_eq!(0, *locker_room.read_cell(0).unwrap());
+        let (sender, receiver) = mpsc::channel();
+        let r = &locker_room;
+        thread::scope(|scope| {
+            scope.spawn(move || sender.send(*r.read_cell(1).unwrap()).unwrap());
+            assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+            let mut guard = guard;
+            *guard += 1;
+            drop(guard);
+            assert_eq!(2, receiver.recv().unwrap());
+        });
+        assert_eq!((vec![0, 2], 2), {
+            let (v, locks) = locker_room.into_parts();
+            (v, locks.len())
+        });
+    }
+
+    #[test]
+    fn cells_from_locker_room() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<LockerRoomCells<Vec<i32>>>();
+        assert_sync::<LockerRoomCells<HashMap<String, i32>>>();
+
+        let locker_room = LockerRoom::with_block_size(vec![0; 4], 2);
+        locker_room.lock_room().extend([0; 2]);
+        let locker_room: LockerRoomCells<_, _> = locker_room.into();
+        *locker_room.write_cell(5).unwrap() += 1;
+        // The block of cells 4 and 5 shares a lock, as it did before the conversion.
+        let (sender, receiver) = mpsc::channel();
+        let r = &locker_room;
+        thread::scope(|scope| {
+            let guard = locker_room.write_cell(4).unwrap();
+            scope.spawn(move || sender.send(*r.read_cell(5).unwrap()).unwrap());
+            assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+            drop(guard);
+            assert_eq!(1, receiver.recv().unwrap());
+        });
+        assert_eq!(vec![0, 0, 0, 0, 0, 1], locker_room.into_inner());
+    }
+
+    #[test]
+    fn split_at_mut() {
+        // Doubles every cell, splitting the view in halves for new threads down to single cells.
+        fn double(view: LockerRoomView<'_, Vec<usize>>) {
+            if view.len() == 1 {
+                *view.write_cell(0).unwrap() *= 2;
+                return;
+            }
+            let mid = view.len() / 2;
+            let (left, right) = view.split_at(mid);
+            thread::scope(|scope| {
+                scope.spawn(|| double(left));
+                double(right);
+            });
+        }
+
+        let mut locker_room: LockerRoom<_> = (0..100).collect::<Vec<_>>().into();
+        let (left, right) = locker_room.split_at_mut(40);
+        assert_eq!((40, 0..40), (left.len(), left.range()));
+        assert_eq!((60, 40..100), (right.len(), right.range()));
+        assert!(left.read_cell(40).is_none());
+        assert_eq!(40, *right.read_cell(0).unwrap());
+        double(left);
+        double(right);
+        let v = locker_room.into_inner();
+        assert!(v.iter().enumerate().all(|(i, value)| *value == 2 * i));
+
+        let mut locker_room: LockerRoom<Vec<usize>> = Default::default();
+        let (left, right) = locker_room.split_at_mut(0);
+        assert!(left.is_empty() && right.is_empty());
+    }
+
+    #[test]
+    fn scope() {
+        let locker_room: LockerRoom<_> = vec![0; 4].into();
+        let sums = locker_room.scope(|scoped| {
+            let handles: Vec<_> = (0..4)
+                .map(|i| {
+                    scoped.spawn(move |locker_room| {
+                        for j in 0..100 {
+                            *locker_room.write_cell((i + j) % 4).unwrap() += 1;
+                        }
+                        i
+                    })
+                })
+                .collect();
+            scoped.thread_scope().spawn(|| ());
+            *scoped.write_cell(0).unwrap() += 1;
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum::<usize>()
+        });
+        assert_eq!(6, sums);
+        assert_eq!(vec![101, 100, 100, 100], locker_room.into_inner());
+
+        // The same works with `thread::scope` directly.
+        let locker_room: LockerRoom<_> = vec![0].into();
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| *locker_room.write_cell(0).unwrap() += 1);
+            }
+        });
+        assert_eq!(4, *locker_room.read_cell(0).unwrap());
+    }
+
+    #[test]
+    fn adaptive_locks() {
+        // Without spins the lock falls back to blocking right away.
+        fn check<const SPINS: u32>(locker_room: LockerRoom<Vec<i32>, AdaptiveLocks<SPINS>>) {
+            thread::scope(|scope| {
+                for _ in 0..4 {
+                    scope.spawn(|| {
+                        for i in 0..1000 {
+                            *locker_room.write_cell(i % 2).unwrap() += 1;
+                            assert!(*locker_room.read_cell(i % 2).unwrap() > 0);
+                        }
+                    });
+                }
+                let guard = locker_room.write_cell(0).unwrap();
+                let reader = scope.spawn(|| *locker_room.read_cell(0).unwrap());
+                thread::sleep(Duration::from_millis(20));
+                drop(guard);
+                assert!(reader.join().unwrap() > 0);
+            });
+            assert_eq!(vec![2000, 2000], locker_room.into_inner());
+        }
+
+        check::<0>(
+            LockerRoom::builder(vec![0, 0])
+                .shadow_locks::<AdaptiveLocks<0>>()
+                .build(),
+        );
+        check::<100>(
+            LockerRoom::builder(vec![0, 0])
+                .shadow_locks::<AdaptiveLocks>()
+                .build(),
+        );
+        // The spins chosen at runtime override the default of the type, also for the locks of new cells.
+        let locker_room = LockerRoom::builder(vec![0, 0]).adaptive(0).build();
+        locker_room.lock_room().push(0);
+        let index_locks = unsafe { &*locker_room.index_locks.get() };
+        assert!((0..3).all(|index| index_locks.index(index).unwrap().spins() == 0));
+        assert_eq!(100, AdaptiveLocks::<100>::default().spins());
+        locker_room.lock_room().pop();
+        check(locker_room);
+    }
+
+    #[test]
+    fn reentrant_read() {
+        // With the default locks the writer queued after the first guard would make the second one wait forever.
+        fn sum(locker_room: &LockerRoom<Vec<i32>, ReentrantReadLocks>, depth: usize) -> i32 {
+            let guard = locker_room.read_cell(0).unwrap();
+            *guard
+                + if depth == 0 {
+                    0
+                } else {
+                    sum(locker_room, depth - 1)
+                }
+        }
+
+        let locker_room = LockerRoom::builder(vec![1, 2])
+            .shadow_locks::<ReentrantReadLocks>()
+            .build();
+        thread::scope(|scope| {
+            let guard = locker_room.read_cell(0).unwrap();
+            let writer = scope.spawn(|| *locker_room.write_cell(0).unwrap() += 10);
+            let room_writer = scope.spawn(|| locker_room.lock_room().push(3));
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(4, sum(&locker_room, 3));
+            assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(0));
+            drop(guard);
+            writer.join().unwrap();
+            room_writer.join().unwrap();
+        });
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+        assert_eq!(vec![11, 2, 3], locker_room.into_inner());
+    }
+
+    #[test]
+    fn seq_lock() {
+        let locker_room = LockerRoom::builder(vec![(0u64, 0u64); 2])
+            .shadow_locks::<SeqLocks>()
+            .build();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for i in 1..=10_000 {
+                    *locker_room.write_cell(1).unwrap() = (i, i);
+                }
+                done.store(true, Ordering::Release);
+            });
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Acquire) {
+                        let (a, b) = locker_room.get_cell_seq(1).unwrap();
+                        assert_eq!(a, b, "torn read");
+                    }
+                });
+            }
+        });
+        assert_eq!(Some((10_000, 10_000)), locker_room.get_cell_seq(1));
+        assert_eq!(Some((0, 0)), locker_room.get_cell_seq(0));
+        let guard = locker_room.write_cell(0).unwrap();
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(0));
+        drop(guard);
+
+        let lock = SeqLock::default();
+        let guard = SeqLock::downgrade(lock.write());
+        assert_eq!(LockState::Read, lock.state());
+        assert_eq!(0, lock.read_with(|| 0));
+        drop(guard);
+        assert_eq!(LockState::Unlocked, lock.state());
+    }
+
+    #[test]
+    fn room_cells() {
+        let locker_room: LockerRoom<HashMap<usize, usize>> =
+            HashMap::from([(0, 0), (1, 10), (2, 20)]).into();
+        {
+            let mut room = locker_room.lock_room();
+            room.cells_mut().for_each(|(index, value)| *value += index);
+            let mut cells: Vec<_> = room.cells().map(|(index, value)| (index, *value)).collect();
+            cells.sort();
+            assert_eq!(vec![(0, 0), (1, 11), (2, 22)], cells);
+        }
+        assert_eq!(Some(22), locker_room.get_cell(2));
+    }
+
+    #[test]
+    fn room_cell() {
+        let locker_room: LockerRoom<_> = BTreeMap::from([("a", 1), ("b", 2)]).into();
+        {
+            let mut room = locker_room.lock_room();
+            *room.write_cell_mut("a").unwrap() += room.read_cell("b").copied().unwrap();
+            assert!(room.write_cell_mut("c").is_none());
+            room.insert("c", 0);
+            // Cells added through the guard are reachable before the shadow locks follow.
+            *room.reborrow().write_cell_mut("c").unwrap() += 1;
+            assert_eq!(Some(&1), room.reborrow().read_cell("c"));
+        }
+        assert_eq!(Some(3), locker_room.get_cell("a"));
+        assert_eq!(Some(1), locker_room.get_cell("c"));
+    }
+
+    #[test]
+    fn write_cells_panic() {
+        static ARMED: AtomicBool = AtomicBool::new(false);
+
+        // Comparison with 1 panics once armed. Sorting `[0, 2]` doesn't compare with it, but looking 2 up does.
+        #[derive(Debug, Clone)]
+        struct Key(usize);
+
+        impl Ord for Key {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                if ARMED.load(Ordering::Relaxed) && (self.0 == 1 || other.0 == 1) {
+                    panic!("comparison with 1");
+                }
+                self.0.cmp(&other.0)
+            }
+        }
+
+        impl PartialOrd for Key {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl PartialEq for Key {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other).is_eq()
+            }
+        }
+
+        impl Eq for Key {}
+
+        let locker_room: LockerRoom<_> =
+            BTreeMap::from([(Key(0), 0), (Key(1), 1), (Key(2), 2)]).into();
+        ARMED.store(true, Ordering::Relaxed);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            locker_room.write_cells([Key(0), Key(2)]).is_some()
+        }));
+        ARMED.store(false, Ordering::Relaxed);
+        assert!(result.is_err());
+        assert_eq!(
+            Some(LockState::Unlocked),
+            locker_room.cell_lock_state(Key(0))
+        );
+        assert!(locker_room.try_lock_room().is_some());
+    }
+
+    #[test]
+    fn try_write_cells() {
+        let locker_room: LockerRoom<_> = vec![0, 1, 2, 3].into();
+        let blocker = locker_room.read_cell(2).unwrap();
+        // Cells 0 and 1 are locked before 2 is found held and must be released.
+        assert!(locker_room.try_write_cells([3, 0, 2, 1]).is_none());
+        for index in [0, 1, 3] {
+            assert_eq!(
+                Some(LockState::Unlocked),
+                locker_room.cell_lock_state(index)
+            );
+        }
+        drop(blocker);
+        assert!(locker_room.try_write_cells([0, 4]).is_none());
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+        {
+            let mut guard = locker_room.try_write_cells([3, 1, 3]).unwrap();
+            assert_eq!(2, guard.len());
+            *guard.get_mut(3).unwrap() += 1;
+            assert!(locker_room.try_write_cells([1]).is_none());
+        }
+        let room_guard = locker_room.lock_room();
+        assert!(locker_room.try_write_cells([0]).is_none());
+        drop(room_guard);
+        assert_eq!(vec![0, 1, 2, 4], locker_room.into_inner());
+
+        // Cells 0 and 1 share a lock, which is tried once.
+        let locker_room = LockerRoom::with_block_size(vec![0; 4], 2);
+        let mut guard = locker_room.try_write_cells([1, 0]).unwrap();
+        *guard.get_mut(1).unwrap() += 1;
+        assert!(locker_room.try_write_cells([0, 2]).is_none());
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(2));
+        drop(guard);
+        assert_eq!(2, locker_room.try_write_cells([2, 3]).unwrap().len());
+        assert_eq!(vec![0, 1, 0, 0], locker_room.into_inner());
+    }
+
+    #[test]
+    fn canonical_order() {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        struct Key(&'static str);
+
+        impl CanonicalOrder for Key {
+            fn canonical_cmp(&self, other: &Self) -> std::cmp::Ordering {
+                cmp_by_hash(self, other)
+            }
+        }
+
+        let keys = [Key("a"), Key("b"), Key("c")];
+        let locker_room: LockerRoom<_> = keys
+            .iter()
+            .cloned()
+            .map(|key| (key, 0))
+            .collect::<HashMap<_, _>>()
+            .into();
+        thread::scope(|scope| {
+            for reversed in [false, true] {
+                let locker_room = &locker_room;
+                let mut keys = keys.to_vec();
+                if reversed {
+                    keys.reverse();
+                }
+                scope.spawn(move || {
+                    for _ in 0..1000 {
+                        let mut cells = locker_room.write_cells(keys.iter().cloned()).unwrap();
+                        for key in &keys {
+                            *cells.get_mut(key).unwrap() += 1;
+                        }
+                    }
+                });
+            }
+        });
+        let cells = locker_room
+            .read_cells([Key("c"), Key("a"), Key("c")])
+            .unwrap();
+        assert_eq!(2, cells.len());
+        assert_eq!(Some(&2000), cells.get(Key("a")));
+        assert_eq!(None, cells.get(Key("b")));
+        drop(cells);
+        assert_eq!(Ok(()), locker_room.validate());
+    }
+
+    #[test]
+    fn read_cell_arc() {
+        let locker_room = SharedLockerRoom::new(vec![String::from("a"), String::from("b")]);
+        let guard = locker_room.read_cell_arc(1).unwrap();
+        assert!(locker_room.read_cell_arc(2).is_none());
+        let cloned = ArcReadCellGuard::locker_room(&guard);
+        drop(locker_room);
+        drop(cloned);
+        // The guard keeps the room alive and the cell locked on its own.
+        let locker_room = ArcReadCellGuard::locker_room(&guard);
+        assert_eq!("b", guard.as_str());
+        assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(1));
+        assert!(locker_room.try_lock_room().is_none());
+        drop(guard);
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(1));
+        assert!(locker_room.try_into_inner().is_ok());
+    }
+
+    #[test]
+    fn drain() {
+        let locker_room: LockerRoom<_> = vec![1, 2, 3].into();
+        {
+            let mut room = locker_room.lock_room();
+            let mut cells = RoomGuard::drain(&mut room);
+            assert_eq!(Some((0, 1)), cells.next());
+            drop(cells);
+            assert!(room.is_empty());
+            room.push(4);
+        }
+        assert_eq!(vec![0], locker_room.indices());
+        assert_eq!(Some(4), locker_room.get_cell(0));
+
+        let locker_room: LockerRoom<_> = BTreeMap::from([("a", 1), ("b", 2)]).into();
+        let cells: Vec<_> = RoomGuard::drain(&mut locker_room.lock_room()).collect();
+        assert_eq!(vec![("a", 1), ("b", 2)], cells);
+        assert_eq!(None, locker_room.get_cell("a"));
+        assert!(locker_room.into_inner().is_empty());
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        let locker_room: LockerRoom<_> = vec![0; 4].into();
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for i in 0..100 {
+                        drop(locker_room.read_cell(i % 4));
+                        *locker_room.write_cell(i % 4).unwrap() += 1;
+                    }
+                });
+            }
+        });
+        drop(locker_room.read_cells([0, 1, 2]));
+        drop(locker_room.try_write_cells([1, 3]));
+        drop(locker_room.read_range(0..4));
+        drop(locker_room.lock_room());
+        drop(locker_room.try_lock_room());
+        drop(locker_room.try_read_cell_for(0, Duration::ZERO));
+        {
+            let _guard = locker_room.write_cell(1).unwrap();
+            assert!(locker_room.try_write_cell_for(1, Duration::ZERO).is_none());
+            assert!(locker_room.try_read_cell_for(1, Duration::ZERO).is_none());
+        }
+        // A missing cell locks nothing.
+        assert!(locker_room.read_cell(4).is_none());
+        assert!(locker_room.try_read_cell_for(4, Duration::ZERO).is_none());
+        assert_eq!(
+            crate::sync::LockerRoomStats {
+                reads: 400 + 3 + 4 + 1,
+                writes: 400 + 2 + 1,
+                rooms: 2,
+                timeouts: 2,
+            },
+            locker_room.stats()
+        );
+        assert_eq!(vec![100; 4], locker_room.into_inner());
+    }
+
+    #[test]
+    fn write_cell_logged() {
+        #[derive(Default)]
+        struct Log(std::sync::Mutex<Vec<(usize, usize)>>);
+
+        impl CellLogger<usize, usize> for Log {
+            fn log(&self, index: &usize, value: &usize) {
+                self.0.lock().unwrap().push((*index, *value));
+            }
+        }
+
+        let log = Log::default();
+        let locker_room: LockerRoom<_> = vec![0; 2].into();
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for i in 0..100 {
+                        *locker_room.write_cell_logged(i % 2, &log).unwrap() += 1;
+                    }
+                });
+            }
+        });
+        assert!(locker_room.write_cell_logged(&2, &log).is_none());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = locker_room.write_cell_logged(0, &log).unwrap();
+            panic!("unfinished write");
+        }));
+        assert!(result.is_err());
+
+        // Records of every cell go in the order of its writes.
+        let log = log.0.into_inner().unwrap();
+        for index in 0..2 {
+            let values: Vec<_> = log
+                .iter()
+                .filter(|(logged, _)| *logged == index)
+                .map(|(_, value)| *value)
+                .collect();
+            assert_eq!((1..=200).collect::<Vec<_>>(), values);
+        }
+    }
+
+    #[test]
+    fn leak() {
+        let locker_room: LockerRoom<_> = vec![0, 1].into();
+        let mut guard = locker_room.write_cell(0).unwrap();
+        *guard += 2;
+        let token = WriteCellGuard::leak(guard);
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(0));
+        assert!(locker_room.try_lock_room().is_none());
+        // Other cells are unaffected.
+        *locker_room.write_cell(1).unwrap() += 2;
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let reader = &locker_room;
+            scope.spawn(move || sender.send(*reader.read_cell(0).unwrap()).unwrap());
+            assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+            locker_room.unlock_cell(token);
+            assert_eq!(2, receiver.recv().unwrap());
+        });
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+        assert_eq!(vec![2, 3], *locker_room.lock_room());
+    }
+
+    #[test]
+    fn eq() {
+        let a: LockerRoom<_> = vec![0, 1].into();
+        let b: LockerRoom<_> = vec![0, 1].into();
+        assert!(a == a);
+        assert!(a == b);
+        *b.write_cell(1).unwrap() += 1;
+        assert!(a != b);
+        let nan: LockerRoom<_> = vec![f64::NAN].into();
+        assert!(nan != nan);
+
+        // Opposite comparisons lock the rooms in the same order.
+        *b.write_cell(1).unwrap() -= 1;
+        thread::scope(|scope| {
+            scope.spawn(|| (0..1000).for_each(|_| assert!(a == b)));
+            scope.spawn(|| (0..1000).for_each(|_| assert!(b == a)));
+        });
+    }
+
+    #[test]
+    fn extend() {
+        let locker_room: LockerRoom<HashMap<usize, usize>> = HashMap::from([(0, 0)]).into();
+        (&locker_room).extend((1..100).map(|i| (i, i)));
+        *locker_room.write_cell(99).unwrap() += 1;
+        assert_eq!(Some(100), locker_room.get_cell(99));
+        assert_eq!(100, locker_room.indices().len());
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(50));
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let locker_room: LockerRoom<_> = vec![0; 1000].into();
+        {
+            let mut room = locker_room.lock_room();
+            room.truncate(10);
+            room.shrink_to_fit();
+            assert_eq!(10, room.capacity());
+        }
+        assert_eq!(10, unsafe { &*locker_room.index_locks.get() }.capacity());
+        *locker_room.write_cell(9).unwrap() += 1;
+        assert!(locker_room.write_cell(10).is_none());
+    }
+
+    #[test]
+    fn block_locks() {
+        let locker_room = LockerRoom::with_block_size(vec![0; 10], 4);
+        let guard = locker_room.write_cell(1).unwrap();
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(3));
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(4));
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let writer = &locker_room;
+            scope.spawn(move || {
+                *writer.write_cell(2).unwrap() += 1;
+                sender.send(()).unwrap();
+            });
+            // Cells of other blocks are unaffected.
+            *locker_room.write_cell(9).unwrap() += 1;
+            assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+            drop(guard);
+            receiver.recv().unwrap();
+        });
+        assert!(locker_room.read_cell(10).is_none());
+
+        // Cells 2 and 3 share a lock, which is locked once by the batch guards.
+        let cells = locker_room.write_cells([2, 3]).unwrap();
+        assert_eq!(2, cells.len());
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(0));
+        drop(cells);
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(2));
+        assert_eq!(2, locker_room.write_cells([3, 4]).unwrap().len());
+        let cells = locker_room.read_cells([0, 1, 2, 4]).unwrap();
+        assert_eq!(
+            vec![0, 0, 1, 0],
+            cells.iter().map(|(_, v)| *v).collect::<Vec<_>>()
+        );
+        drop(cells);
+        assert_eq!(10, locker_room.lock_all_read().len());
+        let mut cells = locker_room.lock_all_write();
+        assert_eq!(10, cells.len());
+        *cells.get_mut(3).unwrap() += 1;
+        drop(cells);
+
+        locker_room.lock_room().truncate(5);
+        assert!(locker_room.read_cell(5).is_none());
+        assert_eq!(vec![0, 0, 1, 1, 0], locker_room.into_inner());
+    }
+
+    #[test]
+    fn builder_strategies() {
+        let observer = Arc::new(Counter::default());
+        let locker_room = LockerRoom::builder(vec![0; 6])
+            .observer(observer.clone())
+            .block(3)
+            .build();
+        let guard = locker_room.write_cell(0).unwrap();
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(2));
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(3));
+        drop(guard);
+        assert_eq!(1, observer.writes.load(Ordering::Relaxed));
+
+        // The observer survives switching back to a lock per cell.
+        let locker_room = LockerRoom::builder(vec![0; 6])
+            .observer(observer.clone())
+            .block(3)
+            .per_cell()
+            .build();
+        let guard = locker_room.write_cell(0).unwrap();
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(1));
+        drop(guard);
+        assert_eq!(2, observer.writes.load(Ordering::Relaxed));
+
+        let locker_room = LockerRoom::builder(HashMap::from([("a", 1)]))
+            .shadow_locks::<BTreeMap<_, RwLock<()>>>()
+            .build();
+        assert_eq!(Some(1), locker_room.get_cell("a"));
+        assert_eq!(Ok(()), locker_room.validate());
+    }
+
+    #[test]
+    fn wait_write_cell() {
+        let locker_room: LockerRoom<_> = vec![0; 2].into();
+        thread::scope(|scope| {
+            for i in 0..2 {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    // Every consumer takes the value once the producer has put it.
+                    for round in 1..=100 {
+                        let mut guard = locker_room
+                            .wait_write_cell(i, |value| *value == round)
+                            .unwrap();
+                        *guard = -round;
+                        drop(guard);
+                        locker_room.notify_cell(i);
+                    }
+                });
+            }
+            for round in 1..=100 {
+                for i in 0..2 {
+                    let mut guard = locker_room
+                        .wait_write_cell(i, |value| *value == 1 - round)
+                        .unwrap();
+                    *guard = round;
+                    drop(guard);
+                    locker_room.notify_cell(i);
+                }
+            }
+        });
+        assert_eq!(vec![-100, -100], locker_room.lock_room().clone());
+        assert!(locker_room.wait_write_cell(2, |_| true).is_none());
+    }
+
+    #[test]
+    fn notify_cell() {
+        let locker_room: LockerRoom<_> = vec![0; 2].into();
+        let checks = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let guard = locker_room.wait_write_cell(0, |value| {
+                    checks.fetch_add(1, Ordering::SeqCst);
+                    *value == 1
+                });
+                assert_eq!(1, *guard.unwrap());
+            });
+            while checks.load(Ordering::SeqCst) == 0 {
+                thread::yield_now();
+            }
+            // The waiter is registered before its first check, so it would be woken by now.
+            *locker_room.write_cell(1).unwrap() = 1;
+            locker_room.notify_cell(1);
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(1, checks.load(Ordering::SeqCst));
+            *locker_room.write_cell(0).unwrap() = 1;
+            locker_room.notify_cell(0);
+        });
+        assert_eq!(2, checks.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn into_cells() {
+        let locker_room: LockerRoom<_> = vec![0; 2].into();
+        let mut room = locker_room.lock_room();
+        room.push(0);
+        let mut cells = RoomGuard::into_cells(room, [2, 0, 2]).unwrap();
+        assert_eq!(2, cells.len());
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(2));
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(1));
+        assert!(locker_room.try_lock_room().is_none());
+        *cells.get_mut(2).unwrap() = 2;
+        drop(cells);
+        assert_eq!(Some(2), locker_room.get_cell(2));
+
+        let room = locker_room.lock_room();
+        assert!(RoomGuard::into_cells(room, [0, 3]).is_none());
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+        assert!(locker_room.try_lock_room().is_some());
+
+        // Cells 4 and 5 share a lock, which is locked once.
+        let locker_room = LockerRoom::with_block_size(vec![0; 8], 4);
+        let mut cells = RoomGuard::into_cells(locker_room.lock_room(), [5, 0, 4]).unwrap();
+        assert_eq!(3, cells.len());
+        cells.iter_mut().for_each(|(index, value)| *value = *index);
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(6));
+        drop(cells);
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(4));
+        assert_eq!(vec![0, 0, 0, 0, 4, 5, 0, 0], locker_room.into_inner());
+    }
+
+    #[test]
+    fn borrowed_keys() {
+        let locker_room: LockerRoom<_> =
+            BTreeMap::from([(String::from("a"), 1), (String::from("b"), 2)]).into();
+        *locker_room.write_cell_borrowed("a").unwrap() += 10;
+        assert_eq!(11, *locker_room.read_cell_borrowed("a").unwrap());
+        assert!(locker_room.read_cell_borrowed("c").is_none());
+
+        let locker_room: LockerRoom<_> = HashMap::from([(String::from("a"), 1)]).into();
+        let guard = locker_room.write_cell_borrowed("a").unwrap();
+        assert_eq!(
+            Some(LockState::Write),
+            locker_room.cell_lock_state(String::from("a"))
+        );
+        drop(guard);
+        assert!(locker_room.write_cell_borrowed("b").is_none());
+    }
+
+    #[test]
+    fn reborrow() {
+        fn insert(mut room: RoomGuardMut<'_, BTreeMap<i32, i32>>, key: i32) {
+            room.insert(key, key);
+            // Helpers can pass the room further.
+            room.reborrow()
+                .cells_mut()
+                .for_each(|(_, value)| *value += 1);
+        }
+
+        let locker_room: LockerRoom<_> = BTreeMap::from([(0, 0)]).into();
+        let mut room = locker_room.lock_room();
+        insert(room.reborrow(), 1);
+        insert(room.reborrow(), 2);
+        room.reborrow().shrink_to_fit();
+        assert_eq!(
+            vec![(0, &2), (1, &3), (2, &3)],
+            room.cells().collect::<Vec<_>>()
+        );
+        drop(room);
+        assert_eq!(Some(3), locker_room.get_cell(2));
+    }
+
+    // A vector whose indices also yield `extra` once more.
+    struct Extra {
+        values: Vec<i32>,
+        extra: usize,
+    }
+
+    impl Collection for Extra {
+        type Idx = usize;
+        type Output = i32;
+        type ShadowLocks = Vec<RwLock<()>>;
+        #[cfg(feature = "async")]
+        type ShadowLocksAsync = Vec<tokio::sync::RwLock<()>>;
+
+        fn index(&self, index: impl Borrow<usize>) -> Option<&i32> {
+            self.values.get(*index.borrow())
+        }
+
+        fn index_mut(&mut self, index: impl Borrow<usize>) -> Option<&mut i32> {
+            self.values.get_mut(*index.borrow())
+        }
+
+        fn indices(&self) -> impl Iterator<Item = usize> {
+            (0..self.values.len()).chain([self.extra])
+        }
+
+        fn shadow_locks(&self) -> Self::ShadowLocks {
+            self.indices().map(|_| RwLock::new(())).collect()
+        }
+
+        #[cfg(feature = "async")]
+        fn shadow_locks_async(&self) -> Self::ShadowLocksAsync {
+            self.indices()
+                .map(|_| tokio::sync::RwLock::new(()))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let locker_room: LockerRoom<_> = BTreeMap::from([("a", 1), ("b", 2)]).into();
+        assert_eq!(Ok(()), locker_room.validate());
+        let locker_room: LockerRoom<_> = Extra {
+            values: vec![1, 2],
+            extra: 0,
+        }
+        .into();
+        assert_eq!(Err(CollectionError::DuplicateIndex), locker_room.validate());
+        let locker_room: LockerRoom<_> = Extra {
+            values: vec![1, 2],
+            extra: 2,
+        }
+        .into();
+        assert_eq!(Err(CollectionError::NoCell), locker_room.validate());
+    }
+
+    #[test]
+    fn write_cell_or_grow() {
+        let locker_room: LockerRoom<Vec<usize>> = Default::default();
+        let defaults = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for i in 0..8 {
+                let (locker_room, defaults) = (&locker_room, &defaults);
+                scope.spawn(move || {
+                    for index in (i..100).step_by(8) {
+                        let default = || {
+                            defaults.fetch_add(1, Ordering::Relaxed);
+                            0
+                        };
+                        *locker_room.write_cell_or_grow(index, default) += index;
+                    }
+                });
+            }
+        });
+        // Every cell is created once, no matter which thread grew the vector.
+        assert_eq!(100, defaults.load(Ordering::Relaxed));
+        assert_eq!((0..100).collect::<Vec<_>>(), locker_room.into_inner());
+    }
+
+    #[test]
+    fn ranges() {
+        const ITERATIONS: usize = 1000;
+        let locker_room: LockerRoom<_> = vec![0; 10].into();
+        assert!(locker_room.write_range(8..11).is_none());
+        assert!(locker_room.read_range(11..11).is_none());
+        assert_eq!(0, locker_room.read_range(10..10).unwrap().len());
+        thread::scope(|scope| {
+            // Every range overlaps with its neighbours.
+            for start in [0, 2, 4, 6] {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for _ in 0..ITERATIONS {
+                        let mut guard = locker_room.write_range(start..start + 4).unwrap();
+                        guard.iter_mut().for_each(|value| *value += 1);
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..ITERATIONS {
+                    let guard = locker_room.read_range(2..4).unwrap();
+                    // Cells 2 and 3 are always written together.
+                    assert_eq!(guard[0], guard[1]);
+                }
+            });
+        });
+        let n = ITERATIONS;
+        assert_eq!(
+            vec![n, n, 2 * n, 2 * n, 2 * n, 2 * n, 2 * n, 2 * n, n, n],
+            locker_room.into_inner()
+        );
+
+        // Cells of the same block share a lock, which is locked once.
+        let locker_room = LockerRoom::with_block_size(vec![0; 10], 4);
+        locker_room.write_range(2..7).unwrap().fill(1);
+        assert_eq!(2, locker_room.read_range(5..9).unwrap().iter().sum::<i32>());
+    }
+
+    #[cfg(feature = "spin")]
+    #[test]
+    fn spin() {
+        let locker_room = LockerRoom::builder(vec![0; 4])
+            .shadow_locks::<Vec<spin::RwLock<()>>>()
+            .build();
+        thread::scope(|scope| {
+            for i in 0..8 {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for _ in 0..1000 {
+                        *locker_room.write_cell(i % 4).unwrap() += 1;
+                        assert!(*locker_room.read_cell((i + 1) % 4).unwrap() <= 2000);
+                    }
+                });
+            }
+            scope.spawn(|| locker_room.lock_room().iter_mut().for_each(|v| *v += 1));
+        });
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(0));
+        let guard = locker_room.read_cell(0).unwrap();
+        assert_eq!(Some(LockState::Read), locker_room.cell_lock_state(0));
+        drop(guard);
+        assert_eq!(vec![2001; 4], locker_room.into_inner());
+    }
+
+    #[test]
+    fn with_ref_mut() {
+        let locker_room: LockerRoom<_> = vec![0; 4].into();
+        thread::scope(|scope| {
+            for i in 0..4 {
+                let locker_room = &locker_room;
+                scope.spawn(move || {
+                    for _ in 0..100 {
+                        *locker_room.write_cell(i).unwrap() += 1;
+                        // The whole collection is never seen in the middle of a write.
+                        assert!(locker_room.with_ref(|v| v.iter().all(|v| *v <= 100)));
+                    }
+                });
+            }
+        });
+        assert_eq!(400, locker_room.with_ref(|v| v.iter().sum::<i32>()));
+        locker_room.with_mut(|v| v.push(0));
+        *locker_room.write_cell(4).unwrap() += 1;
+        assert_eq!(vec![100, 100, 100, 100, 1], locker_room.into_inner());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "shadow locks must have a lock for every index")]
+    fn inconsistent_shadow_locks() {
+        let _ = LockerRoom::<_>::with_shadow_locks(vec![0; 3], Vec::<RwLock<()>>::new(), None);
+    }
+
+    #[test]
+    fn parts() {
+        let locker_room: LockerRoom<_> = vec![0, 1, 2].into();
+        *locker_room.write_cell(1).unwrap() += 10;
+        let (v, shadow_locks) = locker_room.into_parts();
+        assert_eq!(vec![0, 11, 2], v);
+        assert_eq!(3, shadow_locks.len());
+
+        let locker_room = LockerRoom::from_parts(v, shadow_locks);
+        assert_eq!(Ok(()), locker_room.validate());
+        let guard = locker_room.write_cell(1).unwrap();
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(1));
+        assert_eq!(Some(LockState::Unlocked), locker_room.cell_lock_state(2));
+        drop(guard);
+        assert_eq!(vec![0, 11, 2], locker_room.into_inner());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "shadow locks must have a lock for every index")]
+    fn from_parts_checks_len() {
+        let (_, shadow_locks) = LockerRoom::from(vec![0; 2]).into_parts();
+        let _ = LockerRoom::from_parts(vec![0; 3], shadow_locks);
+    }
+
+    #[test]
+    fn reset() {
+        let mut locker_room: LockerRoom<_> = vec![0, 1, 2].into();
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = locker_room.write_cell(1).unwrap();
+            panic!("poisons the lock of cell 1");
+        }));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _room = locker_room.lock_room();
+            panic!("poisons the lock of whole collection");
+        }));
+        assert!(unsafe { &*locker_room.index_locks.get() }[1].is_poisoned());
+        assert!(locker_room.global_lock.is_poisoned());
+
+        locker_room.reset();
+        assert!(!unsafe { &*locker_room.index_locks.get() }[1].is_poisoned());
+        assert!(!locker_room.global_lock.is_poisoned());
+        *locker_room.write_cell(1).unwrap() += 10;
+        assert_eq!(vec![0, 11, 2], locker_room.into_inner());
+    }
+
+    #[test]
+    fn write_where() {
+        let locker_room: LockerRoom<_> = vec![1, 2, 3, 4, 5].into();
+        assert_eq!(2, locker_room.write_where(|&i, _| i >= 3, |v| *v *= 10));
+        assert_eq!(0, locker_room.write_where(|_, &v| v > 100, |v| *v = 0));
+        assert_eq!(vec![1, 2, 3, 40, 50], locker_room.into_inner());
+
+        // The cell stops matching between the predicate under the read lock and the one under the write lock.
+        let locker_room: LockerRoom<_> = vec![0].into();
+        let calls = AtomicUsize::new(0);
+        let pred = |_: &usize, _: &i32| calls.fetch_add(1, Ordering::Relaxed).is_multiple_of(2);
+        assert_eq!(0, locker_room.write_where_checked(pred, |v| *v = 1));
+        assert_eq!(1, locker_room.write_where(pred, |v| *v = 2));
+        assert_eq!(3, calls.load(Ordering::Relaxed));
+        assert_eq!(vec![2], locker_room.into_inner());
+    }
+
+    #[test]
+    fn cell_expect() {
+        let locker_room: LockerRoom<_> = HashMap::from([("a", 1)]).into();
+        *locker_room.write_cell_expect("a") += 1;
+        assert_eq!(2, *locker_room.read_cell_expect("a"));
+    }
+
+    #[test]
+    #[should_panic(expected = "no cell at index \"b\"")]
+    fn read_cell_expect_missing() {
+        let locker_room: LockerRoom<_> = HashMap::from([("a", 1)]).into();
+        let _ = locker_room.read_cell_expect("b");
+    }
+
+    #[test]
+    #[should_panic(expected = "no cell at index 3")]
+    fn write_cell_expect_missing() {
+        let locker_room: LockerRoom<_> = vec![0; 3].into();
+        let _ = locker_room.write_cell_expect(3);
+    }
+
+    #[test]
+    fn project() {
+        let locker_room: LockerRoom<_> = (0..8).collect::<Vec<i32>>().into();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            for index in [1, 3, 5] {
+                let (locker_room, done) = (&locker_room, &done);
+                scope.spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        *locker_room.write_cell(index).unwrap() += 1;
+                    }
+                });
+            }
+            for _ in 0..100 {
+                assert_eq!(vec![6, 0, 2], locker_room.project([6, 0, 2, 8]));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        let locker_room: LockerRoom<_> = BTreeMap::from([(1, "a"), (2, "b"), (3, "c")]).into();
+        let projected = locker_room.project([3, 1]);
+        assert_eq!(BTreeMap::from([(1, "a"), (3, "c")]), projected);
+        assert!(LockerRoom::from(projected).read_cell(2).is_none());
+    }
+
+    #[test]
+    fn read_room_scoped() {
+        let locker_room: LockerRoom<_> = HashMap::from([("a", 1), ("b", 2)]).into();
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            let sum = locker_room.read_room_scoped(|room| {
+                let a = room.read_cell("a").unwrap();
+                scope.spawn(|| {
+                    *locker_room.write_cell("b").unwrap() += 10;
+                    sender.send(()).unwrap();
+                });
+                receiver.recv().unwrap();
+                assert!(locker_room.try_lock_room().is_none());
+                assert!(room.read_cell("c").is_none());
+                *a + *room.read_cell("b").unwrap()
+            });
+            assert_eq!(13, sum);
+        });
+        locker_room.lock_room().insert("c", 3);
+        assert_eq!(Some(3), locker_room.get_cell("c"));
+    }
+
+    #[test]
+    fn deque_ends() {
+        let locker_room: LockerRoom<_> = VecDeque::from([1, 2]).into();
+        let mut room = locker_room.lock_room();
+        room.push_front(0);
+        room.push_back(3);
+        room.push_back(4);
+        assert_eq!(Some(0), room.pop_front());
+        assert_eq!(Some(4), room.pop_back());
+        room.push_front(-1);
+        drop(room);
+        *locker_room.write_cell(0).unwrap() -= 1;
+        *locker_room.write_cell(3).unwrap() += 1;
+        assert!(locker_room.read_cell(4).is_none());
+        let mut room = locker_room.lock_room();
+        assert_eq!(vec![-2, 1, 2, 4], room.iter().copied().collect::<Vec<_>>());
+        while room.pop_back().is_some() {}
+        assert_eq!(None, room.pop_front());
+        drop(room);
+        assert!(locker_room.read_cell(0).is_none());
+    }
+
+    #[test]
+    fn lock_all() {
+        let locker_room: LockerRoom<_> = BTreeMap::from([("a", 1), ("b", 2), ("c", 3)]).into();
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        for (_, value) in locker_room.lock_all_write().iter_mut() {
+                            *value += 1;
+                        }
+                        let cells = locker_room.lock_all_read();
+                        assert_eq!(cells.get("a").unwrap() + 1, *cells.get("b").unwrap());
+                    }
+                });
+            }
+        });
+        let cells = locker_room.lock_all_read();
+        assert_eq!(3, cells.len());
+        assert_eq!(Some(&403), cells.get("c"));
+        assert_eq!(Some(LockState::Read), locker_room.cell_lock_state("a"));
+        assert!(locker_room.try_lock_room().is_none());
+        drop(cells);
+        let room = locker_room.lock_room();
+        assert_eq!(
+            vec![401, 402, 403],
+            room.values().copied().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn contains_index() {
+        let locker_room: LockerRoom<_> = HashMap::from([("a", 1)]).into();
+        let guard = locker_room.write_cell("a").unwrap();
+        assert!(locker_room.contains_index("a"));
+        assert!(!locker_room.contains_index("b"));
+        assert!(locker_room.read_room_scoped(|room| room.contains("a") && !room.contains("b")));
+        drop(guard);
+        locker_room.lock_room().insert("b", 2);
+        assert!(locker_room.contains_index("b"));
+
+        let locker_room: LockerRoom<_> = vec![1, 2].into();
+        assert!(locker_room.read_room_scoped(|room| room.contains(1) && !room.contains(2)));
+    }
+
+    #[test]
+    fn boxed_slice() {
+        let locker_room: LockerRoom<Box<[i32]>> = LockerRoom::from(vec![1, 2].into_boxed_slice());
+        *locker_room.write_cell(1).unwrap() += 1;
+        assert!(locker_room.read_cell(2).is_none());
+        *locker_room.lock_room() = vec![0; 3].into_boxed_slice();
+        *locker_room.write_cell(2).unwrap() += 1;
+        assert_eq!(&[0, 0, 1][..], &*locker_room.into_inner());
+
+        let slice: SharedSlice<_> = LockerRoom::from(vec![0u64; 8].into_boxed_slice()).into();
+        thread::scope(|scope| {
+            for start in 0..4 {
+                let slice = &slice;
+                scope.spawn(move || {
+                    for i in 0..1000 {
+                        *slice.write_cell((start + i) % 8).unwrap() += 1;
+                        assert!(*slice.read_cell((start + i + 1) % 8).unwrap() <= 1000);
+                    }
+                });
+            }
+        });
+        assert!(slice.read_cell(8).is_none());
+        assert_eq!(4000, slice.into_inner().iter().sum::<u64>());
+    }
+
+    #[test]
+    fn write_cell_with_previous() {
+        let locker_room: LockerRoom<_> = vec![String::from("a")].into();
+        let (previous, mut cell) = locker_room.write_cell_with_previous(0).unwrap();
+        cell.push('b');
+        assert_eq!(("a", "ab"), (previous.as_str(), cell.as_str()));
+        assert_eq!(Some(LockState::Write), locker_room.cell_lock_state(0));
+        drop(cell);
+        assert!(locker_room.write_cell_with_previous(1).is_none());
+        assert_eq!(
+            Some(String::from("ab")),
+            locker_room
+                .write_cell_with_previous(0)
+                .map(|(previous, _)| previous)
+        );
+    }
+
+    #[test]
+    fn lock_room_waits_for_guards() {
+        let locker_room: LockerRoom<_> = vec![0; 10].into();
+        let mut write_guard = locker_room.write_cell(5).unwrap();
+        let read_guard = locker_room.read_cell(9).unwrap();
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let resizer = &locker_room;
+            scope.spawn(move || {
+                resizer.lock_room().truncate(1);
+                sender.send(()).unwrap();
+            });
+            assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+            assert!(locker_room.try_lock_room().is_none());
+            // The references are still valid, since the collection wasn't truncated.
+            *write_guard += 1;
+            drop(write_guard);
+            assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+            assert_eq!(0, *read_guard);
+            drop(read_guard);
+            receiver.recv().unwrap();
+        });
+        assert_eq!(vec![0], locker_room.into_inner());
+    }
+
+    #[test]
+    fn custom_hasher() {
+        type Hasher = std::hash::BuildHasherDefault<std::hash::DefaultHasher>;
+        let mut map: HashMap<&str, usize, Hasher> = HashMap::default();
+        map.insert("a", 1);
+        // The shadow locks use the same hasher.
+        let locker_room: LockerRoom<_, HashMap<&str, RwLock<()>, Hasher>> = map.into();
+        *locker_room.write_cell("a").unwrap() += 1;
+        locker_room.lock_room().insert("b", 3);
+        *locker_room.entry("c").or_insert(0) += 4;
+        assert_eq!(Some(3), locker_room.get_cell("b"));
+        let mut cells: Vec<_> = locker_room.into_inner().into_iter().collect();
+        cells.sort();
+        assert_eq!(vec![("a", 2), ("b", 3), ("c", 4)], cells);
+    }
+
+    // Counts hashed keys, so the test can tell how many times an access hashes the index.
+    #[cfg(feature = "hashbrown")]
+    #[derive(Clone, Default)]
+    struct CountingState(Arc<AtomicUsize>);
+
+    #[cfg(feature = "hashbrown")]
+    impl std::hash::BuildHasher for CountingState {
+        type Hasher = std::hash::DefaultHasher;
+
+        fn build_hasher(&self) -> Self::Hasher {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Default::default()
+        }
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn hashbrown_hashes_once() {
+        let state = CountingState::default();
+        let mut map = hashbrown::HashMap::with_hasher(state.clone());
+        map.extend([("a", 1), ("b", 2)]);
+        let locker_room: LockerRoom<_> = map.into();
+        state.0.store(0, Ordering::Relaxed);
+        assert_eq!(1, *locker_room.read_cell("a").unwrap());
+        assert_eq!(1, state.0.swap(0, Ordering::Relaxed));
+        *locker_room.write_cell("b").unwrap() += 1;
+        assert_eq!(1, state.0.swap(0, Ordering::Relaxed));
+        // A missing key is looked up once more, in case the shadow locks have another hasher.
+        assert!(locker_room.read_cell("c").is_none());
+        assert_eq!(2, state.0.swap(0, Ordering::Relaxed));
+        assert_eq!(Some(3), locker_room.get_cell("b"));
+
+        // Shadow locks with another hasher don't find the lock by the hash of the collection, but still find it.
+        let map = hashbrown::HashMap::<_, _, std::hash::RandomState>::from_iter([("a", 1)]);
+        let locker_room = LockerRoom::builder(map)
+            .shadow_locks::<hashbrown::HashMap<_, RwLock<()>, std::hash::RandomState>>()
+            .build();
+        *locker_room.write_cell("a").unwrap() += 1;
+        assert_eq!(2, *locker_room.read_cell("a").unwrap());
+        assert!(locker_room.read_cell("b").is_none());
+    }
+
+    #[test]
+    fn entry() {
+        let locker_room: LockerRoom<BTreeMap<usize, usize>> = Default::default();
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for i in 0..100 {
+                        *locker_room.entry(i % 10).or_insert(0) += 1;
+                    }
+                });
+            }
+        });
+        assert_eq!((0..10).collect::<Vec<_>>(), locker_room.indices());
+        assert!((0..10).all(|i| *locker_room.read_cell(i).unwrap() == 80));
+
+        let Entry::Vacant(entry) = locker_room.entry(10) else {
+            panic!("cell 10 doesn't exist");
+        };
+        locker_room.lock_room().insert(10, 5);
+        assert_eq!(5, *entry.insert(1));
+        assert!(matches!(locker_room.entry(10), Entry::Occupied(_)));
+    }
+
+    #[test]
+    fn builder() {
+        let locker_room = LockerRoom::builder(BTreeMap::from([(1, 1), (3, 3)]))
+            .shadow_locks::<HashMap<_, std::sync::RwLock<()>>>()
+            .build();
+        *locker_room.write_cell(3).unwrap() += 1;
+        assert!(locker_room.read_cell(2).is_none());
+        locker_room.lock_room().insert(2, 2);
+        assert_eq!(2, *locker_room.read_cell(2).unwrap());
+        assert_eq!(
+            vec![(1, 1), (2, 2), (3, 4)],
+            locker_room.into_inner().into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        reads: AtomicUsize,
+        writes: AtomicUsize,
+        contended: AtomicUsize,
+        timed_out: AtomicUsize,
+    }
+
+    impl LockObserver<usize> for Counter {
+        fn on_read_acquired(&self, _index: &usize) {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_write_acquired(&self, _index: &usize) {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_contended(&self, index: &usize) {
+            assert_eq!(1, *index);
+            self.contended.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_timed_out(&self, index: &usize) {
+            assert_eq!(1, *index);
+            self.timed_out.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn observer() {
+        let counter = Arc::new(Counter::default());
+        let locker_room = LockerRoom::with_observer(vec![0, 1, 2], counter.clone());
+        drop(locker_room.read_cell(0).unwrap());
+        drop(locker_room.read_cells([0, 2]).unwrap());
+        drop(locker_room.write_cells([1, 2]).unwrap());
+        assert_eq!(3, counter.reads.load(Ordering::SeqCst));
+        assert_eq!(2, counter.writes.load(Ordering::SeqCst));
+        assert_eq!(0, counter.contended.load(Ordering::SeqCst));
+
+        thread::scope(|scope| {
+            let guard = locker_room.write_cell(1).unwrap();
+            scope.spawn(|| *locker_room.write_cell(1).unwrap() += 1);
+            while counter.contended.load(Ordering::SeqCst) == 0 {
+                thread::yield_now();
+            }
+            drop(guard);
+        });
+        assert_eq!(4, counter.writes.load(Ordering::SeqCst));
+        assert_eq!(1, counter.contended.load(Ordering::SeqCst));
+        assert_eq!(2, *locker_room.read_cell(1).unwrap());
+
+        drop(locker_room.try_write_cell_for(1, Duration::ZERO).unwrap());
+        let guard = locker_room.write_cell(1).unwrap();
+        assert!(locker_room
+            .try_read_cell_for(1, Duration::from_millis(1))
+            .is_none());
+        drop(guard);
+        assert_eq!(6, counter.writes.load(Ordering::SeqCst));
+        assert_eq!(2, counter.contended.load(Ordering::SeqCst));
+        assert_eq!(1, counter.timed_out.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing() {
+        use std::{fmt, sync::Mutex};
+
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        #[derive(Clone, Default)]
+        struct Spans(Arc<Mutex<Vec<String>>>);
+
+        struct Record(String);
+
+        impl Visit for Record {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0 += &format!(" {}={:?}", field.name(), value);
+            }
+        }
+
+        impl Subscriber for Spans {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                let mut record = Record(span.metadata().name().to_owned());
+                span.record(&mut record);
+                let mut spans = self.0.lock().unwrap();
+                spans.push(record.0);
+                span::Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+            fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+            fn event(&self, _event: &Event<'_>) {}
+            fn enter(&self, _span: &span::Id) {}
+            fn exit(&self, _span: &span::Id) {}
+        }
+
+        let spans = Spans::default();
+        tracing::subscriber::with_default(spans.clone(), || {
+            let locker_room: LockerRoom<_> = vec![0, 1].into();
+            drop(locker_room.read_cell(0).unwrap());
+            let locker_room = locker_room.trace_indices();
+            drop(locker_room.write_cells([1, 0]).unwrap());
+            drop(locker_room.lock_room());
+        });
+        assert_eq!(
+            vec![
+                "lock_cell kind=\"read\" index=_",
+                "lock_cell kind=\"write\" index=0",
+                "lock_cell kind=\"write\" index=1",
+                "lock_room",
+            ],
+            *spans.0.lock().unwrap()
+        );
+    }
+
+    #[cfg(all(feature = "deadlock-detect", debug_assertions))]
+    #[test]
+    fn deadlock_detect() {
+        let locker_room: LockerRoom<_> = vec![0, 0].into();
+        let locker_room = Arc::new(locker_room.trace_indices());
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let handles = [(0, 1), (1, 0)].map(|(first, second)| {
+            let locker_room = Arc::clone(&locker_room);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let _first = locker_room.write_cell(first).unwrap();
+                barrier.wait();
+                *locker_room.write_cell(second).unwrap() += 1;
+            })
+        });
+        let panics: Vec<_> = handles
+            .into_iter()
+            .filter_map(|handle| handle.join().err())
+            .collect();
+        assert_eq!(1, panics.len());
+        let message = panics[0].downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("deadlock detected"), "{message}");
+        assert!(
+            message.contains("cell 0") && message.contains("cell 1"),
+            "{message}"
+        );
+        assert_eq!(
+            1,
+            *locker_room.read_cell(0).unwrap() + *locker_room.read_cell(1).unwrap()
+        );
+
+        // Nested reads of one cell don't wait for each other.
+        let guard = locker_room.read_cell(0).unwrap();
+        let nested = locker_room.read_cells([0, 1]).unwrap();
+        drop((guard, nested));
+
+        // Cells locked by cloning a guard and through the lock of whole collection are named too.
+        let barriers = Arc::new([(); 2].map(|_| std::sync::Barrier::new(2)));
+        let reader = {
+            let (locker_room, barriers) = (Arc::clone(&locker_room), Arc::clone(&barriers));
+            thread::spawn(move || {
+                barriers[0].wait();
+                let guard = locker_room.read_cell(0).unwrap();
+                let _cloned = ReadCellGuard::clone_guard(&guard).unwrap();
+                drop(guard);
+                barriers[1].wait();
+                *locker_room.write_cell(1).unwrap() += 1;
+            })
+        };
+        let writer = {
+            let (locker_room, barriers) = (Arc::clone(&locker_room), Arc::clone(&barriers));
+            thread::spawn(move || {
+                let _cells = RoomGuard::into_cells(locker_room.lock_room(), [1]).unwrap();
+                barriers[0].wait();
+                barriers[1].wait();
+                *locker_room.write_cell(0).unwrap() += 1;
+            })
+        };
+        let panics: Vec<_> = [reader, writer]
+            .into_iter()
+            .filter_map(|handle| handle.join().err())
+            .collect();
+        assert_eq!(1, panics.len());
+        let message = panics[0].downcast_ref::<String>().unwrap();
+        assert!(
+            message.contains("cell 0") && message.contains("cell 1") && !message.contains("cell _"),
+            "{message}"
+        );
+    }
+
+    thread_local! {
+        static NEXT_LOCK_ID: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        static RELEASED: std::cell::RefCell<Vec<usize>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    // Records its id in `RELEASED` when a guard of it is dropped. Locks are numbered in order of creation.
+    struct TrackingLock {
+        id: usize,
+        lock: RwLock<()>,
+    }
+
+    impl Default for TrackingLock {
+        fn default() -> Self {
+            let id = NEXT_LOCK_ID.replace(NEXT_LOCK_ID.get() + 1);
+            Self {
+                id,
+                lock: RwLock::new(()),
+            }
+        }
+    }
+
+    struct Tracked<G> {
+        id: usize,
+        guard: Option<G>,
+    }
+
+    impl<G> Tracked<G> {
+        fn new(id: usize, guard: G) -> Self {
+            Self {
+                id,
+                guard: Some(guard),
+            }
+        }
+    }
+
+    impl<G> Drop for Tracked<G> {
+        fn drop(&mut self) {
+            if self.guard.is_some() {
+                RELEASED.with_borrow_mut(|released| released.push(self.id));
+            }
+        }
+    }
+
+    impl ShadowLock for TrackingLock {
+        type ReadGuard<'a> = Tracked<<RwLock<()> as ShadowLock>::ReadGuard<'a>>;
+        type WriteGuard<'a> = Tracked<<RwLock<()> as ShadowLock>::WriteGuard<'a>>;
+
+        fn read(&self) -> Self::ReadGuard<'_> {
+            Tracked::new(self.id, ShadowLock::read(&self.lock))
+        }
+
+        fn write(&self) -> Self::WriteGuard<'_> {
+            Tracked::new(self.id, ShadowLock::write(&self.lock))
+        }
+
+        fn try_read(&self) -> Option<Self::ReadGuard<'_>> {
+            Some(Tracked::new(self.id, ShadowLock::try_read(&self.lock)?))
+        }
+
+        fn try_write(&self) -> Option<Self::WriteGuard<'_>> {
+            Some(Tracked::new(self.id, ShadowLock::try_write(&self.lock)?))
+        }
+
+        fn downgrade<'a>(mut guard: Self::WriteGuard<'a>) -> Self::ReadGuard<'a> {
+            let inner = guard.guard.take().unwrap();
+            Tracked::new(guard.id, <RwLock<()> as ShadowLock>::downgrade(inner))
+        }
+    }
+
+    #[test]
+    fn guards_release_cells_before_room() {
+        NEXT_LOCK_ID.set(0);
+        let locker_room = LockerRoom::builder(vec![String::from("a"), String::from("b")])
+            .shadow_locks::<Vec<TrackingLock>>()
+            .build();
+        // The lock of whole collection is created after the locks of both cells.
+        let released = || RELEASED.take();
+        drop(locker_room.read_cell(1).unwrap());
+        assert_eq!(vec![1, 2], released());
+        drop(locker_room.write_cell(0).unwrap());
+        assert_eq!(vec![0, 2], released());
+        drop(ReadCellGuard::map(
+            locker_room.read_cell(1).unwrap(),
+            String::as_str,
+        ));
+        assert_eq!(vec![1, 2], released());
+        drop(WriteCellGuard::map(
+            locker_room.write_cell(0).unwrap(),
+            String::as_mut_str,
+        ));
+        assert_eq!(vec![0, 2], released());
+        drop(locker_room.read_cells([1, 0]).unwrap());
+        assert_eq!(vec![0, 1, 2], released());
+        drop(locker_room.write_cells([1, 0]).unwrap());
+        assert_eq!(vec![0, 1, 2], released());
+        drop(locker_room.read_range(0..2).unwrap());
+        assert_eq!(vec![0, 1, 2], released());
+        drop(locker_room.write_range(0..2).unwrap());
+        assert_eq!(vec![0, 1, 2], released());
+        drop(RoomGuard::into_cells(locker_room.lock_room(), [1, 0]).unwrap());
+        assert_eq!(vec![0, 1, 2], released());
+    }
+
+    #[test]
+    fn map_locks_survive_rehash() {
+        // Locks are moved by rehashes of the shadow locks, so they are told apart by the ids given on creation.
+        fn ids(
+            locker_room: &LockerRoom<HashMap<u32, u32>, HashMap<u32, TrackingLock>>,
+        ) -> BTreeMap<u32, usize> {
+            let locks = unsafe { &*locker_room.index_locks.get() };
+            locks
+                .iter()
+                .map(|(index, lock)| (*index, lock.id))
+                .collect()
+        }
+
+        NEXT_LOCK_ID.set(0);
+        let mut map = HashMap::new();
+        map.extend((0..3).map(|index| (index, index)));
+        map.shrink_to_fit();
+        let capacity = map.capacity();
+        let locker_room = LockerRoom::builder(map)
+            .shadow_locks::<HashMap<_, TrackingLock>>()
+            .build();
+        // Fills the map up, so the key after them makes it rehash.
+        let mut room = locker_room.lock_room();
+        room.extend((3..capacity as u32).map(|index| (index, index)));
+        drop(room);
+        let filled = ids(&locker_room);
+        locker_room.lock_room().insert(100, 100);
+        assert!(locker_room.lock_room().capacity() > capacity);
+        let mut after = ids(&locker_room);
+        assert_eq!(Some(NEXT_LOCK_ID.get() - 1), after.remove(&100));
+        assert_eq!(filled, after);
+        locker_room.lock_room().remove(&0);
+        after.insert(100, NEXT_LOCK_ID.get() - 1);
+        after.remove(&0);
+        assert_eq!(after, ids(&locker_room));
+    }
+
+    #[test]
+    fn deque_locks_follow_cells() {
+        NEXT_LOCK_ID.set(0);
+        let locker_room = LockerRoom::builder(VecDeque::from([0, 1, 2, 3]))
+            .shadow_locks::<VecDeque<TrackingLock>>()
+            .build();
+        let released = || RELEASED.take();
+        // The lock of whole collection is created after the locks of the cells.
+        let global = 4;
+        locker_room.lock_room().pop_front();
+        released();
+        drop(locker_room.write_cell(0).unwrap());
+        assert_eq!(vec![1, global], released());
+
+        let mut room = locker_room.lock_room();
+        room.rotate_left(1);
+        room.insert(1, 4);
+        assert_eq!(Some(1), room.remove(3));
+        room.push_front(5);
+        // The push through the deque itself gets a lock at the back.
+        room.deref_mut().push_back(6);
+        room.rotate_right(2);
+        drop(room);
+        released();
+        let expected = [(0, 3, 3), (1, 6, 7), (2, 5, 6), (3, 2, 2), (4, 4, 5)];
+        for (index, value, id) in expected {
+            assert_eq!(value, *locker_room.write_cell(index).unwrap());
+            assert_eq!(vec![id, global], released());
+        }
+    }
+
+    #[test]
+    fn sync_locks() {
+        NEXT_LOCK_ID.set(0);
+        let locker_room = LockerRoom::builder(vec![0, 1])
+            .shadow_locks::<Vec<TrackingLock>>()
+            .build();
+        let (sender, receiver) = mpsc::channel();
+        let r = &locker_room;
+        thread::scope(|scope| {
+            let mut room = locker_room.lock_room();
+            room.push(2);
+            // The lock of the new cell is created by `sync_locks`, not by the push.
+            assert_eq!(3, NEXT_LOCK_ID.get());
+            room.sync_locks();
+            assert_eq!(4, NEXT_LOCK_ID.get());
+            room.sync_locks();
+            assert_eq!(4, NEXT_LOCK_ID.get());
+            // The new cell has a lock, but it can't be locked until the guard is dropped.
+            scope.spawn(move || sender.send(r.read_cell(2).map(|guard| *guard)).unwrap());
+            assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+            drop(room);
+            assert_eq!(Some(2), receiver.recv().unwrap());
+        });
+        assert_eq!(4, NEXT_LOCK_ID.get());
+    }
+
+    #[test]
+    fn resize_truncate() {
+        let locker_room: LockerRoom<_> = vec![1, 2].into();
+        locker_room.resize(4, 0);
+        assert_eq!(4, unsafe { &*locker_room.index_locks.get() }.len());
+        *locker_room.write_cell(3).unwrap() = 4;
+        locker_room.resize(3, 0);
+        assert_eq!(3, unsafe { &*locker_room.index_locks.get() }.len());
+        locker_room.truncate(5);
+        assert_eq!(vec![1, 2, 0], *locker_room.lock_room());
+        locker_room.truncate(1);
+        assert_eq!(1, unsafe { &*locker_room.index_locks.get() }.len());
+        assert!(locker_room.read_cell(1).is_none());
+        assert_eq!(vec![1], locker_room.into_inner());
+    }
+}
+
+#[cfg(loom)]
+mod loom_test {
+    use loom::{cell::UnsafeCell, sync::Arc, thread};
+
+    use crate::SeqLocks;
+
+    use super::LockerRoom;
+
+    /// Cell which lets loom catch any access that isn't ordered by the locks of `LockerRoom`.
+    struct Cell(UnsafeCell<usize>);
+
+    // SAFETY: the cells are only accessed under the locks of `LockerRoom`, which is exactly what the models check.
+    unsafe impl Sync for Cell {}
+
+    impl Cell {
+        fn new() -> Self {
+            Self(UnsafeCell::new(0))
+        }
+
+        fn get(&self) -> usize {
+            self.0.with(|value| unsafe { *value })
+        }
+
+        fn increment(&self) {
+            self.0.with_mut(|value| unsafe { *value += 1 });
+        }
+    }
+
+    #[test]
+    fn cells() {
+        loom::model(|| {
+            let locker_room: Arc<LockerRoom<_>> = Arc::new(vec![Cell::new(), Cell::new()].into());
+            let writer = thread::spawn({
+                let locker_room = locker_room.clone();
+                move || locker_room.write_cell(0).unwrap().increment()
+            });
+            let multi_writer = thread::spawn({
+                let locker_room = locker_room.clone();
+                move || {
+                    let guard = locker_room.write_cells([1, 0]).unwrap();
+                    guard.iter().for_each(|(_, cell)| cell.increment());
+                }
+            });
+            assert!(locker_room.read_cell(1).unwrap().get() <= 1);
+            writer.join().unwrap();
+            multi_writer.join().unwrap();
+            assert_eq!(2, locker_room.read_cell(0).unwrap().get());
+            assert_eq!(1, locker_room.read_cell(1).unwrap().get());
+        });
+    }
+
+    #[test]
+    fn room() {
+        loom::model(|| {
+            let locker_room: Arc<LockerRoom<_>> = Arc::new(vec![Cell::new(), Cell::new()].into());
+            let writer = thread::spawn({
+                let locker_room = locker_room.clone();
+                move || locker_room.write_cell(1).unwrap().increment()
+            });
+            let reader = thread::spawn({
+                let locker_room = locker_room.clone();
+                move || locker_room.read_cell(2).map(|cell| cell.get())
+            });
+            {
+                let mut room = locker_room.lock_room();
+                room.iter().for_each(Cell::increment);
+                room.push(Cell::new());
+            }
+            writer.join().unwrap();
+            assert!(reader.join().unwrap().is_none_or(|value| value == 0));
+            assert_eq!(2, locker_room.read_cell(1).unwrap().get());
+            assert_eq!(3, locker_room.indices().len());
+        });
+    }
+
+    #[test]
+    fn write_cell_or_grow() {
+        loom::model(|| {
+            let locker_room: Arc<LockerRoom<_>> = Arc::new(vec![Cell::new()].into());
+            let grower = thread::spawn({
+                let locker_room = locker_room.clone();
+                move || locker_room.write_cell_or_grow(1, Cell::new).increment()
+            });
+            locker_room.lock_room().iter().for_each(Cell::increment);
+            grower.join().unwrap();
+            assert_eq!(1, locker_room.read_cell(0).unwrap().get());
+            assert!((1..=2).contains(&locker_room.read_cell(1).unwrap().get()));
+        });
+    }
+
+    #[test]
+    fn get_cell_seq() {
+        loom::model(|| {
+            let locker_room = Arc::new(
+                LockerRoom::builder(vec![(0u64, 0u64)])
+                    .shadow_locks::<SeqLocks>()
+                    .build(),
+            );
+            let writer = thread::spawn({
+                let locker_room = locker_room.clone();
+                move || {
+                    // Loom doesn't see the plain writes and volatile reads of the value, so a value written halfway
+                    // can't be modeled here; `SeqLock`'s own model covers that with atomics. This one checks that
+                    // the copies are ordered by the sequence counters of write guards.
+                    *locker_room.write_cell(0).unwrap() = (1, 1);
+                    *locker_room.write_cell(0).unwrap() = (2, 2);
+                }
+            });
+            let first = locker_room.get_cell_seq(0).unwrap();
+            let second = locker_room.get_cell_seq(0).unwrap();
+            assert!([(0, 0), (1, 1), (2, 2)].contains(&first));
+            assert!(first <= second);
+            writer.join().unwrap();
+            assert_eq!(Some((2, 2)), locker_room.get_cell_seq(0));
+        });
     }
 }
@@ -0,0 +1,26 @@
+/// Log of cell values written through [`LockerRoom::write_cell_logged`](super::LockerRoom::write_cell_logged),
+/// e.g. a write-ahead log of a persistent collection.
+///
+/// The value is recorded when the guard is dropped, while the cell is still locked, so the records of every cell
+/// go in the order of its writes.
+/// ```
+/// # use std::sync::Mutex;
+/// # use lockerroom::{LockerRoom, sync::CellLogger};
+/// #[derive(Default)]
+/// struct Log(Mutex<Vec<String>>);
+///
+/// impl CellLogger<usize, i32> for Log {
+///     fn log(&self, index: &usize, value: &i32) {
+///         self.0.lock().unwrap().push(format!("{index}={value}"));
+///     }
+/// }
+///
+/// let log = Log::default();
+/// let locker_room: LockerRoom<_> = vec![0, 0].into();
+/// *locker_room.write_cell_logged(1, &log).unwrap() += 5;
+/// assert_eq!(vec!["1=5"], *log.0.lock().unwrap());
+/// ```
+pub trait CellLogger<Idx, Value: ?Sized> {
+    /// Called with the new value of the cell at the index before the cell is released.
+    fn log(&self, index: &Idx, value: &Value);
+}
@@ -1,7 +1,39 @@
 //! `LockerRoom` and its necessary types.
 
+mod builder;
+mod cells;
+mod colocated;
+mod entry;
 mod guard;
+mod handle;
+mod locked;
 mod locker_room;
+mod logger;
+mod notify;
+mod observer;
+mod room_read;
+mod scope;
+mod shared;
+#[cfg(any(feature = "stats", doc))]
+mod stats;
+mod view;
 
+pub use builder::LockerRoomBuilder;
+pub use cells::{LockerRoomCells, SharedSlice};
+pub use colocated::{Colocated, ColocatedRoomGuard};
+pub use entry::{Entry, VacantEntry};
 pub use guard::*;
-pub use locker_room::LockerRoom;
+pub use handle::CellHandle;
+pub use locked::{
+    LockedCells, LockedMultiWriteGuard, LockedReadCellGuard, LockedRoomGuard, LockedWriteCellGuard,
+};
+pub use locker_room::{LockerRoom, Room, RoomLock};
+pub use logger::CellLogger;
+pub use observer::LockObserver;
+pub use room_read::RoomReadCtx;
+pub use scope::ScopedLockerRoom;
+pub use shared::{ArcReadCellGuard, SharedLockerRoom};
+#[cfg(any(feature = "stats", doc))]
+#[doc(cfg(feature = "stats"))]
+pub use stats::LockerRoomStats;
+pub use view::LockerRoomView;
@@ -0,0 +1,96 @@
+//! Wake-ups of threads waiting for a condition on a cell.
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash},
+    sync::Arc,
+};
+
+use crate::primitive::{Condvar, Mutex, MutexGuard};
+
+/// Condition variables of cells for [`wait_write_cell`](super::LockerRoom::wait_write_cell), keyed by hashes of
+/// their indices.
+///
+/// A cell gets its condition variable while some thread waits for it, so a notification wakes only the waiters of
+/// that cell, or of a cell whose index has the same hash. The generation of the cell is bumped by every notification,
+/// so a waiter that registered before checking the cell never misses a notification sent after the check.
+#[derive(Default)]
+pub(crate) struct Notifier {
+    cells: Mutex<HashMap<u64, Waiters>>,
+}
+
+struct Waiters {
+    generation: usize,
+    count: usize,
+    condvar: Arc<Condvar>,
+}
+
+impl Notifier {
+    // Hashes are the same for all `LockerRoom`s, so they're only computed for the cells which threads wait for.
+    fn key(index: &impl Hash) -> u64 {
+        BuildHasherDefault::<DefaultHasher>::default().hash_one(index)
+    }
+
+    fn cells(&self) -> MutexGuard<'_, HashMap<u64, Waiters>> {
+        self.cells.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Registers the current thread as a waiter of the cell at the index.
+    pub(crate) fn register(&self, index: &impl Hash) -> Registration<'_> {
+        let key = Self::key(index);
+        let mut cells = self.cells();
+        let waiters = cells.entry(key).or_insert_with(|| Waiters {
+            generation: 0,
+            count: 0,
+            condvar: Default::default(),
+        });
+        waiters.count += 1;
+        Registration {
+            notifier: self,
+            key,
+            generation: waiters.generation,
+        }
+    }
+
+    pub(crate) fn notify(&self, index: &impl Hash) {
+        if let Some(waiters) = self.cells().get_mut(&Self::key(index)) {
+            waiters.generation = waiters.generation.wrapping_add(1);
+            waiters.condvar.notify_all();
+        }
+    }
+}
+
+/// Waiter of a cell, which is unregistered once dropped.
+pub(crate) struct Registration<'a> {
+    notifier: &'a Notifier,
+    key: u64,
+    generation: usize,
+}
+
+impl Registration<'_> {
+    /// Blocks the current thread until the cell is notified after the registration.
+    pub(crate) fn wait(self) {
+        let mut cells = self.notifier.cells();
+        loop {
+            let waiters = &cells[&self.key];
+            if waiters.generation != self.generation {
+                return;
+            }
+            let condvar = waiters.condvar.clone();
+            cells = condvar.wait(cells).unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut cells = self.notifier.cells();
+        let waiters = cells
+            .get_mut(&self.key)
+            .expect("registered cell has waiters");
+        waiters.count -= 1;
+        if waiters.count == 0 {
+            cells.remove(&self.key);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+/// Observer of cell locking in [`LockerRoom`](super::LockerRoom), e.g. for collecting metrics.
+///
+/// All methods do nothing by default. They are called in the locking thread, so they should be cheap.
+/// ```
+/// # use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+/// # use lockerroom::{LockerRoom, sync::LockObserver};
+/// #[derive(Default)]
+/// struct Writes(AtomicUsize);
+///
+/// impl LockObserver<usize> for Writes {
+///     fn on_write_acquired(&self, _index: &usize) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let writes = Arc::new(Writes::default());
+/// let locker_room = LockerRoom::with_observer(vec![0, 1, 2], writes.clone());
+/// *locker_room.write_cell(0).unwrap() += 1;
+/// *locker_room.write_cell(1).unwrap() += 1;
+/// assert_eq!(2, writes.0.load(Ordering::Relaxed));
+/// ```
+pub trait LockObserver<Idx>: Send + Sync {
+    /// Called after the cell at the index is locked with shared read access.
+    fn on_read_acquired(&self, _index: &Idx) {}
+
+    /// Called after the cell at the index is locked with exclusive write access.
+    fn on_write_acquired(&self, _index: &Idx) {}
+
+    /// Called when the cell at the index can't be locked immediately, before the thread blocks on it.
+    fn on_contended(&self, _index: &Idx) {}
+
+    /// Called when a timed attempt to lock the cell at the index, e.g. by
+    /// [`try_read_cell_for`](super::LockerRoom::try_read_cell_for), gives up.
+    fn on_timed_out(&self, _index: &Idx) {}
+}
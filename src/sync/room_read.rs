@@ -0,0 +1,57 @@
+use std::borrow::Borrow;
+
+use crate::{Collection, ShadowLocksCollection};
+
+use super::{guard::LockReadGuard, BareReadCellGuard, LockerRoom};
+
+/// Access to cells of a [`LockerRoom`] while the lock of whole collection is held with shared read access.
+///
+/// Every [`read_cell`](Self::read_cell) locks only the cell, so the lock of whole collection is taken once for all of
+/// them. Meanwhile [`lock_room`](LockerRoom::lock_room) can't run, so no cell is inserted or removed.
+///
+/// This structure is created by the [`read_room_scoped`](LockerRoom::read_room_scoped) method on [`LockerRoom`].
+pub struct RoomReadCtx<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    locker_room: &'a LockerRoom<T, S>,
+    // Keeps whole collection as it is until the scope ends.
+    #[allow(dead_code)]
+    global_lock_guard: LockReadGuard<'a, S>,
+}
+
+impl<'a, T, S> RoomReadCtx<'a, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'a,
+{
+    pub(crate) fn new(
+        locker_room: &'a LockerRoom<T, S>,
+        global_lock_guard: LockReadGuard<'a, S>,
+    ) -> Self {
+        Self {
+            locker_room,
+            global_lock_guard,
+        }
+    }
+
+    /// Returns `true` if there is a cell at the index, without locking the cell or looking its value up.
+    ///
+    /// Cells can't be inserted or removed within the scope, so the answer holds until it ends.
+    pub fn contains(&self, index: impl Borrow<T::Idx>) -> bool {
+        // The lock of whole collection is held by `self`.
+        self.locker_room.contains_index_unlocked(index.borrow())
+    }
+
+    /// Locks cell at the index with shared read access, blocking the current thread until it can be acquired.
+    ///
+    /// This function will return `None` if there is no cell with such index.
+    ///
+    /// Returns an RAII guard which will release this thread's shared access to the cell once it is dropped.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell(&self, index: impl Borrow<T::Idx>) -> Option<BareReadCellGuard<'_, T, S>> {
+        // The lock of whole collection is held by `self`.
+        self.locker_room.read_cell_bare(index.borrow())
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    ops::Deref,
+    thread::{Scope, ScopedJoinHandle},
+};
+
+use crate::{Collection, ShadowLocksCollection};
+
+use super::LockerRoom;
+
+/// [`LockerRoom`] borrowed by a scope of threads, which spawns threads with access to it.
+///
+/// Threads get a plain reference to the `LockerRoom`, so sharing it costs no reference counting, unlike an [`Arc`](std::sync::Arc).
+/// It also dereferences to the `LockerRoom`, so cells can be locked in the scope itself.
+///
+/// This structure is created by [`LockerRoom::scope`].
+pub struct ScopedLockerRoom<'scope, 'env, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    scope: &'scope Scope<'scope, 'env>,
+    locker_room: &'env LockerRoom<T, S>,
+}
+
+impl<'scope, 'env, T, S> ScopedLockerRoom<'scope, 'env, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    pub(crate) fn new(
+        scope: &'scope Scope<'scope, 'env>,
+        locker_room: &'env LockerRoom<T, S>,
+    ) -> Self {
+        Self { scope, locker_room }
+    }
+
+    /// Spawns a scoped thread which calls `f` with the `LockerRoom`, like [`Scope::spawn`].
+    ///
+    /// The thread is joined automatically at the end of the scope, unless it's joined with the returned handle earlier.
+    pub fn spawn<F, R>(&self, f: F) -> ScopedJoinHandle<'scope, R>
+    where
+        F: FnOnce(&'env LockerRoom<T, S>) -> R + Send + 'scope,
+        R: Send + 'scope,
+        LockerRoom<T, S>: Sync,
+    {
+        let locker_room = self.locker_room;
+        self.scope.spawn(move || f(locker_room))
+    }
+
+    /// Returns the scope of threads, e.g. to spawn threads which don't need the `LockerRoom`.
+    pub fn thread_scope(&self) -> &'scope Scope<'scope, 'env> {
+        self.scope
+    }
+}
+
+impl<'scope, 'env, T, S> Deref for ScopedLockerRoom<'scope, 'env, T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    type Target = LockerRoom<T, S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.locker_room
+    }
+}
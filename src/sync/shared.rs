@@ -0,0 +1,191 @@
+use std::{borrow::Borrow, mem::ManuallyDrop, ops::Deref, sync::Arc};
+
+use crate::{Collection, ShadowLocksCollection};
+
+use super::{LockerRoom, ReadCellGuard};
+
+/// A [`LockerRoom`] behind an [`Arc`], so it can be cloned cheaply and every clone can be moved to another thread.
+///
+/// It packages the common `Arc<LockerRoom<T>>` pattern: clones share the same collection and dereference to the
+/// `LockerRoom`.
+/// ```
+/// # use std::thread;
+/// # use lockerroom::sync::SharedLockerRoom;
+/// let locker_room = SharedLockerRoom::new(vec![0; 4]);
+/// let handles: Vec<_> = (0..4)
+///     .map(|i| {
+///         let locker_room = locker_room.clone();
+///         thread::spawn(move || *locker_room.write_cell(i).unwrap() += i)
+///     })
+///     .collect();
+/// handles.into_iter().for_each(|handle| handle.join().unwrap());
+/// assert_eq!(Some(vec![0, 1, 2, 3]), locker_room.try_into_inner().ok());
+/// ```
+pub struct SharedLockerRoom<T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    locker_room: Arc<LockerRoom<T, S>>,
+}
+
+impl<T> SharedLockerRoom<T>
+where
+    T: Collection,
+{
+    /// Creates a `SharedLockerRoom` of the collection.
+    pub fn new(collection: T) -> Self {
+        LockerRoom::from(collection).into()
+    }
+}
+
+impl<T, S> SharedLockerRoom<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    /// Returns the underlying data if this is the only clone, or gives the `SharedLockerRoom` back otherwise.
+    /// See [`LockerRoom::try_into_inner_arc`].
+    pub fn try_into_inner(self) -> Result<T, Self> {
+        LockerRoom::try_into_inner_arc(self.locker_room).map_err(|locker_room| Self { locker_room })
+    }
+
+    /// Returns the shared `Arc` of the `LockerRoom`.
+    pub fn into_arc(self) -> Arc<LockerRoom<T, S>> {
+        self.locker_room
+    }
+
+    /// Locks cell at the index with shared read access like [`read_cell`](LockerRoom::read_cell), packing the guard
+    /// together with a clone of the `SharedLockerRoom`. See [`ArcReadCellGuard`].
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell_arc(&self, index: impl Borrow<T::Idx>) -> Option<ArcReadCellGuard<T, S>>
+    where
+        T: 'static,
+        S: 'static,
+    {
+        ArcReadCellGuard::new(Arc::clone(&self.locker_room), index)
+    }
+}
+
+impl<T, S> Clone for SharedLockerRoom<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    /// Makes another handle of the same `LockerRoom`. Unlike [`LockerRoom::clone`] it doesn't copy the collection.
+    fn clone(&self) -> Self {
+        Self {
+            locker_room: Arc::clone(&self.locker_room),
+        }
+    }
+}
+
+impl<T, S> Deref for SharedLockerRoom<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    type Target = LockerRoom<T, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.locker_room
+    }
+}
+
+impl<T, S> From<LockerRoom<T, S>> for SharedLockerRoom<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    fn from(locker_room: LockerRoom<T, S>) -> Self {
+        Arc::new(locker_room).into()
+    }
+}
+
+impl<T, S> From<Arc<LockerRoom<T, S>>> for SharedLockerRoom<T, S>
+where
+    T: Collection,
+    S: ShadowLocksCollection<Idx = T::Idx>,
+{
+    fn from(locker_room: Arc<LockerRoom<T, S>>) -> Self {
+        Self { locker_room }
+    }
+}
+
+/// RAII structure used to release the shared read access of a cell lock when dropped, which keeps the
+/// [`SharedLockerRoom`] alive on its own.
+///
+/// Unlike [`ReadCellGuard`] it doesn't borrow the `LockerRoom`, so it's `'static` and can be stored anywhere,
+/// e.g. as a value of a cache map. But the cell stays locked for all that time: writers of the cell and
+/// [`lock_room`](LockerRoom::lock_room) wait until the guard is dropped, so long-lived guards should be kept for
+/// rarely written cells only.
+///
+/// This structure is created by the [`read_cell_arc`](SharedLockerRoom::read_cell_arc) method on [`SharedLockerRoom`].
+/// ```
+/// # use std::collections::HashMap;
+/// # use lockerroom::sync::SharedLockerRoom;
+/// let locker_room = SharedLockerRoom::new(vec![String::from("a"), String::from("b")]);
+/// let mut cache = HashMap::new();
+/// cache.insert("b", locker_room.read_cell_arc(1).unwrap());
+/// let locker_room = locker_room.try_into_inner().unwrap_err();
+/// assert_eq!("b", cache["b"].as_str());
+/// drop(cache);
+/// assert!(locker_room.try_into_inner().is_ok());
+/// ```
+#[must_use = "locking has no effect unless the guard is used"]
+pub struct ArcReadCellGuard<T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection + 'static,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'static,
+{
+    // Borrows from `locker_room`, so it's dropped before it.
+    guard: ManuallyDrop<ReadCellGuard<'static, T, S>>,
+    locker_room: Arc<LockerRoom<T, S>>,
+}
+
+impl<T, S> ArcReadCellGuard<T, S>
+where
+    T: Collection + 'static,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'static,
+{
+    fn new(locker_room: Arc<LockerRoom<T, S>>, index: impl Borrow<T::Idx>) -> Option<Self> {
+        // SAFETY: the `LockerRoom` doesn't move and lives as long as `locker_room`, which outlives the guard.
+        let borrowed: &'static LockerRoom<T, S> = unsafe { &*Arc::as_ptr(&locker_room) };
+        let guard = borrowed.read_cell(index)?;
+        Some(Self {
+            guard: ManuallyDrop::new(guard),
+            locker_room,
+        })
+    }
+
+    /// Returns the `SharedLockerRoom` which the cell belongs to.
+    ///
+    /// This is an associated function that needs to be used as `ArcReadCellGuard::locker_room(&guard)`,
+    /// so it doesn't conflict with a method on the cell value.
+    pub fn locker_room(orig: &Self) -> SharedLockerRoom<T, S> {
+        Arc::clone(&orig.locker_room).into()
+    }
+}
+
+impl<T, S> Deref for ArcReadCellGuard<T, S>
+where
+    T: Collection + 'static,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'static,
+{
+    type Target = T::Output;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T, S> Drop for ArcReadCellGuard<T, S>
+where
+    T: Collection + 'static,
+    S: ShadowLocksCollection<Idx = T::Idx> + 'static,
+{
+    fn drop(&mut self) {
+        // SAFETY: the guard is dropped once, here, while `locker_room` still keeps the `LockerRoom` alive.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+    }
+}
@@ -0,0 +1,52 @@
+use crate::primitive::{AtomicUsize, Ordering};
+
+/// Numbers of locks acquired by a [`LockerRoom`](super::LockerRoom) since it was created.
+///
+/// This structure is returned by the [`stats`](super::LockerRoom::stats) method on [`LockerRoom`](super::LockerRoom).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockerRoomStats {
+    /// Cell locks acquired with shared read access.
+    pub reads: usize,
+    /// Cell locks acquired with exclusive write access.
+    pub writes: usize,
+    /// Locks of whole collection acquired by [`lock_room`](super::LockerRoom::lock_room) and the like.
+    pub rooms: usize,
+    /// Timed attempts to lock a cell that gave up, e.g. by [`try_read_cell_for`](super::LockerRoom::try_read_cell_for).
+    pub timeouts: usize,
+}
+
+// Counters are only ever added to and read separately, so nothing has to be ordered with them.
+#[derive(Default)]
+pub(crate) struct Counters {
+    reads: AtomicUsize,
+    writes: AtomicUsize,
+    rooms: AtomicUsize,
+    timeouts: AtomicUsize,
+}
+
+impl Counters {
+    pub(crate) fn read(&self) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn write(&self) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn room(&self) {
+        self.rooms.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn timeout(&self) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LockerRoomStats {
+        LockerRoomStats {
+            reads: self.reads.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            rooms: self.rooms.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+        }
+    }
+}
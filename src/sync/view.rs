@@ -0,0 +1,79 @@
+use std::ops::Range;
+
+use crate::{Collection, ShadowLocksCollection};
+
+use super::{LockerRoom, ReadCellGuard, WriteCellGuard};
+
+/// View of a contiguous range of cells of a [`LockerRoom`] with indices of type `usize`.
+///
+/// Cells are indexed relative to the start of the range. Views made by [`LockerRoom::split_at_mut`] never overlap,
+/// so every half can be lent to its own thread, which locks cells of the half independently of the other one.
+///
+/// This structure is created by [`LockerRoom::split_at_mut`] and [`LockerRoomView::split_at`].
+pub struct LockerRoomView<'a, T, S = <T as Collection>::ShadowLocks>
+where
+    T: Collection<Idx = usize>,
+    S: ShadowLocksCollection<Idx = usize>,
+{
+    locker_room: &'a LockerRoom<T, S>,
+    range: Range<usize>,
+}
+
+impl<'a, T, S> LockerRoomView<'a, T, S>
+where
+    T: Collection<Idx = usize>,
+    S: ShadowLocksCollection<Idx = usize> + 'a,
+{
+    pub(crate) fn new(locker_room: &'a LockerRoom<T, S>, range: Range<usize>) -> Self {
+        Self { locker_room, range }
+    }
+
+    fn index(&self, index: usize) -> Option<usize> {
+        (index < self.len()).then(|| self.range.start + index)
+    }
+
+    /// Returns the number of cells in the view.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if the view has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Returns the range of indices of the viewed cells in the [`LockerRoom`].
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Locks cell at the index of the view with shared read access, like [`LockerRoom::read_cell`].
+    ///
+    /// This function will return `None` if the index is out of the view.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn read_cell(&self, index: usize) -> Option<ReadCellGuard<'a, T, S>> {
+        self.locker_room.read_cell(self.index(index)?)
+    }
+
+    /// Locks cell at the index of the view with exclusive write access, like [`LockerRoom::write_cell`].
+    ///
+    /// This function will return `None` if the index is out of the view.
+    #[must_use = "locking has no effect unless the guard is used"]
+    pub fn write_cell(&self, index: usize) -> Option<WriteCellGuard<'a, T, S>> {
+        self.locker_room.write_cell(self.index(index)?)
+    }
+
+    /// Divides the view into two at an index of the view, like [`slice::split_at`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at(self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len(), "mid > len");
+        let mid = self.range.start + mid;
+        (
+            Self::new(self.locker_room, self.range.start..mid),
+            Self::new(self.locker_room, mid..self.range.end),
+        )
+    }
+}
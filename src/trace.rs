@@ -0,0 +1,30 @@
+//! Helpers for formatting indices in `tracing` spans around lock operations and in deadlock reports.
+
+use std::fmt;
+
+/// Formats an index in spans and deadlock reports. It is set by `trace_indices` only when the index implements [`Debug`](fmt::Debug).
+pub(crate) type FmtIndex<Idx> = fn(&Idx, &mut fmt::Formatter<'_>) -> fmt::Result;
+
+/// Index recorded in a span or deadlock report. In spans it's formatted lazily, only if some subscriber is interested in them.
+#[cfg(any(
+    feature = "tracing",
+    all(feature = "deadlock-detect", debug_assertions)
+))]
+pub(crate) struct TraceIndex<'i, Idx>(pub(crate) &'i Idx, pub(crate) Option<FmtIndex<Idx>>);
+
+#[cfg(any(
+    feature = "tracing",
+    all(feature = "deadlock-detect", debug_assertions)
+))]
+impl<Idx> fmt::Debug for TraceIndex<'_, Idx> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(fmt_index) => fmt_index(self.0, f),
+            None => f.write_str("_"),
+        }
+    }
+}
+
+pub(crate) fn fmt_index<Idx: fmt::Debug>(index: &Idx, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(index, f)
+}
@@ -0,0 +1,14 @@
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/locker_room_not_sync.rs");
+    t.compile_fail("tests/ui/block_needs_usize.rs");
+    // Rustc qualifies the names of guards only if the async guards of the same names are compiled too. Guards of
+    // cell locks are wrapped with feature `deadlock-detect`, which changes the diagnostics as well.
+    #[cfg(all(feature = "async", not(feature = "deadlock-detect")))]
+    t.compile_fail("tests/ui/guard_not_send.rs");
+    #[cfg(feature = "async")]
+    t.compile_fail("tests/ui/guard_must_use.rs");
+    #[cfg(feature = "async")]
+    t.compile_fail("tests/ui/async/*.rs");
+}
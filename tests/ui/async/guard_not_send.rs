@@ -0,0 +1,12 @@
+use std::{cell::Cell, rc::Rc};
+
+use lockerroom::r#async::{MultiReadGuard, MultiWriteGuard, ReadCellGuard, WriteCellGuard};
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    assert_send::<ReadCellGuard<Vec<Cell<i32>>>>();
+    assert_send::<WriteCellGuard<Vec<Rc<i32>>>>();
+    assert_send::<MultiReadGuard<Vec<Cell<i32>>>>();
+    assert_send::<MultiWriteGuard<Vec<Rc<i32>>>>();
+}
@@ -0,0 +1,10 @@
+use std::cell::Cell;
+
+use lockerroom::r#async::{ReadCellGuard, WriteCellGuard};
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<ReadCellGuard<Vec<Cell<i32>>>>();
+    assert_sync::<WriteCellGuard<Vec<Cell<i32>>>>();
+}
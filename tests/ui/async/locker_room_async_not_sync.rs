@@ -0,0 +1,10 @@
+use std::{cell::Cell, rc::Rc};
+
+use lockerroom::LockerRoomAsync;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<LockerRoomAsync<Vec<Rc<i32>>>>();
+    assert_sync::<LockerRoomAsync<Vec<Cell<i32>>>>();
+}
@@ -0,0 +1,8 @@
+use std::collections::HashMap;
+
+use lockerroom::LockerRoom;
+
+fn main() {
+    // Blocks of consecutive cells exist only for `usize` indices.
+    let _ = LockerRoom::builder(HashMap::from([("a", 1)])).block(4);
+}
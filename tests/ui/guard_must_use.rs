@@ -0,0 +1,11 @@
+#![deny(unused_must_use)]
+
+use lockerroom::{sync::ReadCellGuard, LockerRoom};
+
+fn main() {
+    let locker_room: LockerRoom<_> = vec![String::new()].into();
+    locker_room.write_cell(0);
+    locker_room.read_cell(0).unwrap();
+    ReadCellGuard::map(locker_room.read_cell(0).unwrap(), String::as_str);
+    locker_room.lock_room();
+}
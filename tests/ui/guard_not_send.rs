@@ -0,0 +1,9 @@
+use lockerroom::sync::{ReadCellGuard, WriteCellGuard};
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    // Shadow locks of std can't be unlocked from another thread.
+    assert_send::<ReadCellGuard<Vec<i32>>>();
+    assert_send::<WriteCellGuard<Vec<i32>>>();
+}
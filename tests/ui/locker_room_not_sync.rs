@@ -0,0 +1,10 @@
+use std::{cell::Cell, rc::Rc};
+
+use lockerroom::LockerRoom;
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    assert_sync::<LockerRoom<Vec<Rc<i32>>>>();
+    assert_sync::<LockerRoom<Vec<Cell<i32>>>>();
+}